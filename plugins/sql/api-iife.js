@@ -1 +1 @@
-if("__TAURI__"in window){var __TAURI_PLUGIN_SQL__=function(){"use strict";async function e(e,t={},s){return window.__TAURI_INTERNALS__.invoke(e,t,s)}"function"==typeof SuppressedError&&SuppressedError;class t{constructor(e){this.path=e}static async load(s){const n=await e("plugin:sql|load",{db:s});return new t(n)}static get(e){return new t(e)}async execute(t,s){const[n,r]=await e("plugin:sql|execute",{db:this.path,query:t,values:s??[]});return{lastInsertId:r,rowsAffected:n}}async select(t,s){return await e("plugin:sql|select",{db:this.path,query:t,values:s??[]})}async close(t){return await e("plugin:sql|close",{db:t})}}return t}();Object.defineProperty(window.__TAURI__,"sql",{value:__TAURI_PLUGIN_SQL__})}
+if("__TAURI__"in window){var __TAURI_PLUGIN_SQL__=function(e){"use strict";function t(e,t,s,n){if("a"===s&&!n)throw new TypeError("Private accessor was defined without a getter");if("function"==typeof t?e!==t||!n:!t.has(e))throw new TypeError("Cannot read private member from an object whose class did not declare it");return"m"===s?n:"a"===s?n.call(e):n?n.value:t.get(e)}function s(e,t,s,n,i){if("function"==typeof t?e!==t||!i:!t.has(e))throw new TypeError("Cannot write private member to an object whose class did not declare it");return t.set(e,s),s}var n,i,r,a;function h(e,t=!1){return window.__TAURI_INTERNALS__.transformCallback(e,t)}"function"==typeof SuppressedError&&SuppressedError;class o{constructor(){this.__TAURI_CHANNEL_MARKER__=!0,n.set(this,(()=>{})),i.set(this,0),r.set(this,{}),this.id=h((({message:e,id:a})=>{if(a===t(this,i,"f")){s(this,i,a+1),t(this,n,"f").call(this,e);const o=Object.keys(t(this,r,"f"));if(o.length>0){let e=a+1;for(const s of o.sort()){if(parseInt(s)!==e)break;{const i=t(this,r,"f")[s];delete t(this,r,"f")[s],t(this,n,"f").call(this,i),e+=1}}s(this,i,e)}}else t(this,r,"f")[a.toString()]=e}))}set onmessage(e){s(this,n,e)}get onmessage(){return t(this,n,"f")}toJSON(){return`__CHANNEL__:${this.id}`}}async function d(e,t={},s){return window.__TAURI_INTERNALS__.invoke(e,t,s)}n=new WeakMap,i=new WeakMap,r=new WeakMap;class l{get rid(){return t(this,a,"f")}constructor(e){a.set(this,void 0),s(this,a,e)}async close(){return d("plugin:resources|close",{rid:this.rid})}}a=new WeakMap;class c{constructor(e){this.path=e}static async load(e){const t=await d("plugin:sql|load",{db:e});return new c(t)}static get(e){return new c(e)}async execute(e,t){const[s,n]=await d("plugin:sql|execute",{db:this.path,query:e,values:t??[]});return{lastInsertId:n,rowsAffected:s}}async executeBatch(e,t){const[s,n]=await d("plugin:sql|execute_batch",{db:this.path,query:e,values:t});return{lastInsertIds:n,rowsAffected:s}}async prepare(e){const t=await d("plugin:sql|prepare",{db:this.path,query:e});return new g(t)}async select(e,t){return await d("plugin:sql|select",{db:this.path,query:e,values:t??[]})}async selectStream(e,t,s){let n,i=!1,r=0,a=0,h=()=>{},y=()=>{};const m=new Promise(((e,t)=>{h=e,y=t})),w=()=>{n&&!i&&a>0&&(d("plugin:sql|request_rows",{cursor:n.rid,chunks:a}).catch((()=>{})),a=0)},p=new o;p.onmessage=e=>{if(!i){switch(e.event){case"Rows":return r+=e.data.length,s.onRows(e.data),a+=1,void w();case"Done":h(e.data.count);break;case"Error":y(e.data.message)}i=!0,n?.close()}};const l=await d("plugin:sql|select_stream",{db:this.path,query:e,values:t,chunkSize:s.chunkSize,prefetch:s.prefetch,transaction:s.transaction?.rid,onEvent:p});return n=new f(l,m,(()=>!i&&(i=!0,h(r),!0))),i?await n.close():w(),n}async migrationStatus(){return await d("plugin:sql|migration_status",{db:this.path})}async appliedMigrations(){return(await this.migrationStatus()).filter((e=>e.applied))}async migrateTo(e){await d("plugin:sql|migrate_to",{db:this.path,version:e})}async beginTransaction(){const e=await d("plugin:sql|begin_transaction",{db:this.path});return new u(this.path,e)}async close(e){return await d("plugin:sql|close",{db:e})}}class u{constructor(e,t){this.path=e,this.rid=t}async execute(e,t){const[s,n]=await d("plugin:sql|execute",{db:this.path,query:e,values:t??[],transaction:this.rid});return{lastInsertId:n,rowsAffected:s}}async select(e,t){return await d("plugin:sql|select",{db:this.path,query:e,values:t??[],transaction:this.rid})}async executeBatch(e,t){const[s,n]=await d("plugin:sql|execute_batch",{db:this.path,query:e,values:t,transaction:this.rid});return{lastInsertIds:n,rowsAffected:s}}async commit(){await d("plugin:sql|commit",{transaction:this.rid})}async rollback(){await d("plugin:sql|rollback",{transaction:this.rid})}}class g extends l{async execute(e,t){const{rowsAffected:s,lastInsertIds:n}=await this.executeBatch([e??[]],t);return{lastInsertId:n[0],rowsAffected:s}}async executeBatch(e,t){const[s,n]=await d("plugin:sql|execute_prepared",{statement:this.rid,values:e,transaction:t?.rid});return{lastInsertIds:n,rowsAffected:s}}async select(e,t){return await d("plugin:sql|select_prepared",{statement:this.rid,values:e??[],transaction:t?.rid})}}class f extends l{constructor(e,t,s){super(e),this.done=t,this.onCancel=s}async cancel(){this.onCancel()&&await this.close()}}return e.Cursor=f,e.PreparedStatement=g,e.Transaction=u,e.default=c,Object.defineProperty(e,"__esModule",{value:!0}),e}({});Object.defineProperty(window.__TAURI__,"sql",{value:__TAURI_PLUGIN_SQL__})}
@@ -2,7 +2,23 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-const COMMANDS: &[&str] = &["load", "execute", "select", "close"];
+const COMMANDS: &[&str] = &[
+    "load",
+    "migration_status",
+    "migrate_to",
+    "execute",
+    "select",
+    "execute_batch",
+    "prepare",
+    "execute_prepared",
+    "select_prepared",
+    "select_stream",
+    "request_rows",
+    "begin_transaction",
+    "commit",
+    "rollback",
+    "close",
+];
 
 fn main() {
     tauri_plugin::Builder::new(COMMANDS)
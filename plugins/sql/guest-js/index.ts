@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-import { invoke } from '@tauri-apps/api/core'
+import { Channel, invoke, Resource } from '@tauri-apps/api/core'
 
 export interface QueryResult {
   /** The number of rows affected by the query. */
@@ -18,6 +18,61 @@ export interface QueryResult {
   lastInsertId: number
 }
 
+export interface BatchResult {
+  /** The total number of rows affected by the executions. */
+  rowsAffected: number
+  /** The last inserted `id` of each execution, see {@linkcode QueryResult.lastInsertId}. */
+  lastInsertIds: number[]
+}
+
+export interface SelectStreamOptions<T> {
+  /** The number of rows passed to `onRows` at once, 100 by default and at most 10 000. */
+  chunkSize?: number
+  /**
+   * The number of chunks sent ahead of `onRows`, 2 by default.
+   * The next chunks are only sent once `onRows` returned.
+   */
+  prefetch?: number
+  /**
+   * Runs the query in the transaction, which cannot be used for anything else
+   * until the cursor is done or closed.
+   */
+  transaction?: Transaction
+  /** Called with each chunk of rows, in order. */
+  onRows: (rows: T[]) => void
+}
+
+/**
+ * A selected integer: a number in the safe range of JavaScript numbers
+ * (see `Number.isSafeInteger`), otherwise a decimal string so that no precision is lost.
+ *
+ * This is decided for each value, so a column can mix numbers and strings:
+ * `BigInt(value)` converts both.
+ */
+export type SqlInteger = number | string
+
+/**
+ * A selected float: a number, or one of the strings `NaN`, `Infinity` and `-Infinity`.
+ */
+export type SqlFloat = number | 'NaN' | 'Infinity' | '-Infinity'
+
+type CursorEvent<T> =
+  | { event: 'Rows'; data: T[] }
+  | { event: 'Done'; data: { count: number } }
+  | { event: 'Error'; data: { message: string } }
+
+/**
+ * The state of a migration of a database, see {@linkcode Database.migrationStatus}.
+ */
+export interface MigrationStatus {
+  version: number
+  /** Empty for an applied migration which is not registered, e.g. one added by a newer version of the app. */
+  description: string
+  applied: boolean
+  /** Whether a down migration reverts it. */
+  reversible: boolean
+}
+
 /**
  * **Database**
  *
@@ -120,6 +175,60 @@ export default class Database {
     }
   }
 
+  /**
+   * **executeBatch**
+   *
+   * Executes a SQL expression once per array of bind values, in a single transaction
+   * which is rolled back if one of the executions fails.
+   *
+   * @example
+   * ```ts
+   * const result = await db.executeBatch(
+   *    "INSERT into todos (id, title) VALUES ($1, $2)",
+   *    todos.map((todo) => [todo.id, todo.title])
+   * );
+   * ```
+   */
+  async executeBatch(
+    query: string,
+    bindValues: unknown[][]
+  ): Promise<BatchResult> {
+    const [rowsAffected, lastInsertIds] = await invoke<[number, number[]]>(
+      'plugin:sql|execute_batch',
+      {
+        db: this.path,
+        query,
+        values: bindValues
+      }
+    )
+    return {
+      lastInsertIds,
+      rowsAffected
+    }
+  }
+
+  /**
+   * **prepare**
+   *
+   * Saves a SQL statement which can be executed many times with different bind values,
+   * after checking that the database can prepare it. The statement is not pinned to a connection:
+   * each connection prepares it on its first execution and reuses it for the next ones.
+   *
+   * @example
+   * ```ts
+   * const insert = await db.prepare("INSERT into todos (id, title) VALUES ($1, $2)");
+   * await insert.execute([todo.id, todo.title]);
+   * await insert.close();
+   * ```
+   */
+  async prepare(query: string): Promise<PreparedStatement> {
+    const rid = await invoke<number>('plugin:sql|prepare', {
+      db: this.path,
+      query
+    })
+    return new PreparedStatement(rid)
+  }
+
   /**
    * **select**
    *
@@ -148,6 +257,170 @@ export default class Database {
     return result
   }
 
+  /**
+   * **selectStream**
+   *
+   * Passes in a SELECT query to the database for execution,
+   * streaming the rows in chunks rather than returning them all at once.
+   *
+   * @example
+   * ```ts
+   * const cursor = await db.selectStream<Todo>("SELECT * from todos", [], {
+   *   chunkSize: 500,
+   *   onRows: (rows) => table.append(rows)
+   * });
+   * const count = await cursor.done;
+   * ```
+   */
+  async selectStream<T>(
+    query: string,
+    bindValues: unknown[],
+    options: SelectStreamOptions<T>
+  ): Promise<Cursor> {
+    let cursor: Cursor | undefined
+    let finished = false
+    let received = 0
+    // the chunks handled since the last request for more
+    let handled = 0
+    let resolve: (count: number) => void = () => {}
+    let reject: (message: string) => void = () => {}
+    const done = new Promise<number>((res, rej) => {
+      resolve = res
+      reject = rej
+    })
+
+    const requestRows = () => {
+      if (cursor && !finished && handled > 0) {
+        // fails if the cursor was closed meanwhile
+        invoke('plugin:sql|request_rows', {
+          cursor: cursor.rid,
+          chunks: handled
+        }).catch(() => {})
+        handled = 0
+      }
+    }
+
+    const onEvent = new Channel<CursorEvent<T>>()
+    onEvent.onmessage = (message) => {
+      if (finished) {
+        return
+      }
+      switch (message.event) {
+        case 'Rows':
+          received += message.data.length
+          options.onRows(message.data)
+          handled += 1
+          requestRows()
+          return
+        case 'Done':
+          resolve(message.data.count)
+          break
+        case 'Error':
+          reject(message.data.message)
+          break
+      }
+      finished = true
+      void cursor?.close()
+    }
+
+    const rid = await invoke<number>('plugin:sql|select_stream', {
+      db: this.path,
+      query,
+      values: bindValues,
+      chunkSize: options.chunkSize,
+      prefetch: options.prefetch,
+      transaction: options.transaction?.rid,
+      onEvent
+    })
+    cursor = new Cursor(rid, done, () => {
+      if (finished) {
+        return false
+      }
+      finished = true
+      resolve(received)
+      return true
+    })
+    // the rows may all have been received already
+    if (finished) {
+      await cursor.close()
+    } else {
+      requestRows()
+    }
+    return cursor
+  }
+
+  /**
+   * **migrationStatus**
+   *
+   * Returns the migrations registered for this database in the rust side plugin builder
+   * and the migrations applied to it, by version.
+   *
+   * @example
+   * ```ts
+   * const pending = (await db.migrationStatus()).filter((m) => !m.applied);
+   * ```
+   */
+  async migrationStatus(): Promise<MigrationStatus[]> {
+    return await invoke<MigrationStatus[]>('plugin:sql|migration_status', {
+      db: this.path
+    })
+  }
+
+  /**
+   * **appliedMigrations**
+   *
+   * Returns the migrations applied to this database, by version.
+   */
+  async appliedMigrations(): Promise<MigrationStatus[]> {
+    return (await this.migrationStatus()).filter((m) => m.applied)
+  }
+
+  /**
+   * **migrateTo**
+   *
+   * Migrates this database to `version`, reverting the applied migrations above it
+   * with their down migrations and applying the pending migrations up to it.
+   * Loading the database again does not undo it until the app restarts.
+   *
+   * @example
+   * ```ts
+   * // revert every migration
+   * await db.migrateTo(0);
+   * ```
+   */
+  async migrateTo(version: number): Promise<void> {
+    await invoke('plugin:sql|migrate_to', {
+      db: this.path,
+      version
+    })
+  }
+
+  /**
+   * **beginTransaction**
+   *
+   * Starts a transaction on a connection dedicated to it,
+   * which is rolled back if this webview is closed before it is committed.
+   *
+   * @example
+   * ```ts
+   * const tx = await db.beginTransaction();
+   * try {
+   *   await tx.execute("UPDATE accounts SET balance = balance - $1 WHERE id = $2", [amount, from]);
+   *   await tx.execute("UPDATE accounts SET balance = balance + $1 WHERE id = $2", [amount, to]);
+   *   await tx.commit();
+   * } catch (e) {
+   *   await tx.rollback();
+   *   throw e;
+   * }
+   * ```
+   */
+  async beginTransaction(): Promise<Transaction> {
+    const rid = await invoke<number>('plugin:sql|begin_transaction', {
+      db: this.path
+    })
+    return new Transaction(this.path, rid)
+  }
+
   /**
    * **close**
    *
@@ -166,3 +439,193 @@ export default class Database {
     return success
   }
 }
+
+/**
+ * **Transaction**
+ *
+ * A transaction started with {@linkcode Database.beginTransaction}.
+ * Its queries run on the connection of the transaction until it is committed or rolled back.
+ */
+export class Transaction {
+  path: string
+  rid: number
+  constructor(path: string, rid: number) {
+    this.path = path
+    this.rid = rid
+  }
+
+  /**
+   * **execute**
+   *
+   * Passes a SQL expression to the database for execution in this transaction,
+   * see {@linkcode Database.execute}.
+   */
+  async execute(query: string, bindValues?: unknown[]): Promise<QueryResult> {
+    const [rowsAffected, lastInsertId] = await invoke<[number, number]>(
+      'plugin:sql|execute',
+      {
+        db: this.path,
+        query,
+        values: bindValues ?? [],
+        transaction: this.rid
+      }
+    )
+    return {
+      lastInsertId,
+      rowsAffected
+    }
+  }
+
+  /**
+   * **select**
+   *
+   * Passes in a SELECT query to the database for execution in this transaction,
+   * see {@linkcode Database.select}.
+   */
+  async select<T>(query: string, bindValues?: unknown[]): Promise<T> {
+    return await invoke<T>('plugin:sql|select', {
+      db: this.path,
+      query,
+      values: bindValues ?? [],
+      transaction: this.rid
+    })
+  }
+
+  /**
+   * **executeBatch**
+   *
+   * Executes a SQL expression once per array of bind values in this transaction,
+   * see {@linkcode Database.executeBatch}.
+   */
+  async executeBatch(
+    query: string,
+    bindValues: unknown[][]
+  ): Promise<BatchResult> {
+    const [rowsAffected, lastInsertIds] = await invoke<[number, number[]]>(
+      'plugin:sql|execute_batch',
+      {
+        db: this.path,
+        query,
+        values: bindValues,
+        transaction: this.rid
+      }
+    )
+    return {
+      lastInsertIds,
+      rowsAffected
+    }
+  }
+
+  /**
+   * **commit**
+   *
+   * Commits the transaction.
+   */
+  async commit(): Promise<void> {
+    await invoke('plugin:sql|commit', { transaction: this.rid })
+  }
+
+  /**
+   * **rollback**
+   *
+   * Rolls back the transaction.
+   */
+  async rollback(): Promise<void> {
+    await invoke('plugin:sql|rollback', { transaction: this.rid })
+  }
+}
+
+/**
+ * **PreparedStatement**
+ *
+ * A statement saved with {@linkcode Database.prepare}.
+ * The queries run in the given transaction if any, which must belong to the same database.
+ */
+export class PreparedStatement extends Resource {
+  /**
+   * **execute**
+   *
+   * Executes the statement, see {@linkcode Database.execute}.
+   */
+  async execute(
+    bindValues?: unknown[],
+    transaction?: Transaction
+  ): Promise<QueryResult> {
+    const { rowsAffected, lastInsertIds } = await this.executeBatch(
+      [bindValues ?? []],
+      transaction
+    )
+    return {
+      lastInsertId: lastInsertIds[0],
+      rowsAffected
+    }
+  }
+
+  /**
+   * **executeBatch**
+   *
+   * Executes the statement once per array of bind values, see {@linkcode Database.executeBatch}.
+   */
+  async executeBatch(
+    bindValues: unknown[][],
+    transaction?: Transaction
+  ): Promise<BatchResult> {
+    const [rowsAffected, lastInsertIds] = await invoke<[number, number[]]>(
+      'plugin:sql|execute_prepared',
+      {
+        statement: this.rid,
+        values: bindValues,
+        transaction: transaction?.rid
+      }
+    )
+    return {
+      lastInsertIds,
+      rowsAffected
+    }
+  }
+
+  /**
+   * **select**
+   *
+   * Executes the statement as a SELECT query, see {@linkcode Database.select}.
+   */
+  async select<T>(bindValues?: unknown[], transaction?: Transaction): Promise<T> {
+    return await invoke<T>('plugin:sql|select_prepared', {
+      statement: this.rid,
+      values: bindValues ?? [],
+      transaction: transaction?.rid
+    })
+  }
+}
+
+/**
+ * **Cursor**
+ *
+ * The rows of a query streamed with {@linkcode Database.selectStream}.
+ */
+export class Cursor extends Resource {
+  /**
+   * Resolves to the number of rows once they were all passed to `onRows`,
+   * or to the number of rows passed so far if the cursor is cancelled.
+   * Rejects with the error of the query.
+   */
+  readonly done: Promise<number>
+  private readonly onCancel: () => boolean
+
+  constructor(rid: number, done: Promise<number>, onCancel: () => boolean) {
+    super(rid)
+    this.done = done
+    this.onCancel = onCancel
+  }
+
+  /**
+   * **cancel**
+   *
+   * Stops streaming the rows, `onRows` is not called anymore.
+   */
+  async cancel(): Promise<void> {
+    if (this.onCancel()) {
+      await this.close()
+    }
+  }
+}
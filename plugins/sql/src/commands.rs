@@ -2,12 +2,22 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
+use std::sync::Arc;
+
 use indexmap::IndexMap;
+use serde::Serialize;
 use serde_json::Value as JsonValue;
 use sqlx::migrate::Migrator;
-use tauri::{command, AppHandle, Runtime, State};
+use tauri::{
+    async_runtime::JoinHandle, command, ipc::Channel, AppHandle, Manager, Resource, ResourceId,
+    Runtime, State, Webview,
+};
+use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard, Semaphore};
 
-use crate::{DbInstances, DbPool, Error, LastInsertId, Migrations};
+use crate::{
+    wrapper::DbTransaction, DbInstances, DbPool, Error, LastInsertId, MigrationStatus, Migrations,
+    SqlExt,
+};
 
 #[command]
 pub(crate) async fn load<R: Runtime>(
@@ -18,9 +28,14 @@ pub(crate) async fn load<R: Runtime>(
 ) -> Result<String, crate::Error> {
     let pool = DbPool::connect(&db, &app).await?;
 
-    if let Some(migrations) = migrations.0.lock().await.remove(&db) {
-        let migrator = Migrator::new(migrations).await?;
-        pool.migrate(&migrator).await?;
+    // only the first load migrates the database, so loading it again does not undo `migrate_to`
+    if !migrations.migrated.lock().await.contains(&db) {
+        let registered = migrations.registered.lock().await.get(&db).cloned();
+        if let Some(registered) = registered {
+            let migrator = Migrator::new(registered).await?;
+            pool.migrate(&migrator).await?;
+        }
+        migrations.migrated.lock().await.insert(db.clone());
     }
 
     db_instances.0.write().await.insert(db.clone(), pool);
@@ -28,6 +43,23 @@ pub(crate) async fn load<R: Runtime>(
     Ok(db)
 }
 
+#[command]
+pub(crate) async fn migration_status<R: Runtime>(
+    app: AppHandle<R>,
+    db: String,
+) -> Result<Vec<MigrationStatus>, crate::Error> {
+    app.migrations(db).status().await
+}
+
+#[command]
+pub(crate) async fn migrate_to<R: Runtime>(
+    app: AppHandle<R>,
+    db: String,
+    version: i64,
+) -> Result<(), crate::Error> {
+    app.migrations(db).migrate_to(version).await
+}
+
 /// Allows the database connection(s) to be closed; if no database
 /// name is passed in then _all_ database connection pools will be
 /// shut down.
@@ -52,14 +84,22 @@ pub(crate) async fn close(
     Ok(true)
 }
 
-/// Execute a command against the database
+/// Execute a command against the database, in the given transaction if any.
 #[command]
-pub(crate) async fn execute(
+pub(crate) async fn execute<R: Runtime>(
+    webview: Webview<R>,
     db_instances: State<'_, DbInstances>,
     db: String,
     query: String,
     values: Vec<JsonValue>,
+    transaction: Option<ResourceId>,
 ) -> Result<(u64, LastInsertId), crate::Error> {
+    if let Some(rid) = transaction {
+        let transaction = get_transaction(&webview, rid, &db)?;
+        let mut transaction = transaction.lock().await?;
+        return transaction.execute(query, values).await;
+    }
+
     let instances = db_instances.0.read().await;
 
     let db = instances.get(&db).ok_or(Error::DatabaseNotLoaded(db))?;
@@ -67,14 +107,683 @@ pub(crate) async fn execute(
 }
 
 #[command]
-pub(crate) async fn select(
+pub(crate) async fn select<R: Runtime>(
+    webview: Webview<R>,
     db_instances: State<'_, DbInstances>,
     db: String,
     query: String,
     values: Vec<JsonValue>,
+    transaction: Option<ResourceId>,
 ) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+    if let Some(rid) = transaction {
+        let transaction = get_transaction(&webview, rid, &db)?;
+        let mut transaction = transaction.lock().await?;
+        return transaction.select(query, values).await;
+    }
+
     let instances = db_instances.0.read().await;
 
     let db = instances.get(&db).ok_or(Error::DatabaseNotLoaded(db))?;
     db.select(query, values).await
 }
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", content = "data")]
+pub(crate) enum CursorEvent {
+    Rows(Vec<IndexMap<String, JsonValue>>),
+    /// All the rows were sent.
+    Done {
+        count: usize,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Streams the selected rows to `on_event` in chunks of `chunk_size` rows, 100 by default and at most 10 000.
+///
+/// Only `prefetch` chunks, 2 by default, are sent before the webview requests more with [`request_rows`].
+/// Returns the cursor, which belongs to the calling webview and stops streaming when closed.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn select_stream<R: Runtime>(
+    webview: Webview<R>,
+    db_instances: State<'_, DbInstances>,
+    db: String,
+    query: String,
+    values: Vec<JsonValue>,
+    chunk_size: Option<usize>,
+    prefetch: Option<u32>,
+    transaction: Option<ResourceId>,
+    on_event: Channel<CursorEvent>,
+) -> Result<ResourceId, crate::Error> {
+    let chunk_size = chunk_size.unwrap_or(100).clamp(1, 10_000);
+    let credits = Arc::new(Semaphore::new(prefetch.unwrap_or(2) as usize));
+    let source = match transaction {
+        Some(rid) => {
+            let transaction = get_transaction(&webview, rid, &db)?;
+            transaction.start_cursor(credits.clone())?;
+            CursorSource::Transaction(transaction)
+        }
+        None => {
+            let instances = db_instances.0.read().await;
+            CursorSource::Pool(
+                instances
+                    .get(&db)
+                    .ok_or(Error::DatabaseNotLoaded(db))?
+                    .clone(),
+            )
+        }
+    };
+
+    let task = tauri::async_runtime::spawn({
+        let credits = credits.clone();
+        async move {
+            stream(source, query, values, chunk_size, &credits, |event| {
+                on_event.send(event).is_ok()
+            })
+            .await
+        }
+    });
+
+    Ok(webview.resources_table().add(Cursor { task, credits }))
+}
+
+/// Lets the cursor send `chunks` more chunks of rows, once the webview handled the previous ones.
+#[command]
+pub(crate) async fn request_rows<R: Runtime>(
+    webview: Webview<R>,
+    cursor: ResourceId,
+    chunks: u32,
+) -> Result<(), crate::Error> {
+    let cursor = webview.resources_table().get::<Cursor>(cursor)?;
+    cursor.credits.add_permits(chunks as usize);
+    Ok(())
+}
+
+/// Sends the rows of the query to `send` in chunks, each chunk taking one of the `credits`.
+///
+/// Stops when the credits are closed or when `send` fails, e.g. because the webview is gone,
+/// which closes the credits. They are also closed once all the rows are sent.
+async fn stream(
+    source: CursorSource,
+    query: String,
+    values: Vec<JsonValue>,
+    chunk_size: usize,
+    credits: &Semaphore,
+    send: impl Fn(CursorEvent) -> bool,
+) {
+    let send = &send;
+    let on_chunk = |rows| async move {
+        match credits.acquire().await {
+            Ok(credit) => {
+                credit.forget();
+                if send(CursorEvent::Rows(rows)) {
+                    return true;
+                }
+                credits.close();
+                false
+            }
+            Err(_) => false,
+        }
+    };
+    let result = match source {
+        CursorSource::Pool(pool) => {
+            pool.select_chunks(query, values, chunk_size, on_chunk)
+                .await
+        }
+        CursorSource::Transaction(transaction) => match transaction.lock_for_cursor().await {
+            Ok(mut transaction) => {
+                transaction
+                    .select_chunks(query, values, chunk_size, on_chunk)
+                    .await
+            }
+            Err(e) => Err(e),
+        },
+    };
+    if credits.is_closed() {
+        return;
+    }
+    // the cursor is done, its transaction can be used again by the time the webview knows it
+    credits.close();
+    send(match result {
+        Ok(count) => CursorEvent::Done { count },
+        Err(e) => CursorEvent::Error {
+            message: e.to_string(),
+        },
+    });
+}
+
+/// Execute a command once per list of bind values, in a single transaction.
+///
+/// The command runs in the given transaction if any, otherwise in a new transaction
+/// which is committed if all the executions succeed.
+#[command]
+pub(crate) async fn execute_batch<R: Runtime>(
+    webview: Webview<R>,
+    db_instances: State<'_, DbInstances>,
+    db: String,
+    query: String,
+    values: Vec<Vec<JsonValue>>,
+    transaction: Option<ResourceId>,
+) -> Result<(u64, Vec<LastInsertId>), crate::Error> {
+    run_batch(&webview, &db_instances, db, query, values, transaction).await
+}
+
+/// Saves a query which can then be executed with [`execute_prepared`] and [`select_prepared`],
+/// after checking that the database can prepare it.
+///
+/// The statement belongs to the calling webview.
+#[command]
+pub(crate) async fn prepare<R: Runtime>(
+    webview: Webview<R>,
+    db_instances: State<'_, DbInstances>,
+    db: String,
+    query: String,
+) -> Result<ResourceId, crate::Error> {
+    let instances = db_instances.0.read().await;
+
+    let pool = instances
+        .get(&db)
+        .ok_or_else(|| Error::DatabaseNotLoaded(db.clone()))?;
+    pool.prepare(&query).await?;
+    Ok(webview
+        .resources_table()
+        .add(PreparedStatement { db, query }))
+}
+
+/// Execute a prepared statement once per list of bind values, see [`execute_batch`].
+#[command]
+pub(crate) async fn execute_prepared<R: Runtime>(
+    webview: Webview<R>,
+    db_instances: State<'_, DbInstances>,
+    statement: ResourceId,
+    values: Vec<Vec<JsonValue>>,
+    transaction: Option<ResourceId>,
+) -> Result<(u64, Vec<LastInsertId>), crate::Error> {
+    let statement = webview
+        .resources_table()
+        .get::<PreparedStatement>(statement)?;
+    run_batch(
+        &webview,
+        &db_instances,
+        statement.db.clone(),
+        statement.query.clone(),
+        values,
+        transaction,
+    )
+    .await
+}
+
+#[command]
+pub(crate) async fn select_prepared<R: Runtime>(
+    webview: Webview<R>,
+    db_instances: State<'_, DbInstances>,
+    statement: ResourceId,
+    values: Vec<JsonValue>,
+    transaction: Option<ResourceId>,
+) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+    let statement = webview
+        .resources_table()
+        .get::<PreparedStatement>(statement)?;
+    select(
+        webview,
+        db_instances,
+        statement.db.clone(),
+        statement.query.clone(),
+        values,
+        transaction,
+    )
+    .await
+}
+
+async fn run_batch<R: Runtime>(
+    webview: &Webview<R>,
+    db_instances: &DbInstances,
+    db: String,
+    query: String,
+    values: Vec<Vec<JsonValue>>,
+    transaction: Option<ResourceId>,
+) -> Result<(u64, Vec<LastInsertId>), crate::Error> {
+    if let Some(rid) = transaction {
+        let transaction = get_transaction(webview, rid, &db)?;
+        let mut transaction = transaction.lock().await?;
+        return transaction.execute_batch(query, values).await;
+    }
+
+    let instances = db_instances.0.read().await;
+
+    let db = instances.get(&db).ok_or(Error::DatabaseNotLoaded(db))?;
+    db.execute_batch(query, values).await
+}
+
+/// Starts a transaction on a connection dedicated to it until it is committed or rolled back.
+///
+/// The transaction belongs to the calling webview and is rolled back if it is closed first.
+#[command]
+pub(crate) async fn begin_transaction<R: Runtime>(
+    webview: Webview<R>,
+    db_instances: State<'_, DbInstances>,
+    db: String,
+) -> Result<ResourceId, crate::Error> {
+    let instances = db_instances.0.read().await;
+
+    let pool = instances
+        .get(&db)
+        .ok_or_else(|| Error::DatabaseNotLoaded(db.clone()))?;
+    let transaction = pool.begin().await?;
+    Ok(webview.resources_table().add(TransactionResource {
+        db,
+        transaction: Mutex::new(Some(transaction)),
+        cursor: Default::default(),
+    }))
+}
+
+#[command]
+pub(crate) async fn commit<R: Runtime>(
+    webview: Webview<R>,
+    transaction: ResourceId,
+) -> Result<(), crate::Error> {
+    finish_transaction(&webview, transaction)
+        .await?
+        .commit()
+        .await
+}
+
+#[command]
+pub(crate) async fn rollback<R: Runtime>(
+    webview: Webview<R>,
+    transaction: ResourceId,
+) -> Result<(), crate::Error> {
+    finish_transaction(&webview, transaction)
+        .await?
+        .rollback()
+        .await
+}
+
+/// Takes the transaction `rid` of the webview to commit or roll it back.
+///
+/// The transaction is only removed once it can be finished, so it is still usable when a cursor keeps it busy.
+async fn finish_transaction<R: Runtime>(
+    webview: &Webview<R>,
+    rid: ResourceId,
+) -> Result<DbTransaction, crate::Error> {
+    let transaction = webview.resources_table().get::<TransactionResource>(rid)?;
+    let finished = transaction.finish().await?;
+    // the webview may have closed it meanwhile
+    let _ = webview.resources_table().close(rid);
+    Ok(finished)
+}
+
+/// A transaction pinned to a connection for the webview, which is rolled back when dropped.
+pub(crate) struct TransactionResource {
+    /// The database the transaction was started on.
+    db: String,
+    transaction: Mutex<Option<DbTransaction>>,
+    /// The credits of the last cursor streaming the rows of the transaction, which keeps it locked until it is done.
+    cursor: std::sync::Mutex<Option<Arc<Semaphore>>>,
+}
+
+impl TransactionResource {
+    /// Fails if the transaction was started on another database than `db`.
+    fn check_database(&self, db: &str) -> Result<(), crate::Error> {
+        if self.db == db {
+            Ok(())
+        } else {
+            Err(Error::TransactionDatabaseMismatch(self.db.clone()))
+        }
+    }
+
+    /// Fails if a cursor is streaming the rows of the transaction,
+    /// instead of waiting for the webview to request all of them.
+    fn check_cursor(&self) -> Result<(), crate::Error> {
+        match &*self.cursor.lock().unwrap() {
+            Some(credits) if !credits.is_closed() => Err(Error::TransactionBusy),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reserves the transaction for the cursor with the given `credits` until it is done or closed.
+    fn start_cursor(&self, credits: Arc<Semaphore>) -> Result<(), crate::Error> {
+        self.check_cursor()?;
+        self.cursor.lock().unwrap().replace(credits);
+        Ok(())
+    }
+
+    /// Locks the transaction, failing if it was already committed or rolled back
+    /// or if it is reserved by a cursor.
+    async fn lock(&self) -> Result<MappedMutexGuard<'_, DbTransaction>, crate::Error> {
+        self.check_cursor()?;
+        self.lock_for_cursor().await
+    }
+
+    /// Locks the transaction for the cursor it is reserved by, see [`TransactionResource::start_cursor`].
+    async fn lock_for_cursor(&self) -> Result<MappedMutexGuard<'_, DbTransaction>, crate::Error> {
+        MutexGuard::try_map(self.transaction.lock().await, Option::as_mut)
+            .map_err(|_| Error::TransactionFinished)
+    }
+
+    /// Takes the transaction to commit or roll it back, failing if it is reserved by a cursor.
+    async fn finish(&self) -> Result<DbTransaction, crate::Error> {
+        self.check_cursor()?;
+        self.transaction
+            .lock()
+            .await
+            .take()
+            .ok_or(Error::TransactionFinished)
+    }
+}
+
+impl Resource for TransactionResource {}
+
+/// Returns the transaction `rid` of the webview, checking that it was started on the database `db`.
+fn get_transaction<R: Runtime>(
+    webview: &Webview<R>,
+    rid: ResourceId,
+    db: &str,
+) -> Result<Arc<TransactionResource>, crate::Error> {
+    let transaction = webview.resources_table().get::<TransactionResource>(rid)?;
+    transaction.check_database(db)?;
+    Ok(transaction)
+}
+
+/// A query saved for the webview by [`prepare`], which runs in the transaction or on the pool given to each execution.
+///
+/// It is not pinned to a connection: each connection prepares the query the first time it executes it
+/// and reuses its cached statement for the next executions.
+pub(crate) struct PreparedStatement {
+    db: String,
+    query: String,
+}
+
+impl Resource for PreparedStatement {}
+
+enum CursorSource {
+    Pool(DbPool),
+    /// The transaction stays locked until all the rows are fetched or the cursor is closed,
+    /// other uses of the transaction fail meanwhile.
+    Transaction(Arc<TransactionResource>),
+}
+
+/// The task streaming the rows of [`select_stream`], aborted when the cursor is closed.
+pub(crate) struct Cursor {
+    task: JoinHandle<()>,
+    /// The number of chunks the task may still send.
+    credits: Arc<Semaphore>,
+}
+
+impl Resource for Cursor {
+    fn close(self: Arc<Self>) {
+        self.credits.close();
+        self.task.abort();
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use serde_json::json;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+
+    /// An in-memory database with a single connection, so it is shared by the transactions and the pool.
+    async fn pool() -> DbPool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let pool = DbPool::Sqlite(pool);
+        pool.execute("CREATE TABLE items (name TEXT NOT NULL)".into(), Vec::new())
+            .await
+            .unwrap();
+        pool
+    }
+
+    async fn count(pool: &DbPool) -> JsonValue {
+        let rows = pool
+            .select("SELECT COUNT(*) AS count FROM items".into(), Vec::new())
+            .await
+            .unwrap();
+        rows[0]["count"].clone()
+    }
+
+    async fn begin(pool: &DbPool) -> TransactionResource {
+        let transaction = TransactionResource {
+            db: "sqlite:test.db".into(),
+            transaction: Mutex::new(Some(pool.begin().await.unwrap())),
+            cursor: Default::default(),
+        };
+        transaction
+            .lock()
+            .await
+            .unwrap()
+            .execute(
+                "INSERT INTO items VALUES (?)".into(),
+                vec![json!("transaction")],
+            )
+            .await
+            .unwrap();
+        transaction
+    }
+
+    #[test]
+    fn it_commits_transactions() {
+        tauri::async_runtime::block_on(async {
+            let pool = pool().await;
+            let transaction = begin(&pool).await;
+            transaction.finish().await.unwrap().commit().await.unwrap();
+            assert_eq!(count(&pool).await, json!(1));
+        })
+    }
+
+    #[test]
+    fn it_rolls_back_transactions() {
+        tauri::async_runtime::block_on(async {
+            let pool = pool().await;
+            let transaction = begin(&pool).await;
+            transaction
+                .finish()
+                .await
+                .unwrap()
+                .rollback()
+                .await
+                .unwrap();
+            assert_eq!(count(&pool).await, json!(0));
+        })
+    }
+
+    #[test]
+    fn it_rolls_back_dropped_transactions() {
+        tauri::async_runtime::block_on(async {
+            let pool = pool().await;
+            drop(begin(&pool).await);
+            assert_eq!(count(&pool).await, json!(0));
+        })
+    }
+
+    #[test]
+    fn it_fails_on_finished_transactions() {
+        tauri::async_runtime::block_on(async {
+            let pool = pool().await;
+            let transaction = begin(&pool).await;
+            transaction.finish().await.unwrap().commit().await.unwrap();
+
+            assert!(matches!(
+                transaction.lock().await,
+                Err(Error::TransactionFinished)
+            ));
+            assert!(matches!(
+                transaction.finish().await,
+                Err(Error::TransactionFinished)
+            ));
+        })
+    }
+
+    #[test]
+    fn it_checks_the_database_of_transactions() {
+        tauri::async_runtime::block_on(async {
+            let pool = pool().await;
+            let transaction = begin(&pool).await;
+            assert!(transaction.check_database("sqlite:test.db").is_ok());
+            assert!(matches!(
+                transaction.check_database("sqlite:other.db"),
+                Err(Error::TransactionDatabaseMismatch(db)) if db == "sqlite:test.db"
+            ));
+        })
+    }
+
+    #[test]
+    fn it_executes_batches() {
+        tauri::async_runtime::block_on(async {
+            let pool = pool().await;
+            let (rows_affected, last_insert_ids) = pool
+                .execute_batch(
+                    "INSERT INTO items VALUES (?)".into(),
+                    vec![vec![json!("a")], vec![json!("b")], vec![json!("c")]],
+                )
+                .await
+                .unwrap();
+            assert_eq!(rows_affected, 3);
+            assert_eq!(
+                serde_json::to_value(last_insert_ids).unwrap(),
+                json!([1, 2, 3])
+            );
+            assert_eq!(count(&pool).await, json!(3));
+        })
+    }
+
+    #[test]
+    fn it_rolls_back_failed_batches() {
+        tauri::async_runtime::block_on(async {
+            let pool = pool().await;
+            let result = pool
+                .execute_batch(
+                    "INSERT INTO items VALUES (?)".into(),
+                    vec![vec![json!("a")], vec![JsonValue::Null], vec![json!("c")]],
+                )
+                .await;
+            assert!(result.is_err());
+            assert_eq!(count(&pool).await, json!(0));
+        })
+    }
+
+    async fn stream_items(
+        credits: Arc<Semaphore>,
+        send: impl Fn(CursorEvent) -> bool + Send + Sync + 'static,
+    ) -> JoinHandle<()> {
+        let pool = pool().await;
+        pool.execute_batch(
+            "INSERT INTO items VALUES (?)".into(),
+            (0..5).map(|i| vec![json!(i.to_string())]).collect(),
+        )
+        .await
+        .unwrap();
+        tauri::async_runtime::spawn(async move {
+            let source = CursorSource::Pool(pool);
+            let query = "SELECT name FROM items".to_string();
+            stream(source, query, Vec::new(), 2, &credits, send).await
+        })
+    }
+
+    fn rows(event: Option<CursorEvent>) -> usize {
+        match event {
+            Some(CursorEvent::Rows(rows)) => rows.len(),
+            event => panic!("expected rows, got {event:?}"),
+        }
+    }
+
+    #[test]
+    fn it_streams_the_requested_chunks() {
+        tauri::async_runtime::block_on(async {
+            let credits = Arc::new(Semaphore::new(1));
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let task = stream_items(credits.clone(), move |event| tx.send(event).is_ok()).await;
+
+            assert_eq!(rows(rx.recv().await), 2);
+            // the next chunk waits for a request
+            assert!(rx.try_recv().is_err());
+
+            credits.add_permits(2);
+            assert_eq!(rows(rx.recv().await), 2);
+            assert_eq!(rows(rx.recv().await), 1);
+            assert!(matches!(
+                rx.recv().await,
+                Some(CursorEvent::Done { count: 5 })
+            ));
+            task.await.unwrap();
+        })
+    }
+
+    #[test]
+    fn it_stops_streaming_when_closed() {
+        tauri::async_runtime::block_on(async {
+            let credits = Arc::new(Semaphore::new(1));
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let task = stream_items(credits.clone(), move |event| tx.send(event).is_ok()).await;
+
+            assert_eq!(rows(rx.recv().await), 2);
+            credits.close();
+            task.await.unwrap();
+            assert!(rx.recv().await.is_none());
+        })
+    }
+
+    #[test]
+    fn it_stops_streaming_when_sending_fails() {
+        tauri::async_runtime::block_on(async {
+            let credits = Arc::new(Semaphore::new(10));
+            let sent = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let task = stream_items(credits.clone(), {
+                let sent = sent.clone();
+                move |event| {
+                    sent.lock().unwrap().push(event);
+                    false
+                }
+            })
+            .await;
+
+            task.await.unwrap();
+            assert_eq!(sent.lock().unwrap().len(), 1);
+            assert!(credits.is_closed());
+        })
+    }
+
+    #[test]
+    fn it_fails_fast_on_transactions_streamed_by_a_cursor() {
+        tauri::async_runtime::block_on(async {
+            let pool = pool().await;
+            let transaction = Arc::new(begin(&pool).await);
+            // the cursor waits for the webview to request the rows while holding the transaction
+            let credits = Arc::new(Semaphore::new(0));
+            transaction.start_cursor(credits.clone()).unwrap();
+            let task = tauri::async_runtime::spawn({
+                let source = CursorSource::Transaction(transaction.clone());
+                let credits = credits.clone();
+                async move {
+                    let query = "SELECT name FROM items".to_string();
+                    stream(source, query, Vec::new(), 1, &credits, |_| true).await
+                }
+            });
+
+            assert!(matches!(
+                transaction.lock().await,
+                Err(Error::TransactionBusy)
+            ));
+            assert!(matches!(
+                transaction.finish().await,
+                Err(Error::TransactionBusy)
+            ));
+            assert!(matches!(
+                transaction.start_cursor(Arc::new(Semaphore::new(1))),
+                Err(Error::TransactionBusy)
+            ));
+
+            // closing the cursor releases the transaction
+            credits.close();
+            task.await.unwrap();
+            transaction.finish().await.unwrap().commit().await.unwrap();
+            assert_eq!(count(&pool).await, json!(1));
+        })
+    }
+}
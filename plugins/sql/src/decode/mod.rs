@@ -2,9 +2,95 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
+//! Decoding of the selected values to JSON.
+//!
+//! The values are encoded the same way for all the databases, without losing precision:
+//! - integers outside of the safe range of JavaScript numbers are decimal strings; this is decided
+//!   for each value, so a column mixes numbers and strings if its values are on both sides of the range
+//! - NaN and infinite floats are the strings `NaN`, `Infinity` and `-Infinity`
+//! - decimals, dates, times and UUIDs are strings
+//! - binary values are base64 strings
+//!
+//! A value which cannot be decoded is an error rather than `null`.
+
+#![cfg_attr(
+    not(any(feature = "sqlite", feature = "mysql", feature = "postgres")),
+    allow(dead_code)
+)]
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value as JsonValue;
+
 #[cfg(feature = "mysql")]
 pub(crate) mod mysql;
 #[cfg(feature = "postgres")]
 pub(crate) mod postgres;
 #[cfg(feature = "sqlite")]
 pub(crate) mod sqlite;
+
+/// `Number.MAX_SAFE_INTEGER` in JavaScript.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+pub(crate) fn int(v: i64) -> JsonValue {
+    if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&v) {
+        JsonValue::from(v)
+    } else {
+        JsonValue::String(v.to_string())
+    }
+}
+
+pub(crate) fn uint(v: u64) -> JsonValue {
+    if v <= MAX_SAFE_INTEGER as u64 {
+        JsonValue::from(v)
+    } else {
+        JsonValue::String(v.to_string())
+    }
+}
+
+pub(crate) fn float(v: f64) -> JsonValue {
+    if v.is_nan() {
+        JsonValue::String("NaN".into())
+    } else if v.is_infinite() {
+        JsonValue::String(if v > 0. { "Infinity" } else { "-Infinity" }.into())
+    } else {
+        JsonValue::from(v)
+    }
+}
+
+/// Encodes the bytes in standard base64, with padding.
+pub(crate) fn bytes(v: &[u8]) -> JsonValue {
+    JsonValue::String(STANDARD.encode(v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn it_keeps_integers_precise() {
+        assert_eq!(int(42), json!(42));
+        assert_eq!(int(-MAX_SAFE_INTEGER), json!(-9007199254740991i64));
+        assert_eq!(int(MAX_SAFE_INTEGER + 2), json!("9007199254740993"));
+        assert_eq!(int(i64::MIN), json!("-9223372036854775808"));
+        assert_eq!(uint(7), json!(7));
+        assert_eq!(uint(u64::MAX), json!("18446744073709551615"));
+    }
+
+    #[test]
+    fn it_encodes_non_finite_floats() {
+        assert_eq!(float(1.5), json!(1.5));
+        assert_eq!(float(f64::NAN), json!("NaN"));
+        assert_eq!(float(f64::INFINITY), json!("Infinity"));
+        assert_eq!(float(f64::NEG_INFINITY), json!("-Infinity"));
+    }
+
+    #[test]
+    fn it_encodes_bytes_in_base64() {
+        assert_eq!(bytes(b""), json!(""));
+        assert_eq!(bytes(b"f"), json!("Zg=="));
+        assert_eq!(bytes(b"fo"), json!("Zm8="));
+        assert_eq!(bytes(b"foo"), json!("Zm9v"));
+        assert_eq!(bytes(&[0, 255, 254, 1]), json!("AP/+AQ=="));
+    }
+}
@@ -13,85 +13,26 @@ pub(crate) fn to_json(v: MySqlValueRef) -> Result<JsonValue, Error> {
         return Ok(JsonValue::Null);
     }
 
+    let v = ValueRef::to_owned(&v);
     let res = match v.type_info().name() {
-        "CHAR" | "VARCHAR" | "TINYTEXT" | "TEXT" | "MEDIUMTEXT" | "LONGTEXT" | "ENUM" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode() {
-                JsonValue::String(v)
-            } else {
-                JsonValue::Null
-            }
-        }
-        "FLOAT" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<f32>() {
-                JsonValue::from(v)
-            } else {
-                JsonValue::Null
-            }
-        }
-        "DOUBLE" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<f64>() {
-                JsonValue::from(v)
-            } else {
-                JsonValue::Null
-            }
-        }
-        "TINYINT" | "SMALLINT" | "INT" | "MEDIUMINT" | "BIGINT" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<i64>() {
-                JsonValue::Number(v.into())
-            } else {
-                JsonValue::Null
-            }
+        "CHAR" | "VARCHAR" | "TINYTEXT" | "TEXT" | "MEDIUMTEXT" | "LONGTEXT" | "ENUM" | "SET" => {
+            JsonValue::String(v.try_decode()?)
         }
+        "FLOAT" => super::float(v.try_decode::<f32>()?.into()),
+        "DOUBLE" => super::float(v.try_decode()?),
+        "TINYINT" | "SMALLINT" | "INT" | "MEDIUMINT" | "BIGINT" => super::int(v.try_decode()?),
         "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "INT UNSIGNED" | "MEDIUMINT UNSIGNED"
-        | "BIGINT UNSIGNED" | "YEAR" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<u64>() {
-                JsonValue::Number(v.into())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "BOOLEAN" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode() {
-                JsonValue::Bool(v)
-            } else {
-                JsonValue::Null
-            }
-        }
-        "DATE" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<Date>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "TIME" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<Time>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "DATETIME" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<PrimitiveDateTime>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "TIMESTAMP" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<OffsetDateTime>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "JSON" => ValueRef::to_owned(&v).try_decode().unwrap_or_default(),
-        "TINIYBLOB" | "MEDIUMBLOB" | "BLOB" | "LONGBLOB" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<Vec<u8>>() {
-                JsonValue::Array(v.into_iter().map(|n| JsonValue::Number(n.into())).collect())
-            } else {
-                JsonValue::Null
-            }
+        | "BIGINT UNSIGNED" | "YEAR" => super::uint(v.try_decode()?),
+        // sent as text, which is decoded as is to keep its precision
+        "DECIMAL" => JsonValue::String(v.try_decode_unchecked()?),
+        "BOOLEAN" => JsonValue::Bool(v.try_decode()?),
+        "DATE" => JsonValue::String(v.try_decode::<Date>()?.to_string()),
+        "TIME" => JsonValue::String(v.try_decode::<Time>()?.to_string()),
+        "DATETIME" => JsonValue::String(v.try_decode::<PrimitiveDateTime>()?.to_string()),
+        "TIMESTAMP" => JsonValue::String(v.try_decode::<OffsetDateTime>()?.to_string()),
+        "JSON" => v.try_decode()?,
+        "TINYBLOB" | "MEDIUMBLOB" | "BLOB" | "LONGBLOB" | "BINARY" | "VARBINARY" => {
+            super::bytes(&v.try_decode::<Vec<u8>>()?)
         }
         "NULL" => JsonValue::Null,
         _ => return Err(Error::UnsupportedDatatype(v.type_info().name().to_string())),
@@ -99,3 +40,54 @@ pub(crate) fn to_json(v: MySqlValueRef) -> Result<JsonValue, Error> {
 
     Ok(res)
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use sqlx::{Executor, MySqlPool, Row};
+
+    use super::*;
+
+    /// Selects the first value of `query` in a prepared statement, like the queries of the plugin.
+    async fn select(pool: &MySqlPool, query: &str) -> JsonValue {
+        let row = pool.fetch_one(sqlx::query(query)).await.unwrap();
+        to_json(row.try_get_raw(0).unwrap()).unwrap()
+    }
+
+    /// Runs against the database of `MYSQL_DATABASE_URL`, skipped if it is not set.
+    #[test]
+    fn it_decodes_values() {
+        let Ok(url) = std::env::var("MYSQL_DATABASE_URL") else {
+            return;
+        };
+        tauri::async_runtime::block_on(async {
+            let pool = MySqlPool::connect(&url).await.unwrap();
+            assert_eq!(select(&pool, "SELECT 'todo'").await, json!("todo"));
+            assert_eq!(select(&pool, "SELECT CAST(42 AS SIGNED)").await, json!(42));
+            assert_eq!(
+                select(&pool, "SELECT CAST(-9007199254740993 AS SIGNED)").await,
+                json!("-9007199254740993")
+            );
+            assert_eq!(select(&pool, "SELECT CAST(7 AS UNSIGNED)").await, json!(7));
+            assert_eq!(
+                select(&pool, "SELECT CAST(18446744073709551615 AS UNSIGNED)").await,
+                json!("18446744073709551615")
+            );
+            assert_eq!(select(&pool, "SELECT 1.5e0").await, json!(1.5));
+            assert_eq!(
+                select(&pool, "SELECT CAST(12.50 AS DECIMAL(5, 2))").await,
+                json!("12.50")
+            );
+            assert_eq!(
+                select(&pool, "SELECT CAST('2024-01-02' AS DATE)").await,
+                json!("2024-01-02")
+            );
+            assert_eq!(
+                select(&pool, r#"SELECT CAST('{"a": 1}' AS JSON)"#).await,
+                json!({ "a": 1 })
+            );
+            assert_eq!(select(&pool, "SELECT x'00fffe01'").await, json!("AP/+AQ=="));
+            assert_eq!(select(&pool, "SELECT NULL").await, JsonValue::Null);
+        })
+    }
+}
@@ -2,8 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-use serde_json::Value as JsonValue;
-use sqlx::{postgres::PgValueRef, TypeInfo, Value, ValueRef};
+use serde_json::{json, Value as JsonValue};
+use sqlx::{
+    postgres::{types::PgInterval, PgHasArrayType, PgValueFormat, PgValueRef},
+    Decode, Postgres, Type, TypeInfo, Value, ValueRef,
+};
 use time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
 
 use crate::Error;
@@ -14,94 +17,273 @@ pub(crate) fn to_json(v: PgValueRef) -> Result<JsonValue, Error> {
     }
 
     let res = match v.type_info().name() {
-        "CHAR" | "VARCHAR" | "TEXT" | "NAME" | "UUID" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode() {
-                JsonValue::String(v)
-            } else {
-                JsonValue::Null
-            }
-        }
-        "FLOAT4" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<f32>() {
-                JsonValue::from(v)
-            } else {
-                JsonValue::Null
-            }
-        }
-        "FLOAT8" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<f64>() {
-                JsonValue::from(v)
-            } else {
-                JsonValue::Null
-            }
-        }
-        "INT2" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<i16>() {
-                JsonValue::Number(v.into())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "INT4" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<i32>() {
-                JsonValue::Number(v.into())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "INT8" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<i64>() {
-                JsonValue::Number(v.into())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "BOOL" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode() {
-                JsonValue::Bool(v)
-            } else {
-                JsonValue::Null
-            }
-        }
-        "DATE" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<Date>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "TIME" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<Time>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "TIMESTAMP" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<PrimitiveDateTime>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "TIMESTAMPTZ" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<OffsetDateTime>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "JSON" | "JSONB" => ValueRef::to_owned(&v).try_decode().unwrap_or_default(),
-        "BYTEA" => {
-            if let Ok(v) = ValueRef::to_owned(&v).try_decode::<Vec<u8>>() {
-                JsonValue::Array(v.into_iter().map(|n| JsonValue::Number(n.into())).collect())
-            } else {
-                JsonValue::Null
-            }
+        "CHAR" | "VARCHAR" | "TEXT" | "NAME" => {
+            JsonValue::String(ValueRef::to_owned(&v).try_decode()?)
         }
+        "UUID" => JsonValue::String(match v.format() {
+            PgValueFormat::Binary => uuid(raw(&v)?)?,
+            PgValueFormat::Text => v.as_str().map_err(sqlx::Error::Decode)?.to_string(),
+        }),
+        "NUMERIC" => JsonValue::String(match v.format() {
+            PgValueFormat::Binary => numeric(raw(&v)?)?,
+            PgValueFormat::Text => v.as_str().map_err(sqlx::Error::Decode)?.to_string(),
+        }),
+        "FLOAT4" => super::float(ValueRef::to_owned(&v).try_decode::<f32>()?.into()),
+        "FLOAT8" => super::float(ValueRef::to_owned(&v).try_decode()?),
+        "INT2" => JsonValue::from(ValueRef::to_owned(&v).try_decode::<i16>()?),
+        "INT4" => JsonValue::from(ValueRef::to_owned(&v).try_decode::<i32>()?),
+        "INT8" => super::int(ValueRef::to_owned(&v).try_decode()?),
+        "BOOL" => JsonValue::Bool(ValueRef::to_owned(&v).try_decode()?),
+        "DATE" => JsonValue::String(ValueRef::to_owned(&v).try_decode::<Date>()?.to_string()),
+        "TIME" => JsonValue::String(ValueRef::to_owned(&v).try_decode::<Time>()?.to_string()),
+        "TIMESTAMP" => JsonValue::String(
+            ValueRef::to_owned(&v)
+                .try_decode::<PrimitiveDateTime>()?
+                .to_string(),
+        ),
+        "TIMESTAMPTZ" => JsonValue::String(
+            ValueRef::to_owned(&v)
+                .try_decode::<OffsetDateTime>()?
+                .to_string(),
+        ),
+        "INTERVAL" => interval(ValueRef::to_owned(&v).try_decode()?),
+        "JSON" | "JSONB" => ValueRef::to_owned(&v).try_decode()?,
+        "BYTEA" => super::bytes(&ValueRef::to_owned(&v).try_decode::<Vec<u8>>()?),
+        "CHAR[]" | "VARCHAR[]" | "TEXT[]" | "NAME[]" => array(v, JsonValue::String)?,
+        "FLOAT4[]" => array(v, |v: f32| super::float(v.into()))?,
+        "FLOAT8[]" => array(v, super::float)?,
+        "INT2[]" => array(v, |v: i16| JsonValue::from(v))?,
+        "INT4[]" => array(v, |v: i32| JsonValue::from(v))?,
+        "INT8[]" => array(v, super::int)?,
+        "BOOL[]" => array(v, JsonValue::Bool)?,
+        "INTERVAL[]" => array(v, interval)?,
+        "JSON[]" | "JSONB[]" => array(v, |v: JsonValue| v)?,
+        "BYTEA[]" => array(v, |v: Vec<u8>| super::bytes(&v))?,
         "VOID" => JsonValue::Null,
         _ => return Err(Error::UnsupportedDatatype(v.type_info().name().to_string())),
     };
 
     Ok(res)
 }
+
+/// Decodes a one-dimensional array, converting its elements with `to_json`.
+fn array<T>(v: PgValueRef, to_json: impl Fn(T) -> JsonValue) -> Result<JsonValue, Error>
+where
+    T: for<'r> Decode<'r, Postgres> + Type<Postgres> + PgHasArrayType,
+{
+    let values = ValueRef::to_owned(&v).try_decode::<Vec<Option<T>>>()?;
+    Ok(JsonValue::Array(
+        values
+            .into_iter()
+            .map(|v| v.map(&to_json).unwrap_or(JsonValue::Null))
+            .collect(),
+    ))
+}
+
+/// The parts of an interval are kept apart since their lengths vary, e.g. a month is 28 to 31 days.
+fn interval(v: PgInterval) -> JsonValue {
+    json!({
+        "months": v.months,
+        "days": v.days,
+        "microseconds": super::int(v.microseconds),
+    })
+}
+
+fn raw<'r>(v: &PgValueRef<'r>) -> Result<&'r [u8], Error> {
+    v.as_bytes().map_err(|e| sqlx::Error::Decode(e).into())
+}
+
+fn decode_error(message: &str) -> Error {
+    sqlx::Error::Decode(message.into()).into()
+}
+
+/// Formats a UUID in its binary representation as a hyphenated string.
+fn uuid(bytes: &[u8]) -> Result<String, Error> {
+    if bytes.len() != 16 {
+        return Err(decode_error("invalid UUID length"));
+    }
+    let mut uuid = String::with_capacity(36);
+    for (i, byte) in bytes.iter().enumerate() {
+        if matches!(i, 4 | 6 | 8 | 10) {
+            uuid.push('-');
+        }
+        uuid.push_str(&format!("{byte:02x}"));
+    }
+    Ok(uuid)
+}
+
+/// Formats a NUMERIC in its binary representation as a decimal string, with all its digits.
+///
+/// The representation is a header of 4 16-bit integers (the number of digits, the weight of the first digit,
+/// the sign and the number of decimal places to display) followed by the digits in base 10000.
+fn numeric(bytes: &[u8]) -> Result<String, Error> {
+    const NEGATIVE: u16 = 0x4000;
+    const NAN: u16 = 0xC000;
+    const INFINITY: u16 = 0xD000;
+    const NEGATIVE_INFINITY: u16 = 0xF000;
+
+    let words: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|word| u16::from_be_bytes([word[0], word[1]]))
+        .collect();
+    let [ndigits, weight, sign, scale, digits @ ..] = words.as_slice() else {
+        return Err(decode_error("invalid NUMERIC header"));
+    };
+    if bytes.len() != words.len() * 2 || digits.len() != usize::from(*ndigits) {
+        return Err(decode_error("invalid NUMERIC length"));
+    }
+    let weight = i32::from(*weight as i16);
+    let digit = |i: i32| {
+        usize::try_from(i)
+            .ok()
+            .and_then(|i| digits.get(i))
+            .copied()
+            .unwrap_or(0)
+    };
+
+    let mut numeric = match *sign {
+        NAN => return Ok("NaN".into()),
+        INFINITY => return Ok("Infinity".into()),
+        NEGATIVE_INFINITY => return Ok("-Infinity".into()),
+        NEGATIVE => String::from("-"),
+        _ => String::new(),
+    };
+    if weight < 0 {
+        numeric.push('0');
+    } else {
+        numeric.push_str(&digit(0).to_string());
+        for i in 1..=weight {
+            numeric.push_str(&format!("{:04}", digit(i)));
+        }
+    }
+    if *scale > 0 {
+        let mut fraction = String::new();
+        let mut i = weight + 1;
+        while fraction.len() < usize::from(*scale) {
+            fraction.push_str(&format!("{:04}", digit(i)));
+            i += 1;
+        }
+        fraction.truncate(usize::from(*scale));
+        numeric.push('.');
+        numeric.push_str(&fraction);
+    }
+    Ok(numeric)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::{Executor, PgPool, Row};
+
+    use super::*;
+
+    fn encode(words: &[i16]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn it_decodes_numerics() {
+        // 12345.678
+        assert_eq!(
+            numeric(&encode(&[3, 1, 0, 3, 1, 2345, 6780])).unwrap(),
+            "12345.678"
+        );
+        // -0.001
+        assert_eq!(numeric(&encode(&[1, -1, 0x4000, 3, 10])).unwrap(), "-0.001");
+        // 100000000
+        assert_eq!(numeric(&encode(&[1, 2, 0, 0, 1])).unwrap(), "100000000");
+        // 0.00
+        assert_eq!(numeric(&encode(&[0, 0, 0, 2])).unwrap(), "0.00");
+        assert_eq!(
+            numeric(&encode(&[0, 0, 0xC000u16 as i16, 0])).unwrap(),
+            "NaN"
+        );
+        assert!(numeric(&encode(&[2, 0, 0, 0, 1])).is_err());
+    }
+
+    #[test]
+    fn it_decodes_uuids() {
+        let bytes = [
+            0x67, 0xe5, 0x50, 0x44, 0x10, 0xb1, 0x42, 0x6f, 0x92, 0x47, 0xbb, 0x68, 0x0e, 0x5f,
+            0xe0, 0xc8,
+        ];
+        assert_eq!(
+            uuid(&bytes).unwrap(),
+            "67e55044-10b1-426f-9247-bb680e5fe0c8"
+        );
+        assert!(uuid(&bytes[..8]).is_err());
+    }
+
+    #[test]
+    fn it_decodes_intervals() {
+        let v = PgInterval {
+            months: 1,
+            days: 2,
+            microseconds: 3_000_000,
+        };
+        assert_eq!(
+            interval(v),
+            json!({ "months": 1, "days": 2, "microseconds": 3_000_000 })
+        );
+    }
+
+    /// Selects the first value of `query` in a prepared statement, like the queries of the plugin.
+    async fn select(pool: &PgPool, query: &str) -> JsonValue {
+        let row = pool.fetch_one(sqlx::query(query)).await.unwrap();
+        to_json(row.try_get_raw(0).unwrap()).unwrap()
+    }
+
+    /// Runs against the database of `POSTGRES_DATABASE_URL`, skipped if it is not set.
+    #[test]
+    fn it_decodes_values() {
+        let Ok(url) = std::env::var("POSTGRES_DATABASE_URL") else {
+            return;
+        };
+        tauri::async_runtime::block_on(async {
+            let pool = PgPool::connect(&url).await.unwrap();
+            assert_eq!(select(&pool, "SELECT 'todo'::TEXT").await, json!("todo"));
+            assert_eq!(select(&pool, "SELECT 42::INT2").await, json!(42));
+            assert_eq!(select(&pool, "SELECT 42::INT4").await, json!(42));
+            assert_eq!(select(&pool, "SELECT 42::INT8").await, json!(42));
+            assert_eq!(
+                select(&pool, "SELECT 9007199254740993::INT8").await,
+                json!("9007199254740993")
+            );
+            assert_eq!(select(&pool, "SELECT 1.5::FLOAT4").await, json!(1.5));
+            assert_eq!(select(&pool, "SELECT 'NaN'::FLOAT8").await, json!("NaN"));
+            assert_eq!(
+                select(&pool, "SELECT 12345.678::NUMERIC").await,
+                json!("12345.678")
+            );
+            assert_eq!(select(&pool, "SELECT TRUE").await, json!(true));
+            assert_eq!(
+                select(&pool, "SELECT '2024-01-02'::DATE").await,
+                json!("2024-01-02")
+            );
+            assert_eq!(
+                select(&pool, "SELECT '67e55044-10b1-426f-9247-bb680e5fe0c8'::UUID").await,
+                json!("67e55044-10b1-426f-9247-bb680e5fe0c8")
+            );
+            assert_eq!(
+                select(&pool, "SELECT '1 month 2 days 3 seconds'::INTERVAL").await,
+                json!({ "months": 1, "days": 2, "microseconds": 3_000_000 })
+            );
+            assert_eq!(
+                select(&pool, r#"SELECT '{"a": 1}'::JSONB"#).await,
+                json!({ "a": 1 })
+            );
+            assert_eq!(
+                select(&pool, r"SELECT '\x00fffe01'::BYTEA").await,
+                json!("AP/+AQ==")
+            );
+            assert_eq!(
+                select(&pool, "SELECT ARRAY[1, NULL, 9007199254740993]::INT8[]").await,
+                json!([1, null, "9007199254740993"])
+            );
+            assert_eq!(
+                select(&pool, "SELECT ARRAY['a', 'b']::TEXT[]").await,
+                json!(["a", "b"])
+            );
+            assert_eq!(select(&pool, "SELECT NULL::TEXT").await, JsonValue::Null);
+        })
+    }
+}
@@ -14,65 +14,45 @@ pub(crate) fn to_json(v: SqliteValueRef) -> Result<JsonValue, Error> {
     }
 
     let res = match v.type_info().name() {
-        "TEXT" => {
-            if let Ok(v) = v.to_owned().try_decode() {
-                JsonValue::String(v)
-            } else {
-                JsonValue::Null
-            }
-        }
-        "REAL" => {
-            if let Ok(v) = v.to_owned().try_decode::<f64>() {
-                JsonValue::from(v)
-            } else {
-                JsonValue::Null
-            }
-        }
-        "INTEGER" | "NUMERIC" => {
-            if let Ok(v) = v.to_owned().try_decode::<i64>() {
-                JsonValue::Number(v.into())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "BOOLEAN" => {
-            if let Ok(v) = v.to_owned().try_decode() {
-                JsonValue::Bool(v)
-            } else {
-                JsonValue::Null
-            }
-        }
-        "DATE" => {
-            if let Ok(v) = v.to_owned().try_decode::<Date>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "TIME" => {
-            if let Ok(v) = v.to_owned().try_decode::<Time>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
-        }
+        "TEXT" => JsonValue::String(v.to_owned().try_decode()?),
+        "REAL" => super::float(v.to_owned().try_decode()?),
+        "INTEGER" | "NUMERIC" => super::int(v.to_owned().try_decode()?),
+        "BOOLEAN" => JsonValue::Bool(v.to_owned().try_decode()?),
+        "DATE" => JsonValue::String(v.to_owned().try_decode::<Date>()?.to_string()),
+        "TIME" => JsonValue::String(v.to_owned().try_decode::<Time>()?.to_string()),
         "DATETIME" => {
-            if let Ok(v) = v.to_owned().try_decode::<PrimitiveDateTime>() {
-                JsonValue::String(v.to_string())
-            } else {
-                JsonValue::Null
-            }
-        }
-        "BLOB" => {
-            if let Ok(v) = v.to_owned().try_decode::<Vec<u8>>() {
-                JsonValue::Array(v.into_iter().map(|n| JsonValue::Number(n.into())).collect())
-            } else {
-                JsonValue::Null
-            }
+            JsonValue::String(v.to_owned().try_decode::<PrimitiveDateTime>()?.to_string())
         }
+        "BLOB" => super::bytes(&v.to_owned().try_decode::<Vec<u8>>()?),
         "NULL" => JsonValue::Null,
         _ => return Err(Error::UnsupportedDatatype(v.type_info().name().to_string())),
     };
 
     Ok(res)
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use sqlx::{Executor, Row, SqlitePool};
+
+    use super::*;
+
+    fn select(query: &str) -> JsonValue {
+        tauri::async_runtime::block_on(async {
+            let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+            let row = pool.fetch_one(query).await.unwrap();
+            to_json(row.try_get_raw(0).unwrap()).unwrap()
+        })
+    }
+
+    #[test]
+    fn it_decodes_values() {
+        assert_eq!(select("SELECT 'todo'"), json!("todo"));
+        assert_eq!(select("SELECT 1.5"), json!(1.5));
+        assert_eq!(select("SELECT 42"), json!(42));
+        assert_eq!(select("SELECT 9007199254740993"), json!("9007199254740993"));
+        assert_eq!(select("SELECT x'00fffe01'"), json!("AP/+AQ=="));
+        assert_eq!(select("SELECT NULL"), JsonValue::Null);
+    }
+}
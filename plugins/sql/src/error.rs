@@ -5,6 +5,7 @@
 use serde::{Serialize, Serializer};
 
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum Error {
     #[error(transparent)]
     Sql(#[from] sqlx::Error),
@@ -16,6 +17,16 @@ pub enum Error {
     DatabaseNotLoaded(String),
     #[error("unsupported datatype: {0}")]
     UnsupportedDatatype(String),
+    #[error(transparent)]
+    Tauri(#[from] tauri::Error),
+    #[error("transaction already committed or rolled back")]
+    TransactionFinished,
+    #[error("the transaction was started on database {0}")]
+    TransactionDatabaseMismatch(String),
+    #[error("transaction locked by a cursor streaming its rows, close the cursor first")]
+    TransactionBusy,
+    #[error("migration {0} cannot be reverted, it has no down migration")]
+    IrreversibleMigration(i64),
 }
 
 impl Serialize for Error {
@@ -14,9 +14,11 @@
 mod commands;
 mod decode;
 mod error;
+mod migrations;
 mod wrapper;
 
 pub use error::Error;
+pub use migrations::{MigrationManager, MigrationStatus, SqlExt};
 pub use wrapper::DbPool;
 
 use futures_core::future::BoxFuture;
@@ -31,7 +33,7 @@ use tauri::{
 };
 use tokio::sync::{Mutex, RwLock};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Default)]
 pub struct DbInstances(pub RwLock<HashMap<String, DbPool>>);
@@ -49,7 +51,12 @@ pub(crate) enum LastInsertId {
     None,
 }
 
-struct Migrations(Mutex<HashMap<String, MigrationList>>);
+struct Migrations {
+    registered: Mutex<HashMap<String, MigrationList>>,
+    /// The databases already migrated by a load, which are not migrated again by the next loads
+    /// so they do not undo a [`MigrationManager::migrate_to`].
+    migrated: Mutex<HashSet<String>>,
+}
 
 #[derive(Default, Clone, Deserialize)]
 pub struct PluginConfig {
@@ -57,7 +64,7 @@ pub struct PluginConfig {
     preload: Vec<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum MigrationKind {
     Up,
     Down,
@@ -73,7 +80,10 @@ impl From<MigrationKind> for MigrationType {
 }
 
 /// A migration definition.
-#[derive(Debug)]
+///
+/// A [`MigrationKind::Down`] migration reverts the [`MigrationKind::Up`] migration with the same version,
+/// see [`MigrationManager::migrate_to`].
+#[derive(Debug, Clone)]
 pub struct Migration {
     pub version: i64,
     pub description: &'static str,
@@ -81,24 +91,39 @@ pub struct Migration {
     pub kind: MigrationKind,
 }
 
-#[derive(Debug)]
-struct MigrationList(Vec<Migration>);
+#[derive(Debug, Clone, Default)]
+struct MigrationList(Vec<SqlxMigration>);
 
-impl MigrationSource<'static> for MigrationList {
-    fn resolve(self) -> BoxFuture<'static, std::result::Result<Vec<SqlxMigration>, BoxDynError>> {
-        Box::pin(async move {
-            let mut migrations = Vec::new();
-            for migration in self.0 {
-                if matches!(migration.kind, MigrationKind::Up) {
-                    migrations.push(SqlxMigration::new(
+impl From<Vec<Migration>> for MigrationList {
+    fn from(migrations: Vec<Migration>) -> Self {
+        Self(
+            migrations
+                .into_iter()
+                .map(|migration| {
+                    SqlxMigration::new(
                         migration.version,
                         migration.description.into(),
                         migration.kind.into(),
                         migration.sql.into(),
                         false,
-                    ));
-                }
-            }
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+impl MigrationSource<'static> for MigrationList {
+    fn resolve(self) -> BoxFuture<'static, std::result::Result<Vec<SqlxMigration>, BoxDynError>> {
+        Box::pin(async move {
+            let mut migrations = self.0;
+            // the migrator applies the up migrations in order and reverts them in reverse order
+            migrations.sort_by_key(|migration| {
+                (
+                    migration.version,
+                    migration.migration_type.is_down_migration(),
+                )
+            });
             Ok(migrations)
         })
     }
@@ -123,7 +148,32 @@ impl Builder {
     pub fn add_migrations(mut self, db_url: &str, migrations: Vec<Migration>) -> Self {
         self.migrations
             .get_or_insert(Default::default())
-            .insert(db_url.to_string(), MigrationList(migrations));
+            .insert(db_url.to_string(), migrations.into());
+        self
+    }
+
+    /// Add the migrations of a [`Migrator`] to a database.
+    ///
+    /// Combined with the [`sqlx::migrate!`](https://docs.rs/sqlx/0.8/sqlx/macro.migrate.html) macro,
+    /// this embeds a directory of `<VERSION>_<DESCRIPTION>.up.sql` and `<VERSION>_<DESCRIPTION>.down.sql` files
+    /// at build time. The macro requires a direct dependency on `sqlx` with the `macros` and `migrate` features.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// tauri::Builder::default()
+    ///   .plugin(
+    ///     tauri_plugin_sql::Builder::default()
+    ///       .add_migrator("sqlite:mydatabase.db", sqlx::migrate!("./migrations"))
+    ///       .build(),
+    ///   );
+    /// ```
+    #[must_use]
+    pub fn add_migrator(mut self, db_url: &str, migrator: Migrator) -> Self {
+        self.migrations.get_or_insert(Default::default()).insert(
+            db_url.to_string(),
+            MigrationList(migrator.iter().cloned().collect()),
+        );
         self
     }
 
@@ -131,8 +181,19 @@ impl Builder {
         PluginBuilder::<R, Option<PluginConfig>>::new("sql")
             .invoke_handler(tauri::generate_handler![
                 commands::load,
+                commands::migration_status,
+                commands::migrate_to,
                 commands::execute,
                 commands::select,
+                commands::execute_batch,
+                commands::prepare,
+                commands::execute_prepared,
+                commands::select_prepared,
+                commands::select_stream,
+                commands::request_rows,
+                commands::begin_transaction,
+                commands::commit,
+                commands::rollback,
                 commands::close
             ])
             .setup(|app, api| {
@@ -141,25 +202,28 @@ impl Builder {
                 tauri::async_runtime::block_on(async move {
                     let instances = DbInstances::default();
                     let mut lock = instances.0.write().await;
+                    let mut migrated = HashSet::new();
 
                     for db in config.preload {
                         let pool = DbPool::connect(&db, app).await?;
 
                         if let Some(migrations) =
-                            self.migrations.as_mut().and_then(|mm| mm.remove(&db))
+                            self.migrations.as_ref().and_then(|mm| mm.get(&db)).cloned()
                         {
                             let migrator = Migrator::new(migrations).await?;
                             pool.migrate(&migrator).await?;
                         }
 
+                        migrated.insert(db.clone());
                         lock.insert(db, pool);
                     }
                     drop(lock);
 
                     app.manage(instances);
-                    app.manage(Migrations(Mutex::new(
-                        self.migrations.take().unwrap_or_default(),
-                    )));
+                    app.manage(Migrations {
+                        registered: Mutex::new(self.migrations.take().unwrap_or_default()),
+                        migrated: Mutex::new(migrated),
+                    });
 
                     Ok(())
                 })
@@ -0,0 +1,213 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Management of the migrations of the loaded databases, see [`SqlExt::migrations`].
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use sqlx::migrate::Migrator;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::{DbInstances, Error, MigrationList, Migrations};
+
+/// The state of a migration of a database.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationStatus {
+    pub version: i64,
+    /// Empty for an applied migration which is not registered, e.g. one added by a newer version of the app.
+    pub description: String,
+    pub applied: bool,
+    /// Whether a down migration reverts it.
+    pub reversible: bool,
+}
+
+/// Extensions to [`tauri::App`], [`tauri::AppHandle`], [`tauri::WebviewWindow`], [`tauri::Webview`] and [`tauri::Window`] to access the migrations of the databases.
+pub trait SqlExt<R: Runtime> {
+    /// Manages the migrations registered for the database with the connection url `db`,
+    /// which must be loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tauri_plugin_sql::SqlExt;
+    ///
+    /// tauri::Builder::default()
+    ///   .plugin(tauri_plugin_sql::Builder::default().build())
+    ///   .setup(|app| {
+    ///     let migrations = app.migrations("sqlite:mydatabase.db");
+    ///     tauri::async_runtime::block_on(migrations.migrate_to(1))?;
+    ///     Ok(())
+    ///   });
+    /// ```
+    fn migrations(&self, db: impl Into<String>) -> MigrationManager<R>;
+}
+
+impl<R: Runtime, T: Manager<R>> SqlExt<R> for T {
+    fn migrations(&self, db: impl Into<String>) -> MigrationManager<R> {
+        MigrationManager {
+            app: self.app_handle().clone(),
+            db: db.into(),
+        }
+    }
+}
+
+/// Lists and applies the migrations of a database, see [`SqlExt::migrations`].
+pub struct MigrationManager<R: Runtime> {
+    app: AppHandle<R>,
+    db: String,
+}
+
+impl<R: Runtime> MigrationManager<R> {
+    /// Returns the registered and the applied migrations, by version.
+    pub async fn status(&self) -> Result<Vec<MigrationStatus>, Error> {
+        let instances = self.app.state::<DbInstances>();
+        let instances = instances.0.read().await;
+        let db = instances
+            .get(&self.db)
+            .ok_or_else(|| Error::DatabaseNotLoaded(self.db.clone()))?;
+
+        let applied = db.applied_migrations().await?;
+        Ok(status(&self.registered().await, &applied))
+    }
+
+    /// Returns the applied migrations, by version.
+    pub async fn applied(&self) -> Result<Vec<MigrationStatus>, Error> {
+        let mut status = self.status().await?;
+        status.retain(|migration| migration.applied);
+        Ok(status)
+    }
+
+    /// Migrates the database to `version`.
+    ///
+    /// The applied migrations above `version` are reverted with their down migrations, in reverse order,
+    /// then the pending migrations up to `version` are applied.
+    /// Nothing is reverted if one of them has no down migration.
+    ///
+    /// The registered migrations are only applied by the first load of the database,
+    /// so the version is kept until the app restarts.
+    pub async fn migrate_to(&self, version: i64) -> Result<(), Error> {
+        let instances = self.app.state::<DbInstances>();
+        let instances = instances.0.read().await;
+        let db = instances
+            .get(&self.db)
+            .ok_or_else(|| Error::DatabaseNotLoaded(self.db.clone()))?;
+
+        let registered = self.registered().await;
+        let applied = db.applied_migrations().await?;
+        let to_revert: Vec<i64> = applied.into_iter().filter(|v| *v > version).collect();
+        if !to_revert.is_empty() {
+            let status = status(&registered, &to_revert);
+            if let Some(migration) = status.iter().find(|m| m.applied && !m.reversible) {
+                return Err(Error::IrreversibleMigration(migration.version));
+            }
+
+            let migrator = Migrator::new(registered.clone()).await?;
+            db.undo(&migrator, version).await?;
+        }
+
+        let pending = MigrationList(
+            registered
+                .0
+                .into_iter()
+                .filter(|migration| migration.version <= version)
+                .collect(),
+        );
+        let migrator = Migrator::new(pending).await?;
+        db.migrate(&migrator).await
+    }
+
+    async fn registered(&self) -> MigrationList {
+        self.app
+            .state::<Migrations>()
+            .registered
+            .lock()
+            .await
+            .get(&self.db)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// The status of the `registered` migrations and of the `applied` versions.
+fn status(registered: &MigrationList, applied: &[i64]) -> Vec<MigrationStatus> {
+    let mut status = BTreeMap::new();
+    for migration in &registered.0 {
+        let entry = status
+            .entry(migration.version)
+            .or_insert_with(|| MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                applied: false,
+                reversible: false,
+            });
+        if migration.migration_type.is_down_migration() {
+            entry.reversible = true;
+        }
+    }
+    for version in applied {
+        status
+            .entry(*version)
+            .or_insert_with(|| MigrationStatus {
+                version: *version,
+                description: String::new(),
+                applied: true,
+                reversible: false,
+            })
+            .applied = true;
+    }
+    status.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::migrate::MigrationSource;
+
+    use super::*;
+    use crate::{Migration, MigrationKind};
+
+    fn migrations() -> MigrationList {
+        let migration = |version, kind| Migration {
+            version,
+            description: "migration",
+            sql: "",
+            kind,
+        };
+        vec![
+            migration(2, MigrationKind::Down),
+            migration(3, MigrationKind::Up),
+            migration(2, MigrationKind::Up),
+            migration(1, MigrationKind::Up),
+        ]
+        .into()
+    }
+
+    #[test]
+    fn it_resolves_up_migrations_before_their_down_migration() {
+        let resolved = tauri::async_runtime::block_on(migrations().resolve()).unwrap();
+        let resolved: Vec<(i64, bool)> = resolved
+            .iter()
+            .map(|m| (m.version, m.migration_type.is_down_migration()))
+            .collect();
+        assert_eq!(resolved, [(1, false), (2, false), (2, true), (3, false)]);
+    }
+
+    #[test]
+    fn it_lists_the_status_of_the_migrations() {
+        let status: Vec<(i64, bool, bool, String)> = status(&migrations(), &[1, 2, 4])
+            .into_iter()
+            .map(|m| (m.version, m.applied, m.reversible, m.description))
+            .collect();
+        assert_eq!(
+            status,
+            [
+                (1, true, false, "migration".to_string()),
+                (2, true, true, "migration".to_string()),
+                (3, false, false, "migration".to_string()),
+                (4, true, false, String::new()),
+            ]
+        );
+    }
+}
@@ -4,11 +4,19 @@
 
 #[cfg(feature = "sqlite")]
 use std::fs::create_dir_all;
+use std::future::Future;
 
+#[cfg(any(feature = "sqlite", feature = "mysql", feature = "postgres"))]
+use futures_core::stream::{BoxStream, Stream};
 use indexmap::IndexMap;
 use serde_json::Value as JsonValue;
 #[cfg(any(feature = "sqlite", feature = "mysql", feature = "postgres"))]
-use sqlx::{migrate::MigrateDatabase, Column, Executor, Pool, Row};
+use sqlx::{
+    encode::Encode,
+    migrate::{Migrate, MigrateDatabase},
+    query::Query,
+    Column, ColumnIndex, Database, Executor, Pool, Row, Transaction, Type,
+};
 #[cfg(any(feature = "sqlite", feature = "mysql", feature = "postgres"))]
 use tauri::Manager;
 use tauri::{AppHandle, Runtime};
@@ -22,6 +30,7 @@ use sqlx::Sqlite;
 
 use crate::LastInsertId;
 
+#[derive(Clone)]
 pub enum DbPool {
     #[cfg(feature = "sqlite")]
     Sqlite(Pool<Sqlite>),
@@ -125,6 +134,57 @@ impl DbPool {
         Ok(())
     }
 
+    /// Reverts the applied migrations above `_target` with their down migrations.
+    pub(crate) async fn undo(
+        &self,
+        _migrator: &sqlx::migrate::Migrator,
+        _target: i64,
+    ) -> Result<(), crate::Error> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            DbPool::Sqlite(pool) => _migrator.undo(pool, _target).await?,
+            #[cfg(feature = "mysql")]
+            DbPool::MySql(pool) => _migrator.undo(pool, _target).await?,
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => _migrator.undo(pool, _target).await?,
+            #[cfg(not(any(feature = "sqlite", feature = "mysql", feature = "postgres")))]
+            DbPool::None => (),
+        }
+        Ok(())
+    }
+
+    /// Versions of the migrations applied to the database, in ascending order.
+    pub(crate) async fn applied_migrations(&self) -> Result<Vec<i64>, crate::Error> {
+        let applied: Vec<sqlx::migrate::AppliedMigration> = match self {
+            #[cfg(feature = "sqlite")]
+            DbPool::Sqlite(pool) => {
+                let mut conn = pool.acquire().await?;
+                conn.ensure_migrations_table().await?;
+                conn.list_applied_migrations().await?
+            }
+            #[cfg(feature = "mysql")]
+            DbPool::MySql(pool) => {
+                let mut conn = pool.acquire().await?;
+                conn.ensure_migrations_table().await?;
+                conn.list_applied_migrations().await?
+            }
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => {
+                let mut conn = pool.acquire().await?;
+                conn.ensure_migrations_table().await?;
+                conn.list_applied_migrations().await?
+            }
+            #[cfg(not(any(feature = "sqlite", feature = "mysql", feature = "postgres")))]
+            DbPool::None => Vec::new(),
+        };
+        let mut versions: Vec<i64> = applied
+            .into_iter()
+            .map(|migration| migration.version)
+            .collect();
+        versions.sort_unstable();
+        Ok(versions)
+    }
+
     pub(crate) async fn close(&self) {
         match self {
             #[cfg(feature = "sqlite")]
@@ -146,19 +206,7 @@ impl DbPool {
         Ok(match self {
             #[cfg(feature = "sqlite")]
             DbPool::Sqlite(pool) => {
-                let mut query = sqlx::query(&_query);
-                for value in _values {
-                    if value.is_null() {
-                        query = query.bind(None::<JsonValue>);
-                    } else if value.is_string() {
-                        query = query.bind(value.as_str().unwrap().to_owned())
-                    } else if let Some(number) = value.as_number() {
-                        query = query.bind(number.as_f64().unwrap_or_default())
-                    } else {
-                        query = query.bind(value);
-                    }
-                }
-                let result = pool.execute(query).await?;
+                let result = pool.execute(bind_values(&_query, _values)).await?;
                 (
                     result.rows_affected(),
                     LastInsertId::Sqlite(result.last_insert_rowid()),
@@ -166,19 +214,7 @@ impl DbPool {
             }
             #[cfg(feature = "mysql")]
             DbPool::MySql(pool) => {
-                let mut query = sqlx::query(&_query);
-                for value in _values {
-                    if value.is_null() {
-                        query = query.bind(None::<JsonValue>);
-                    } else if value.is_string() {
-                        query = query.bind(value.as_str().unwrap().to_owned())
-                    } else if let Some(number) = value.as_number() {
-                        query = query.bind(number.as_f64().unwrap_or_default())
-                    } else {
-                        query = query.bind(value);
-                    }
-                }
-                let result = pool.execute(query).await?;
+                let result = pool.execute(bind_values(&_query, _values)).await?;
                 (
                     result.rows_affected(),
                     LastInsertId::MySql(result.last_insert_id()),
@@ -186,19 +222,7 @@ impl DbPool {
             }
             #[cfg(feature = "postgres")]
             DbPool::Postgres(pool) => {
-                let mut query = sqlx::query(&_query);
-                for value in _values {
-                    if value.is_null() {
-                        query = query.bind(None::<JsonValue>);
-                    } else if value.is_string() {
-                        query = query.bind(value.as_str().unwrap().to_owned())
-                    } else if let Some(number) = value.as_number() {
-                        query = query.bind(number.as_f64().unwrap_or_default())
-                    } else {
-                        query = query.bind(value);
-                    }
-                }
-                let result = pool.execute(query).await?;
+                let result = pool.execute(bind_values(&_query, _values)).await?;
                 (result.rows_affected(), LastInsertId::Postgres(()))
             }
             #[cfg(not(any(feature = "sqlite", feature = "mysql", feature = "postgres")))]
@@ -214,98 +238,356 @@ impl DbPool {
         Ok(match self {
             #[cfg(feature = "sqlite")]
             DbPool::Sqlite(pool) => {
-                let mut query = sqlx::query(&_query);
-                for value in _values {
-                    if value.is_null() {
-                        query = query.bind(None::<JsonValue>);
-                    } else if value.is_string() {
-                        query = query.bind(value.as_str().unwrap().to_owned())
-                    } else if let Some(number) = value.as_number() {
-                        query = query.bind(number.as_f64().unwrap_or_default())
-                    } else {
-                        query = query.bind(value);
-                    }
-                }
-                let rows = pool.fetch_all(query).await?;
-                let mut values = Vec::new();
-                for row in rows {
-                    let mut value = IndexMap::default();
-                    for (i, column) in row.columns().iter().enumerate() {
-                        let v = row.try_get_raw(i)?;
-
-                        let v = crate::decode::sqlite::to_json(v)?;
+                let rows = pool.fetch_all(bind_values(&_query, _values)).await?;
+                rows_to_json(rows, crate::decode::sqlite::to_json)?
+            }
+            #[cfg(feature = "mysql")]
+            DbPool::MySql(pool) => {
+                let rows = pool.fetch_all(bind_values(&_query, _values)).await?;
+                rows_to_json(rows, crate::decode::mysql::to_json)?
+            }
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => {
+                let rows = pool.fetch_all(bind_values(&_query, _values)).await?;
+                rows_to_json(rows, crate::decode::postgres::to_json)?
+            }
+            #[cfg(not(any(feature = "sqlite", feature = "mysql", feature = "postgres")))]
+            DbPool::None => Vec::new(),
+        })
+    }
 
-                        value.insert(column.name().to_string(), v);
-                    }
+    /// Fetches the rows selected by `_query` in chunks of `_chunk_size` rows passed to `_on_chunk`,
+    /// returning the number of rows.
+    ///
+    /// The next chunk is fetched once the future returned by `_on_chunk` resolves,
+    /// and the fetching stops if it resolves to `false`.
+    pub(crate) async fn select_chunks<F: Future<Output = bool>>(
+        &self,
+        _query: String,
+        _values: Vec<JsonValue>,
+        _chunk_size: usize,
+        _on_chunk: impl FnMut(Vec<IndexMap<String, JsonValue>>) -> F,
+    ) -> Result<usize, crate::Error> {
+        Ok(match self {
+            #[cfg(feature = "sqlite")]
+            DbPool::Sqlite(pool) => {
+                let rows = pool.fetch(bind_values(&_query, _values));
+                fetch_chunks(rows, crate::decode::sqlite::to_json, _chunk_size, _on_chunk).await?
+            }
+            #[cfg(feature = "mysql")]
+            DbPool::MySql(pool) => {
+                let rows = pool.fetch(bind_values(&_query, _values));
+                fetch_chunks(rows, crate::decode::mysql::to_json, _chunk_size, _on_chunk).await?
+            }
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => {
+                let rows = pool.fetch(bind_values(&_query, _values));
+                fetch_chunks(
+                    rows,
+                    crate::decode::postgres::to_json,
+                    _chunk_size,
+                    _on_chunk,
+                )
+                .await?
+            }
+            #[cfg(not(any(feature = "sqlite", feature = "mysql", feature = "postgres")))]
+            DbPool::None => 0,
+        })
+    }
 
-                    values.push(value);
-                }
-                values
+    /// Prepares `_query` on a connection of the pool, failing if it is invalid.
+    ///
+    /// The connection caches the prepared statement for the next executions of the same query.
+    pub(crate) async fn prepare(&self, _query: &str) -> Result<(), crate::Error> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            DbPool::Sqlite(pool) => {
+                pool.prepare(_query).await?;
             }
             #[cfg(feature = "mysql")]
             DbPool::MySql(pool) => {
-                let mut query = sqlx::query(&_query);
-                for value in _values {
-                    if value.is_null() {
-                        query = query.bind(None::<JsonValue>);
-                    } else if value.is_string() {
-                        query = query.bind(value.as_str().unwrap().to_owned())
-                    } else if let Some(number) = value.as_number() {
-                        query = query.bind(number.as_f64().unwrap_or_default())
-                    } else {
-                        query = query.bind(value);
-                    }
-                }
-                let rows = pool.fetch_all(query).await?;
-                let mut values = Vec::new();
-                for row in rows {
-                    let mut value = IndexMap::default();
-                    for (i, column) in row.columns().iter().enumerate() {
-                        let v = row.try_get_raw(i)?;
+                pool.prepare(_query).await?;
+            }
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => {
+                pool.prepare(_query).await?;
+            }
+            #[cfg(not(any(feature = "sqlite", feature = "mysql", feature = "postgres")))]
+            DbPool::None => (),
+        }
+        Ok(())
+    }
 
-                        let v = crate::decode::mysql::to_json(v)?;
+    /// Starts a transaction on a connection of the pool, which is dedicated to it until it ends.
+    pub(crate) async fn begin(&self) -> Result<DbTransaction, crate::Error> {
+        Ok(match self {
+            #[cfg(feature = "sqlite")]
+            DbPool::Sqlite(pool) => DbTransaction::Sqlite(pool.begin().await?),
+            #[cfg(feature = "mysql")]
+            DbPool::MySql(pool) => DbTransaction::MySql(pool.begin().await?),
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => DbTransaction::Postgres(pool.begin().await?),
+            #[cfg(not(any(feature = "sqlite", feature = "mysql", feature = "postgres")))]
+            DbPool::None => DbTransaction::None,
+        })
+    }
 
-                        value.insert(column.name().to_string(), v);
-                    }
+    /// Executes `query` once per list of bind values in a new transaction, which is committed
+    /// if all the executions succeed, see [`DbTransaction::execute_batch`].
+    pub(crate) async fn execute_batch(
+        &self,
+        query: String,
+        values: Vec<Vec<JsonValue>>,
+    ) -> Result<(u64, Vec<LastInsertId>), crate::Error> {
+        // rolled back when dropped on error
+        let mut transaction = self.begin().await?;
+        let result = transaction.execute_batch(query, values).await?;
+        transaction.commit().await?;
+        Ok(result)
+    }
+}
 
-                    values.push(value);
-                }
-                values
+/// A transaction started with [`DbPool::begin`].
+///
+/// It is rolled back when dropped without being committed.
+pub(crate) enum DbTransaction {
+    #[cfg(feature = "sqlite")]
+    Sqlite(Transaction<'static, Sqlite>),
+    #[cfg(feature = "mysql")]
+    MySql(Transaction<'static, MySql>),
+    #[cfg(feature = "postgres")]
+    Postgres(Transaction<'static, Postgres>),
+    #[cfg(not(any(feature = "sqlite", feature = "mysql", feature = "postgres")))]
+    None,
+}
+
+impl DbTransaction {
+    pub(crate) async fn execute(
+        &mut self,
+        _query: String,
+        _values: Vec<JsonValue>,
+    ) -> Result<(u64, LastInsertId), crate::Error> {
+        Ok(match self {
+            #[cfg(feature = "sqlite")]
+            DbTransaction::Sqlite(tx) => {
+                let result = (&mut **tx).execute(bind_values(&_query, _values)).await?;
+                (
+                    result.rows_affected(),
+                    LastInsertId::Sqlite(result.last_insert_rowid()),
+                )
+            }
+            #[cfg(feature = "mysql")]
+            DbTransaction::MySql(tx) => {
+                let result = (&mut **tx).execute(bind_values(&_query, _values)).await?;
+                (
+                    result.rows_affected(),
+                    LastInsertId::MySql(result.last_insert_id()),
+                )
             }
             #[cfg(feature = "postgres")]
-            DbPool::Postgres(pool) => {
-                let mut query = sqlx::query(&_query);
-                for value in _values {
-                    if value.is_null() {
-                        query = query.bind(None::<JsonValue>);
-                    } else if value.is_string() {
-                        query = query.bind(value.as_str().unwrap().to_owned())
-                    } else if let Some(number) = value.as_number() {
-                        query = query.bind(number.as_f64().unwrap_or_default())
-                    } else {
-                        query = query.bind(value);
-                    }
-                }
-                let rows = pool.fetch_all(query).await?;
-                let mut values = Vec::new();
-                for row in rows {
-                    let mut value = IndexMap::default();
-                    for (i, column) in row.columns().iter().enumerate() {
-                        let v = row.try_get_raw(i)?;
-
-                        let v = crate::decode::postgres::to_json(v)?;
+            DbTransaction::Postgres(tx) => {
+                let result = (&mut **tx).execute(bind_values(&_query, _values)).await?;
+                (result.rows_affected(), LastInsertId::Postgres(()))
+            }
+            #[cfg(not(any(feature = "sqlite", feature = "mysql", feature = "postgres")))]
+            DbTransaction::None => (0, LastInsertId::None),
+        })
+    }
 
-                        value.insert(column.name().to_string(), v);
-                    }
+    pub(crate) async fn select(
+        &mut self,
+        _query: String,
+        _values: Vec<JsonValue>,
+    ) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+        Ok(match self {
+            #[cfg(feature = "sqlite")]
+            DbTransaction::Sqlite(tx) => {
+                let rows = (&mut **tx).fetch_all(bind_values(&_query, _values)).await?;
+                rows_to_json(rows, crate::decode::sqlite::to_json)?
+            }
+            #[cfg(feature = "mysql")]
+            DbTransaction::MySql(tx) => {
+                let rows = (&mut **tx).fetch_all(bind_values(&_query, _values)).await?;
+                rows_to_json(rows, crate::decode::mysql::to_json)?
+            }
+            #[cfg(feature = "postgres")]
+            DbTransaction::Postgres(tx) => {
+                let rows = (&mut **tx).fetch_all(bind_values(&_query, _values)).await?;
+                rows_to_json(rows, crate::decode::postgres::to_json)?
+            }
+            #[cfg(not(any(feature = "sqlite", feature = "mysql", feature = "postgres")))]
+            DbTransaction::None => Vec::new(),
+        })
+    }
 
-                    values.push(value);
-                }
-                values
+    /// See [`DbPool::select_chunks`].
+    pub(crate) async fn select_chunks<F: Future<Output = bool>>(
+        &mut self,
+        _query: String,
+        _values: Vec<JsonValue>,
+        _chunk_size: usize,
+        _on_chunk: impl FnMut(Vec<IndexMap<String, JsonValue>>) -> F,
+    ) -> Result<usize, crate::Error> {
+        Ok(match self {
+            #[cfg(feature = "sqlite")]
+            DbTransaction::Sqlite(tx) => {
+                let rows = (&mut **tx).fetch(bind_values(&_query, _values));
+                fetch_chunks(rows, crate::decode::sqlite::to_json, _chunk_size, _on_chunk).await?
+            }
+            #[cfg(feature = "mysql")]
+            DbTransaction::MySql(tx) => {
+                let rows = (&mut **tx).fetch(bind_values(&_query, _values));
+                fetch_chunks(rows, crate::decode::mysql::to_json, _chunk_size, _on_chunk).await?
+            }
+            #[cfg(feature = "postgres")]
+            DbTransaction::Postgres(tx) => {
+                let rows = (&mut **tx).fetch(bind_values(&_query, _values));
+                fetch_chunks(
+                    rows,
+                    crate::decode::postgres::to_json,
+                    _chunk_size,
+                    _on_chunk,
+                )
+                .await?
             }
             #[cfg(not(any(feature = "sqlite", feature = "mysql", feature = "postgres")))]
-            DbPool::None => Vec::new(),
+            DbTransaction::None => 0,
         })
     }
+
+    /// Executes `query` once per list of bind values, returning the total number of affected rows
+    /// and the last inserted id of each execution.
+    pub(crate) async fn execute_batch(
+        &mut self,
+        query: String,
+        values: Vec<Vec<JsonValue>>,
+    ) -> Result<(u64, Vec<LastInsertId>), crate::Error> {
+        let mut rows_affected = 0;
+        let mut last_insert_ids = Vec::with_capacity(values.len());
+        for values in values {
+            let (rows, last_insert_id) = self.execute(query.clone(), values).await?;
+            rows_affected += rows;
+            last_insert_ids.push(last_insert_id);
+        }
+        Ok((rows_affected, last_insert_ids))
+    }
+
+    pub(crate) async fn commit(self) -> Result<(), crate::Error> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            DbTransaction::Sqlite(tx) => tx.commit().await?,
+            #[cfg(feature = "mysql")]
+            DbTransaction::MySql(tx) => tx.commit().await?,
+            #[cfg(feature = "postgres")]
+            DbTransaction::Postgres(tx) => tx.commit().await?,
+            #[cfg(not(any(feature = "sqlite", feature = "mysql", feature = "postgres")))]
+            DbTransaction::None => (),
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn rollback(self) -> Result<(), crate::Error> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            DbTransaction::Sqlite(tx) => tx.rollback().await?,
+            #[cfg(feature = "mysql")]
+            DbTransaction::MySql(tx) => tx.rollback().await?,
+            #[cfg(feature = "postgres")]
+            DbTransaction::Postgres(tx) => tx.rollback().await?,
+            #[cfg(not(any(feature = "sqlite", feature = "mysql", feature = "postgres")))]
+            DbTransaction::None => (),
+        }
+        Ok(())
+    }
+}
+
+/// Binds the JSON values to the parameters of `query`, in order.
+#[cfg(any(feature = "sqlite", feature = "mysql", feature = "postgres"))]
+fn bind_values<'q, DB: Database>(
+    query: &'q str,
+    values: Vec<JsonValue>,
+) -> Query<'q, DB, <DB as Database>::Arguments<'q>>
+where
+    JsonValue: Encode<'q, DB> + Type<DB>,
+    String: Encode<'q, DB> + Type<DB>,
+    f64: Encode<'q, DB> + Type<DB>,
+{
+    let mut query = sqlx::query(query);
+    for value in values {
+        if value.is_null() {
+            query = query.bind(None::<JsonValue>);
+        } else if value.is_string() {
+            query = query.bind(value.as_str().unwrap().to_owned())
+        } else if let Some(number) = value.as_number() {
+            query = query.bind(number.as_f64().unwrap_or_default())
+        } else {
+            query = query.bind(value);
+        }
+    }
+    query
+}
+
+/// Converts the rows to JSON objects with their columns in order, decoding the values with `to_json`.
+#[cfg(any(feature = "sqlite", feature = "mysql", feature = "postgres"))]
+fn rows_to_json<R: Row>(
+    rows: Vec<R>,
+    to_json: fn(<R::Database as Database>::ValueRef<'_>) -> Result<JsonValue, crate::Error>,
+) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error>
+where
+    usize: ColumnIndex<R>,
+{
+    rows.iter().map(|row| row_to_json(row, to_json)).collect()
+}
+
+#[cfg(any(feature = "sqlite", feature = "mysql", feature = "postgres"))]
+fn row_to_json<R: Row>(
+    row: &R,
+    to_json: fn(<R::Database as Database>::ValueRef<'_>) -> Result<JsonValue, crate::Error>,
+) -> Result<IndexMap<String, JsonValue>, crate::Error>
+where
+    usize: ColumnIndex<R>,
+{
+    let mut value = IndexMap::default();
+    for (i, column) in row.columns().iter().enumerate() {
+        let v = row.try_get_raw(i)?;
+
+        let v = to_json(v)?;
+
+        value.insert(column.name().to_string(), v);
+    }
+    Ok(value)
+}
+
+/// Converts the rows of the stream as they arrive, see [`DbPool::select_chunks`].
+#[cfg(any(feature = "sqlite", feature = "mysql", feature = "postgres"))]
+async fn fetch_chunks<R: Row, F: Future<Output = bool>>(
+    mut rows: BoxStream<'_, Result<R, sqlx::Error>>,
+    to_json: fn(<R::Database as Database>::ValueRef<'_>) -> Result<JsonValue, crate::Error>,
+    chunk_size: usize,
+    mut on_chunk: impl FnMut(Vec<IndexMap<String, JsonValue>>) -> F,
+) -> Result<usize, crate::Error>
+where
+    usize: ColumnIndex<R>,
+{
+    let chunk_size = chunk_size.max(1);
+    // the chunk size comes from the webview, do not trust it to preallocate
+    let capacity = chunk_size.min(1024);
+    let mut count = 0;
+    let mut chunk = Vec::with_capacity(capacity);
+    while let Some(row) = std::future::poll_fn(|cx| rows.as_mut().poll_next(cx)).await {
+        chunk.push(row_to_json(&row?, to_json)?);
+        count += 1;
+        if chunk.len() == chunk_size {
+            let chunk = std::mem::replace(&mut chunk, Vec::with_capacity(capacity));
+            if !on_chunk(chunk).await {
+                return Ok(count);
+            }
+        }
+    }
+    if !chunk.is_empty() {
+        on_chunk(chunk).await;
+    }
+    Ok(count)
 }
 
 #[cfg(feature = "sqlite")]
@@ -1 +1 @@
-if("__TAURI__"in window){var __TAURI_PLUGIN_STORE__=function(t){"use strict";var e,a;function r(t,e=!1){return window.__TAURI_INTERNALS__.transformCallback(t,e)}async function s(t,e={},a){return window.__TAURI_INTERNALS__.invoke(t,e,a)}"function"==typeof SuppressedError&&SuppressedError;class i{get rid(){return function(t,e,a,r){if("a"===a&&!r)throw new TypeError("Private accessor was defined without a getter");if("function"==typeof e?t!==e||!r:!e.has(t))throw new TypeError("Cannot read private member from an object whose class did not declare it");return"m"===a?r:"a"===a?r.call(t):r?r.value:e.get(t)}(this,e,"f")}constructor(t){e.set(this,void 0),function(t,e,a,r,s){if("function"==typeof e?t!==e||!s:!e.has(t))throw new TypeError("Cannot write private member to an object whose class did not declare it");e.set(t,a)}(this,e,t)}async close(){return s("plugin:resources|close",{rid:this.rid})}}async function n(t,e,a){const i={kind:"Any"};return s("plugin:event|listen",{event:t,target:i,handler:r(e)}).then((e=>async()=>async function(t,e){await s("plugin:event|unlisten",{event:t,eventId:e})}(t,e)))}async function o(t,e){return await u.load(t,e)}e=new WeakMap,function(t){t.WINDOW_RESIZED="tauri://resize",t.WINDOW_MOVED="tauri://move",t.WINDOW_CLOSE_REQUESTED="tauri://close-requested",t.WINDOW_DESTROYED="tauri://destroyed",t.WINDOW_FOCUS="tauri://focus",t.WINDOW_BLUR="tauri://blur",t.WINDOW_SCALE_FACTOR_CHANGED="tauri://scale-change",t.WINDOW_THEME_CHANGED="tauri://theme-changed",t.WINDOW_CREATED="tauri://window-created",t.WEBVIEW_CREATED="tauri://webview-created",t.DRAG_ENTER="tauri://drag-enter",t.DRAG_OVER="tauri://drag-over",t.DRAG_DROP="tauri://drag-drop",t.DRAG_LEAVE="tauri://drag-leave"}(a||(a={}));class u extends i{constructor(t){super(t)}static async load(t,e){const a=await s("plugin:store|load",{path:t,...e});return new u(a)}static async get(t){return await s("plugin:store|get_store",{path:t}).then((t=>t?new u(t):null))}async set(t,e){await s("plugin:store|set",{rid:this.rid,key:t,value:e})}async get(t){const[e,a]=await s("plugin:store|get",{rid:this.rid,key:t});return a?e:void 0}async has(t){return await s("plugin:store|has",{rid:this.rid,key:t})}async delete(t){return await s("plugin:store|delete",{rid:this.rid,key:t})}async clear(){await s("plugin:store|clear",{rid:this.rid})}async reset(){await s("plugin:store|reset",{rid:this.rid})}async keys(){return await s("plugin:store|keys",{rid:this.rid})}async values(){return await s("plugin:store|values",{rid:this.rid})}async entries(){return await s("plugin:store|entries",{rid:this.rid})}async length(){return await s("plugin:store|length",{rid:this.rid})}async reload(){await s("plugin:store|reload",{rid:this.rid})}async save(){await s("plugin:store|save",{rid:this.rid})}async onKeyChange(t,e){return await n("store://change",(a=>{a.payload.resourceId===this.rid&&a.payload.key===t&&e(a.payload.exists?a.payload.value:void 0)}))}async onChange(t){return await n("store://change",(e=>{e.payload.resourceId===this.rid&&t(e.payload.key,e.payload.exists?e.payload.value:void 0)}))}}return t.LazyStore=class{get store(){return this._store||(this._store=o(this.path,this.options)),this._store}constructor(t,e){this.path=t,this.options=e}async init(){await this.store}async set(t,e){return(await this.store).set(t,e)}async get(t){return(await this.store).get(t)}async has(t){return(await this.store).has(t)}async delete(t){return(await this.store).delete(t)}async clear(){await(await this.store).clear()}async reset(){await(await this.store).reset()}async keys(){return(await this.store).keys()}async values(){return(await this.store).values()}async entries(){return(await this.store).entries()}async length(){return(await this.store).length()}async reload(){await(await this.store).reload()}async save(){await(await this.store).save()}async onKeyChange(t,e){return(await this.store).onKeyChange(t,e)}async onChange(t){return(await this.store).onChange(t)}async close(){this._store&&await(await this._store).close()}},t.Store=u,t.getStore=async function(t){return await u.get(t)},t.load=o,t}({});Object.defineProperty(window.__TAURI__,"store",{value:__TAURI_PLUGIN_STORE__})}
+if("__TAURI__"in window){var __TAURI_PLUGIN_STORE__=function(e){"use strict";function t(e,t,s,n){if("a"===s&&!n)throw new TypeError("Private accessor was defined without a getter");if("function"==typeof t?e!==t||!n:!t.has(e))throw new TypeError("Cannot read private member from an object whose class did not declare it");return"m"===s?n:"a"===s?n.call(e):n?n.value:t.get(e)}function s(e,t,s,n,i){if("function"==typeof t?e!==t||!i:!t.has(e))throw new TypeError("Cannot write private member to an object whose class did not declare it");return t.set(e,s),s}var n,i,r,a,w;function h(e,t=!1){return window.__TAURI_INTERNALS__.transformCallback(e,t)}"function"==typeof SuppressedError&&SuppressedError;class o{constructor(){this.__TAURI_CHANNEL_MARKER__=!0,n.set(this,(()=>{})),i.set(this,0),r.set(this,{}),this.id=h((({message:e,id:a})=>{if(a===t(this,i,"f")){s(this,i,a+1),t(this,n,"f").call(this,e);const o=Object.keys(t(this,r,"f"));if(o.length>0){let e=a+1;for(const s of o.sort()){if(parseInt(s)!==e)break;{const i=t(this,r,"f")[s];delete t(this,r,"f")[s],t(this,n,"f").call(this,i),e+=1}}s(this,i,e)}}else t(this,r,"f")[a.toString()]=e}))}set onmessage(e){s(this,n,e)}get onmessage(){return t(this,n,"f")}toJSON(){return`__CHANNEL__:${this.id}`}}async function d(e,t={},s){return window.__TAURI_INTERNALS__.invoke(e,t,s)}n=new WeakMap,i=new WeakMap,r=new WeakMap;class l{get rid(){return t(this,a,"f")}constructor(e){a.set(this,void 0),s(this,a,e)}async close(){return d("plugin:resources|close",{rid:this.rid})}}async function p(e,t,s){const n={kind:"Any"};return d("plugin:event|listen",{event:e,target:n,handler:h(t)}).then((t=>async()=>async function(e,t){await d("plugin:event|unlisten",{event:e,eventId:t})}(e,t)))}function f(e){return e.split("/").slice(1).map((e=>e.replace(/~1/g,"/").replace(/~0/g,"~")))}function g(e,t){for(const s of t){if(null===e||"object"!=typeof e)return;e=e[s]}return e}async function u(e,t){return await c.load(e,t)}a=new WeakMap,function(e){e.WINDOW_RESIZED="tauri://resize",e.WINDOW_MOVED="tauri://move",e.WINDOW_CLOSE_REQUESTED="tauri://close-requested",e.WINDOW_DESTROYED="tauri://destroyed",e.WINDOW_FOCUS="tauri://focus",e.WINDOW_BLUR="tauri://blur",e.WINDOW_SCALE_FACTOR_CHANGED="tauri://scale-change",e.WINDOW_THEME_CHANGED="tauri://theme-changed",e.WINDOW_CREATED="tauri://window-created",e.WEBVIEW_CREATED="tauri://webview-created",e.DRAG_ENTER="tauri://drag-enter",e.DRAG_OVER="tauri://drag-over",e.DRAG_DROP="tauri://drag-drop",e.DRAG_LEAVE="tauri://drag-leave"}(w||(w={}));class c extends l{constructor(e){super(e)}static async load(e,t){const s=await d("plugin:store|load",{path:e,...t});return new c(s)}static async get(e){return await d("plugin:store|get_store",{path:e}).then((e=>e?new c(e):null))}async set(e,t,s){await d("plugin:store|set",{rid:this.rid,key:e,value:t,ttl:s?.ttl})}async get(e){const[t,s]=await d("plugin:store|get",{rid:this.rid,key:e});return s?t:void 0}async has(e){return await d("plugin:store|has",{rid:this.rid,key:e})}async delete(e){return await d("plugin:store|delete",{rid:this.rid,key:e})}async setPointer(e,t){await d("plugin:store|set_pointer",{rid:this.rid,pointer:e,value:t})}async getPointer(e){const[t,s]=await d("plugin:store|get_pointer",{rid:this.rid,pointer:e});return s?t:void 0}async hasPointer(e){return await d("plugin:store|has_pointer",{rid:this.rid,pointer:e})}async deletePointer(e){return await d("plugin:store|delete_pointer",{rid:this.rid,pointer:e})}async patch(e){await d("plugin:store|patch",{rid:this.rid,patch:e})}async batch(e){await d("plugin:store|batch",{rid:this.rid,operations:e})}async clear(){await d("plugin:store|clear",{rid:this.rid})}async reset(){await d("plugin:store|reset",{rid:this.rid})}async keys(){return await d("plugin:store|keys",{rid:this.rid})}async values(){return await d("plugin:store|values",{rid:this.rid})}async entries(){return await d("plugin:store|entries",{rid:this.rid})}async length(){return await d("plugin:store|length",{rid:this.rid})}async reload(){await d("plugin:store|reload",{rid:this.rid})}async save(){await d("plugin:store|save",{rid:this.rid})}async onKeyChange(e,t){return await this.subscribe(e,(s=>{s.key===e&&t(s.exists?s.value:void 0)}))}async onChange(e){return await this.subscribe(void 0,(t=>{e(t.key,t.exists?t.value:void 0)}))}async onPrefixChange(e,t){return await this.subscribe(e,(e=>{t(e.key,e.exists?e.value:void 0)}))}async onPointerChange(e,t){const s=f(e);return await this.listenChanges((e=>{const n=e.pointer?f(e.pointer):[e.key],i=Math.min(s.length,n.length);for(let a=0;a<i;a++)if(s[a]!==n[a])return;const r=e.exists?g(e.value,s.slice(1)):void 0;t(r)}))}async onConflict(e){return await p("store://conflict",(t=>{t.payload.resourceId===this.rid&&e({key:t.payload.key,local:t.payload.local,disk:t.payload.disk})}))}async subscribe(e,t){const s=new o;s.onmessage=t;const n=await d("plugin:store|subscribe",{rid:this.rid,prefix:e,onChange:s});return()=>{d("plugin:store|unsubscribe",{rid:n})}}async listenChanges(e){const t=await p("store://change",(t=>{t.payload.resourceId===this.rid&&e(t.payload)})),s=await p("store://batch-change",(t=>{t.payload.resourceId===this.rid&&t.payload.changes.forEach(e)}));return()=>{t(),s()}}}return e.LazyStore=class{get store(){return this._store||(this._store=u(this.path,this.options)),this._store}constructor(e,t){this.path=e,this.options=t}async init(){await this.store}async set(e,t,s){return(await this.store).set(e,t,s)}async get(e){return(await this.store).get(e)}async has(e){return(await this.store).has(e)}async delete(e){return(await this.store).delete(e)}async setPointer(e,t){return(await this.store).setPointer(e,t)}async getPointer(e){return(await this.store).getPointer(e)}async hasPointer(e){return(await this.store).hasPointer(e)}async deletePointer(e){return(await this.store).deletePointer(e)}async patch(e){return(await this.store).patch(e)}async batch(e){return(await this.store).batch(e)}async clear(){await(await this.store).clear()}async reset(){await(await this.store).reset()}async keys(){return(await this.store).keys()}async values(){return(await this.store).values()}async entries(){return(await this.store).entries()}async length(){return(await this.store).length()}async reload(){await(await this.store).reload()}async save(){await(await this.store).save()}async onKeyChange(e,t){return(await this.store).onKeyChange(e,t)}async onChange(e){return(await this.store).onChange(e)}async onPrefixChange(e,t){return(await this.store).onPrefixChange(e,t)}async onPointerChange(e,t){return(await this.store).onPointerChange(e,t)}async onConflict(e){return(await this.store).onConflict(e)}async close(){this._store&&await(await this._store).close()}},e.Store=c,e.getStore=async function(e){return await c.get(e)},e.load=u,e.onRecovery=async function(e){return await p("store://recovery",(t=>{e(t.payload)}))},e}({});Object.defineProperty(window.__TAURI__,"store",{value:__TAURI_PLUGIN_STORE__})}
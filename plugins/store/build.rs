@@ -9,6 +9,14 @@ const COMMANDS: &[&str] = &[
     "get",
     "has",
     "delete",
+    "set_pointer",
+    "get_pointer",
+    "has_pointer",
+    "delete_pointer",
+    "patch",
+    "batch",
+    "subscribe",
+    "unsubscribe",
     "clear",
     "reset",
     "keys",
@@ -4,14 +4,53 @@
 
 import { listen, type UnlistenFn } from '@tauri-apps/api/event'
 
-import { invoke, Resource } from '@tauri-apps/api/core'
+import { invoke, Channel, Resource } from '@tauri-apps/api/core'
 
-interface ChangePayload<T> {
-  path: string
-  resourceId?: number
+interface KeyChange<T> {
   key: string
   value: T
   exists: boolean
+  pointer?: string
+}
+
+interface ChangePayload<T> extends KeyChange<T> {
+  path: string
+  resourceId?: number
+}
+
+interface BatchChangePayload<T> {
+  path: string
+  resourceId?: number
+  changes: Array<KeyChange<T>>
+}
+
+/**
+ * A mutation applied by {@linkcode Store.batch}.
+ */
+export type BatchOperation =
+  | { op: 'set'; key: string; value: unknown }
+  | { op: 'delete'; key: string }
+  | { op: 'setPointer'; pointer: string; value: unknown }
+  | { op: 'deletePointer'; pointer: string }
+  | { op: 'patch'; patch: Record<string, unknown> }
+  | { op: 'clear' }
+  | { op: 'reset' }
+
+function parsePointer(pointer: string): string[] {
+  return pointer
+    .split('/')
+    .slice(1)
+    .map((token) => token.replace(/~1/g, '/').replace(/~0/g, '~'))
+}
+
+function resolvePointer(value: unknown, tokens: string[]): unknown {
+  for (const token of tokens) {
+    if (value === null || typeof value !== 'object') {
+      return undefined
+    }
+    value = (value as Record<string, unknown>)[token]
+  }
+  return value
 }
 
 /**
@@ -23,17 +62,97 @@ export type StoreOptions = {
    */
   autoSave?: boolean | number
   /**
-   * Name of a serialize function registered in the rust side plugin builder
+   * Name of a serialize function registered in the rust side plugin builder,
+   * or of a built-in format enabled by its feature: `toml`, `yaml`, `msgpack` or `cbor`.
+   *
+   * Defaults to the format matching the extension of the store path.
    */
   serializeFnName?: string
   /**
-   * Name of a deserialize function registered in the rust side plugin builder
+   * Name of a deserialize function registered in the rust side plugin builder,
+   * or of a built-in format enabled by its feature: `toml`, `yaml`, `msgpack` or `cbor`.
+   *
+   * Defaults to the format matching the extension of the store path.
    */
   deserializeFnName?: string
   /**
    * Force create a new store with default values even if it already exists.
    */
   createNew?: boolean
+  /**
+   * Keep a copy of the previous save next to the store file, with a `.bak` extension appended.
+   *
+   * If the store file is corrupted when the store is loaded, the backup is loaded instead,
+   * see {@link onRecovery}.
+   */
+  backup?: boolean
+  /**
+   * Name of an encryption key registered in the rust side plugin builder, used to encrypt the store file.
+   *
+   * Requires the `encryption` feature of the rust plugin.
+   */
+  encryptionKeyName?: string
+  /**
+   * Watch the store file and pick up the changes made to it by other processes, see {@link Store.onConflict}.
+   *
+   * Requires the `watch` feature of the rust plugin.
+   */
+  watch?: boolean
+  /**
+   * Hold an advisory lock on a `.lock` file next to the store file while the store is saved or read.
+   *
+   * Requires the `file-lock` feature of the rust plugin.
+   */
+  fileLock?: boolean
+}
+
+/**
+ * Options to set a value
+ */
+export type SetOptions = {
+  /**
+   * Time-to-live of the entry in milliseconds, after which it is treated as absent and removed from the store.
+   */
+  ttl?: number
+}
+
+/**
+ * Payload of the event emitted when a watched store file changed a key that also has unsaved changes.
+ */
+export interface ConflictPayload<T = unknown> {
+  /** The key changed in both places */
+  key: string
+  /** The value in memory, which is kept and overwrites the store file on the next save */
+  local: T | null
+  /** The value in the store file */
+  disk: T | null
+}
+
+/**
+ * Payload of the event emitted when a store file could not be loaded.
+ */
+export interface RecoveryPayload {
+  /** Path of the store file */
+  path: string
+  /** Why the store file could not be loaded */
+  error: string
+  /** Whether the store was restored from its backup, otherwise it starts from its defaults */
+  restoredFromBackup: boolean
+}
+
+/**
+ * Listens to store files that are corrupted when loaded.
+ *
+ * The corrupted file is kept next to the store file with a `.corrupted` extension appended.
+ *
+ * @param cb The handler called with the store that was recovered.
+ */
+export async function onRecovery(
+  cb: (payload: RecoveryPayload) => void
+): Promise<UnlistenFn> {
+  return await listen<RecoveryPayload>('store://recovery', (event) => {
+    cb(event.payload)
+  })
 }
 
 /**
@@ -105,8 +224,8 @@ export class LazyStore implements IStore {
     await this.store
   }
 
-  async set(key: string, value: unknown): Promise<void> {
-    return (await this.store).set(key, value)
+  async set(key: string, value: unknown, options?: SetOptions): Promise<void> {
+    return (await this.store).set(key, value, options)
   }
 
   async get<T>(key: string): Promise<T | undefined> {
@@ -121,6 +240,30 @@ export class LazyStore implements IStore {
     return (await this.store).delete(key)
   }
 
+  async setPointer(pointer: string, value: unknown): Promise<void> {
+    return (await this.store).setPointer(pointer, value)
+  }
+
+  async getPointer<T>(pointer: string): Promise<T | undefined> {
+    return (await this.store).getPointer<T>(pointer)
+  }
+
+  async hasPointer(pointer: string): Promise<boolean> {
+    return (await this.store).hasPointer(pointer)
+  }
+
+  async deletePointer(pointer: string): Promise<boolean> {
+    return (await this.store).deletePointer(pointer)
+  }
+
+  async patch(patch: Record<string, unknown>): Promise<void> {
+    return (await this.store).patch(patch)
+  }
+
+  async batch(operations: BatchOperation[]): Promise<void> {
+    return (await this.store).batch(operations)
+  }
+
   async clear(): Promise<void> {
     await (await this.store).clear()
   }
@@ -166,6 +309,26 @@ export class LazyStore implements IStore {
     return (await this.store).onChange<T>(cb)
   }
 
+  async onPrefixChange<T>(
+    prefix: string,
+    cb: (key: string, value: T | undefined) => void
+  ): Promise<UnlistenFn> {
+    return (await this.store).onPrefixChange<T>(prefix, cb)
+  }
+
+  async onPointerChange<T>(
+    pointer: string,
+    cb: (value: T | undefined) => void
+  ): Promise<UnlistenFn> {
+    return (await this.store).onPointerChange<T>(pointer, cb)
+  }
+
+  async onConflict<T>(
+    cb: (conflict: ConflictPayload<T>) => void
+  ): Promise<UnlistenFn> {
+    return (await this.store).onConflict<T>(cb)
+  }
+
   async close(): Promise<void> {
     if (this._store) {
       await (await this._store).close()
@@ -226,11 +389,12 @@ export class Store extends Resource implements IStore {
     )
   }
 
-  async set(key: string, value: unknown): Promise<void> {
+  async set(key: string, value: unknown, options?: SetOptions): Promise<void> {
     await invoke('plugin:store|set', {
       rid: this.rid,
       key,
-      value
+      value,
+      ttl: options?.ttl
     })
   }
 
@@ -256,6 +420,53 @@ export class Store extends Resource implements IStore {
     })
   }
 
+  async setPointer(pointer: string, value: unknown): Promise<void> {
+    await invoke('plugin:store|set_pointer', {
+      rid: this.rid,
+      pointer,
+      value
+    })
+  }
+
+  async getPointer<T>(pointer: string): Promise<T | undefined> {
+    const [value, exists] = await invoke<[T, boolean]>(
+      'plugin:store|get_pointer',
+      {
+        rid: this.rid,
+        pointer
+      }
+    )
+    return exists ? value : undefined
+  }
+
+  async hasPointer(pointer: string): Promise<boolean> {
+    return await invoke('plugin:store|has_pointer', {
+      rid: this.rid,
+      pointer
+    })
+  }
+
+  async deletePointer(pointer: string): Promise<boolean> {
+    return await invoke('plugin:store|delete_pointer', {
+      rid: this.rid,
+      pointer
+    })
+  }
+
+  async patch(patch: Record<string, unknown>): Promise<void> {
+    await invoke('plugin:store|patch', {
+      rid: this.rid,
+      patch
+    })
+  }
+
+  async batch(operations: BatchOperation[]): Promise<void> {
+    await invoke('plugin:store|batch', {
+      rid: this.rid,
+      operations
+    })
+  }
+
   async clear(): Promise<void> {
     await invoke('plugin:store|clear', { rid: this.rid })
   }
@@ -292,9 +503,9 @@ export class Store extends Resource implements IStore {
     key: string,
     cb: (value: T | undefined) => void
   ): Promise<UnlistenFn> {
-    return await listen<ChangePayload<T>>('store://change', (event) => {
-      if (event.payload.resourceId === this.rid && event.payload.key === key) {
-        cb(event.payload.exists ? event.payload.value : undefined)
+    return await this.subscribe<T>(key, (change) => {
+      if (change.key === key) {
+        cb(change.exists ? change.value : undefined)
       }
     })
   }
@@ -302,14 +513,106 @@ export class Store extends Resource implements IStore {
   async onChange<T>(
     cb: (key: string, value: T | undefined) => void
   ): Promise<UnlistenFn> {
-    return await listen<ChangePayload<T>>('store://change', (event) => {
-      if (event.payload.resourceId === this.rid) {
-        cb(
-          event.payload.key,
-          event.payload.exists ? event.payload.value : undefined
-        )
+    return await this.subscribe<T>(undefined, (change) => {
+      cb(change.key, change.exists ? change.value : undefined)
+    })
+  }
+
+  async onPrefixChange<T>(
+    prefix: string,
+    cb: (key: string, value: T | undefined) => void
+  ): Promise<UnlistenFn> {
+    return await this.subscribe<T>(prefix, (change) => {
+      cb(change.key, change.exists ? change.value : undefined)
+    })
+  }
+
+  async onPointerChange<T>(
+    pointer: string,
+    cb: (value: T | undefined) => void
+  ): Promise<UnlistenFn> {
+    const tokens = parsePointer(pointer)
+    return await this.listenChanges<unknown>((change) => {
+      const changed = change.pointer
+        ? parsePointer(change.pointer)
+        : [change.key]
+      // a change of a parent or a child of the pointer changes its value
+      const length = Math.min(tokens.length, changed.length)
+      for (let i = 0; i < length; i++) {
+        if (tokens[i] !== changed[i]) {
+          return
+        }
+      }
+      const value = change.exists
+        ? resolvePointer(change.value, tokens.slice(1))
+        : undefined
+      cb(value as T | undefined)
+    })
+  }
+
+  async onConflict<T>(
+    cb: (conflict: ConflictPayload<T>) => void
+  ): Promise<UnlistenFn> {
+    return await listen<ConflictPayload<T> & { resourceId?: number }>(
+      'store://conflict',
+      (event) => {
+        if (event.payload.resourceId === this.rid) {
+          cb({
+            key: event.payload.key,
+            local: event.payload.local,
+            disk: event.payload.disk
+          })
+        }
       }
+    )
+  }
+
+  /**
+   * Receives the changes of the keys starting with `prefix`, or of all keys, through a channel
+   * scoped to this store rather than the global change events.
+   */
+  private async subscribe<T>(
+    prefix: string | undefined,
+    cb: (change: KeyChange<T>) => void
+  ): Promise<UnlistenFn> {
+    const onChange = new Channel<KeyChange<T>>()
+    onChange.onmessage = cb
+    const rid = await invoke<number>('plugin:store|subscribe', {
+      rid: this.rid,
+      prefix,
+      onChange
     })
+    return () => {
+      void invoke('plugin:store|unsubscribe', { rid })
+    }
+  }
+
+  /**
+   * Listens to both the single changes and the changes applied by a batch.
+   */
+  private async listenChanges<T>(
+    cb: (change: KeyChange<T>) => void
+  ): Promise<UnlistenFn> {
+    const unlistenChange = await listen<ChangePayload<T>>(
+      'store://change',
+      (event) => {
+        if (event.payload.resourceId === this.rid) {
+          cb(event.payload)
+        }
+      }
+    )
+    const unlistenBatch = await listen<BatchChangePayload<T>>(
+      'store://batch-change',
+      (event) => {
+        if (event.payload.resourceId === this.rid) {
+          event.payload.changes.forEach(cb)
+        }
+      }
+    )
+    return () => {
+      unlistenChange()
+      unlistenBatch()
+    }
   }
 }
 
@@ -317,11 +620,18 @@ interface IStore {
   /**
    * Inserts a key-value pair into the store.
    *
+   * @example
+   * ```typescript
+   * // expires in an hour
+   * await store.set('token', token, { ttl: 60 * 60 * 1000 });
+   * ```
+   *
    * @param key
    * @param value
+   * @param options
    * @returns
    */
-  set(key: string, value: unknown): Promise<void>
+  set(key: string, value: unknown, options?: SetOptions): Promise<void>
 
   /**
    * Returns the value for the given `key` or `undefined` if the key does not exist.
@@ -347,6 +657,76 @@ interface IStore {
    */
   delete(key: string): Promise<boolean>
 
+  /**
+   * Sets the value at a [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901), creating the missing parent objects.
+   *
+   * The first token of the pointer is the store key, e.g. `/settings/editor/fontSize`.
+   *
+   * @param pointer
+   * @param value
+   * @returns
+   */
+  setPointer(pointer: string, value: unknown): Promise<void>
+
+  /**
+   * Returns the value at a JSON Pointer or `undefined` if it does not exist.
+   *
+   * @param pointer
+   * @returns
+   */
+  getPointer<T>(pointer: string): Promise<T | undefined>
+
+  /**
+   * Returns `true` if a value exists at a JSON Pointer.
+   *
+   * @param pointer
+   * @returns
+   */
+  hasPointer(pointer: string): Promise<boolean>
+
+  /**
+   * Removes the value at a JSON Pointer.
+   *
+   * @param pointer
+   * @returns
+   */
+  deletePointer(pointer: string): Promise<boolean>
+
+  /**
+   * Applies a [JSON Merge Patch](https://www.rfc-editor.org/rfc/rfc7396) to the store.
+   *
+   * `null` values remove the corresponding entries, objects are merged recursively
+   * and every other value replaces the current one.
+   *
+   * @example
+   * ```typescript
+   * await store.patch({ settings: { editor: { fontSize: 14 }, theme: null } });
+   * ```
+   *
+   * @param patch
+   * @returns
+   */
+  patch(patch: Record<string, unknown>): Promise<void>
+
+  /**
+   * Applies several mutations at once, or none of them if one fails.
+   *
+   * The store is saved once afterwards if auto save is enabled.
+   *
+   * @example
+   * ```typescript
+   * await store.batch([
+   *   { op: 'set', key: 'theme', value: 'dark' },
+   *   { op: 'setPointer', pointer: '/editor/fontSize', value: 14 },
+   *   { op: 'delete', key: 'legacy' }
+   * ]);
+   * ```
+   *
+   * @param operations
+   * @returns
+   */
+  batch(operations: BatchOperation[]): Promise<void>
+
   /**
    * Clears the store, removing all key-value pairs.
    *
@@ -431,6 +811,40 @@ interface IStore {
     cb: (key: string, value: T | undefined) => void
   ): Promise<UnlistenFn>
 
+  /**
+   * Listen to changes of the keys starting with `prefix`.
+   *
+   * Only the changes of these keys are sent to the webview.
+   * @param prefix
+   * @param cb
+   * @returns A promise resolving to a function to unlisten to the event.
+   */
+  onPrefixChange<T>(
+    prefix: string,
+    cb: (key: string, value: T | undefined) => void
+  ): Promise<UnlistenFn>
+
+  /**
+   * Listen to changes of the value at a JSON Pointer,
+   * including changes of its parents and of the values nested in it.
+   * @param pointer
+   * @param cb
+   * @returns A promise resolving to a function to unlisten to the event.
+   */
+  onPointerChange<T>(
+    pointer: string,
+    cb: (value: T | undefined) => void
+  ): Promise<UnlistenFn>
+
+  /**
+   * Listen to keys changed both in the watched store file and in memory since the last save.
+   * @param cb
+   * @returns A promise resolving to a function to unlisten to the event.
+   */
+  onConflict<T>(
+    cb: (conflict: ConflictPayload<T>) => void
+  ): Promise<UnlistenFn>
+
   /**
    * Close the store and cleans up this resource from memory.
    * **You should not call any method on this object anymore and should drop any reference to it.**
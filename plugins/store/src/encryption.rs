@@ -0,0 +1,73 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Encrypts stores at rest with XChaCha20-Poly1305.
+
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    XChaCha20Poly1305, XNonce,
+};
+
+/// Identifies encrypted store files and the format version.
+const MAGIC: &[u8] = b"TAURI-STORE-XCHACHA20POLY1305-V1\0";
+const NONCE_LENGTH: usize = 24;
+
+/// Supplies the 256-bit key used to encrypt a store, called whenever the store is loaded or saved.
+///
+/// The key can for instance be read from the OS keyring, a stronghold vault, or derived from a password.
+pub type EncryptionKeyFn = std::sync::Arc<
+    dyn Fn() -> Result<[u8; 32], Box<dyn std::error::Error + Send + Sync>> + Send + Sync,
+>;
+
+pub(crate) fn encrypt(key: &EncryptionKeyFn, plaintext: &[u8]) -> crate::Result<Vec<u8>> {
+    let cipher = XChaCha20Poly1305::new(&key().map_err(crate::Error::Encryption)?.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|e| crate::Error::Encryption(e.to_string().into()))?;
+
+    let mut bytes = Vec::with_capacity(MAGIC.len() + NONCE_LENGTH + ciphertext.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&nonce);
+    bytes.extend_from_slice(&ciphertext);
+    Ok(bytes)
+}
+
+pub(crate) fn decrypt(key: &EncryptionKeyFn, bytes: &[u8]) -> crate::Result<Vec<u8>> {
+    let payload = bytes
+        .strip_prefix(MAGIC)
+        .filter(|payload| payload.len() >= NONCE_LENGTH)
+        .ok_or_else(|| crate::Error::Encryption("the store file is not encrypted".into()))?;
+    let (nonce, ciphertext) = payload.split_at(NONCE_LENGTH);
+
+    let cipher = XChaCha20Poly1305::new(&key().map_err(crate::Error::Encryption)?.into());
+    cipher
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            crate::Error::Encryption("wrong key or the store file was tampered with".into())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn it_encrypts_and_decrypts() {
+        let key: EncryptionKeyFn = Arc::new(|| Ok([7; 32]));
+        let encrypted = encrypt(&key, b"{\"foo\":\"bar\"}").unwrap();
+        assert!(encrypted.starts_with(MAGIC));
+        assert_eq!(decrypt(&key, &encrypted).unwrap(), b"{\"foo\":\"bar\"}");
+
+        let wrong_key: EncryptionKeyFn = Arc::new(|| Ok([8; 32]));
+        assert!(decrypt(&wrong_key, &encrypted).is_err());
+
+        let mut tampered = encrypted.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt(&key, &tampered).is_err());
+
+        assert!(decrypt(&key, b"{\"foo\":\"bar\"}").is_err());
+    }
+}
@@ -29,6 +29,40 @@ pub enum Error {
     /// Deserialize function not found
     #[error("Deserialize Function \"{0}\" not found")]
     DeserializeFunctionNotFound(String),
+    /// Encryption key not found
+    #[error("Encryption key \"{0}\" not found")]
+    EncryptionKeyNotFound(String),
+    /// Failed to encrypt or decrypt the store
+    #[error("Failed to encrypt or decrypt store. {0}")]
+    Encryption(Box<dyn std::error::Error + Send + Sync>),
+    /// The JSON Pointer is malformed or does not point into an object or array
+    #[error("Invalid JSON Pointer \"{0}\"")]
+    InvalidPointer(String),
+    /// A merge patch for the whole store is not an object
+    #[error("Merge patch for a store must be an object")]
+    InvalidPatch,
+    /// The JSON Schema of a store is invalid
+    #[cfg(feature = "validation")]
+    #[error("Invalid JSON Schema. {0}")]
+    InvalidSchema(String),
+    /// A value does not match the JSON Schema of the store
+    #[cfg(feature = "validation")]
+    #[error("Invalid store value at \"{path}\": {message}")]
+    Validation {
+        /// JSON Pointer of the invalid value.
+        path: String,
+        message: String,
+    },
+    /// Watching a store requires the `watch` feature
+    #[error("Watching stores requires the `watch` feature")]
+    WatchNotEnabled,
+    /// Locking store files requires the `file-lock` feature
+    #[error("Locking store files requires the `file-lock` feature")]
+    FileLockNotEnabled,
+    /// Failed to watch the store file
+    #[cfg(feature = "watch")]
+    #[error(transparent)]
+    Watch(#[from] notify::Error),
     /// Some Tauri API failed
     #[error(transparent)]
     Tauri(#[from] tauri::Error),
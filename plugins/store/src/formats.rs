@@ -0,0 +1,234 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Store file formats supported out of the box, each behind the feature of the same name.
+//!
+//! The enabled formats are registered under their name, like the functions of
+//! [`Builder::register_serialize_fn`](crate::Builder::register_serialize_fn),
+//! and used by default for the store files with a matching extension, see [`StoreBuilder::new`](crate::StoreBuilder::new).
+//! Functions registered under the same name by the app replace them for the JavaScript side only.
+//!
+//! | Name      | Feature   | Extensions          |
+//! |-----------|-----------|---------------------|
+//! | `toml`    | `toml`    | `.toml`             |
+//! | `yaml`    | `yaml`    | `.yaml`, `.yml`     |
+//! | `msgpack` | `msgpack` | `.msgpack`, `.mpk`  |
+//! | `cbor`    | `cbor`    | `.cbor`             |
+
+use std::{collections::HashMap, path::Path};
+
+use crate::{DeserializeFn, SerializeFn};
+
+/// Name of the format of a store file, inferred from its extension.
+pub(crate) fn format_name(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "toml" => Some("toml"),
+        "yaml" | "yml" => Some("yaml"),
+        "msgpack" | "mpk" => Some("msgpack"),
+        "cbor" => Some("cbor"),
+        _ => None,
+    }
+}
+
+/// The enabled formats.
+const FORMATS: &[(&str, SerializeFn, DeserializeFn)] = &[
+    #[cfg(feature = "toml")]
+    ("toml", toml::serialize, toml::deserialize),
+    #[cfg(feature = "yaml")]
+    ("yaml", yaml::serialize, yaml::deserialize),
+    #[cfg(feature = "msgpack")]
+    ("msgpack", msgpack::serialize, msgpack::deserialize),
+    #[cfg(feature = "cbor")]
+    ("cbor", cbor::serialize, cbor::deserialize),
+];
+
+/// The serialize and deserialize functions of the enabled format matching the extension of a store file.
+pub(crate) fn infer(path: &Path) -> Option<(SerializeFn, DeserializeFn)> {
+    let name = format_name(path)?;
+    FORMATS
+        .iter()
+        .find(|(format, _, _)| *format == name)
+        .map(|(_, serialize, deserialize)| (*serialize, *deserialize))
+}
+
+/// The serialize and deserialize functions of the enabled formats, by name.
+pub(crate) fn builtin() -> (HashMap<String, SerializeFn>, HashMap<String, DeserializeFn>) {
+    let serialize_fns = FORMATS
+        .iter()
+        .map(|(name, serialize, _)| (name.to_string(), *serialize))
+        .collect();
+    let deserialize_fns = FORMATS
+        .iter()
+        .map(|(name, _, deserialize)| (name.to_string(), *deserialize))
+        .collect();
+    (serialize_fns, deserialize_fns)
+}
+
+/// [TOML](https://toml.io), which has no `null` and no integers above `i64::MAX`,
+/// so saving a store containing them fails.
+#[cfg(feature = "toml")]
+pub mod toml {
+    use serde_json::Value as JsonValue;
+    use std::collections::HashMap;
+
+    pub fn serialize(
+        cache: &HashMap<String, JsonValue>,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(::toml::to_string_pretty(cache)?.into_bytes())
+    }
+
+    pub fn deserialize(
+        bytes: &[u8],
+    ) -> Result<HashMap<String, JsonValue>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(::toml::from_str(std::str::from_utf8(bytes)?)?)
+    }
+}
+
+/// [YAML](https://yaml.org).
+#[cfg(feature = "yaml")]
+pub mod yaml {
+    use serde_json::Value as JsonValue;
+    use std::collections::HashMap;
+
+    pub fn serialize(
+        cache: &HashMap<String, JsonValue>,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(serde_yaml::to_string(cache)?.into_bytes())
+    }
+
+    pub fn deserialize(
+        bytes: &[u8],
+    ) -> Result<HashMap<String, JsonValue>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(serde_yaml::from_slice(bytes)?)
+    }
+}
+
+/// [MessagePack](https://msgpack.org).
+#[cfg(feature = "msgpack")]
+pub mod msgpack {
+    use serde_json::Value as JsonValue;
+    use std::collections::HashMap;
+
+    pub fn serialize(
+        cache: &HashMap<String, JsonValue>,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(rmp_serde::to_vec(cache)?)
+    }
+
+    pub fn deserialize(
+        bytes: &[u8],
+    ) -> Result<HashMap<String, JsonValue>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+}
+
+/// [CBOR](https://cbor.io).
+#[cfg(feature = "cbor")]
+pub mod cbor {
+    use serde_json::Value as JsonValue;
+    use std::collections::HashMap;
+
+    pub fn serialize(
+        cache: &HashMap<String, JsonValue>,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(cache, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn deserialize(
+        bytes: &[u8],
+    ) -> Result<HashMap<String, JsonValue>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(ciborium::from_reader(bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(
+        feature = "toml",
+        feature = "yaml",
+        feature = "msgpack",
+        feature = "cbor"
+    ))]
+    fn edge_cases() -> HashMap<String, serde_json::Value> {
+        serde_json::from_value(serde_json::json!({
+            "i64Min": i64::MIN,
+            "i64Max": i64::MAX,
+            "u64Max": u64::MAX,
+            "float": 0.1,
+            "null": null,
+            "nested": [[1, [2, null]], [], [{ "a": [true, "b"] }]],
+            "object": { "empty": {}, "string": "multi\nline \"quoted\"" }
+        }))
+        .unwrap()
+    }
+
+    #[cfg(any(feature = "yaml", feature = "msgpack", feature = "cbor"))]
+    fn assert_round_trip(serialize: SerializeFn, deserialize: DeserializeFn) {
+        let store = edge_cases();
+        let bytes = serialize(&store).unwrap();
+        assert_eq!(deserialize(&bytes).unwrap(), store);
+    }
+
+    #[test]
+    fn it_infers_format_from_extension() {
+        assert_eq!(format_name(Path::new("settings.toml")), Some("toml"));
+        assert_eq!(format_name(Path::new("settings.YML")), Some("yaml"));
+        assert_eq!(format_name(Path::new("cache.mpk")), Some("msgpack"));
+        assert_eq!(format_name(Path::new("store.json")), None);
+        assert_eq!(format_name(Path::new("store")), None);
+    }
+
+    #[test]
+    fn it_only_infers_enabled_formats() {
+        assert_eq!(
+            infer(Path::new("settings.toml")).is_some(),
+            cfg!(feature = "toml")
+        );
+        assert_eq!(
+            infer(Path::new("settings.yaml")).is_some(),
+            cfg!(feature = "yaml")
+        );
+        assert!(infer(Path::new("store.json")).is_none());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn it_round_trips_toml() {
+        let mut store = edge_cases();
+        // TOML cannot represent them
+        assert!(toml::serialize(&store).is_err());
+        store.remove("null");
+        store.remove("u64Max");
+        store.insert(
+            "nested".into(),
+            serde_json::json!([[1, [2]], [], [{ "a": [true, "b"] }]]),
+        );
+
+        let bytes = toml::serialize(&store).unwrap();
+        assert_eq!(toml::deserialize(&bytes).unwrap(), store);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn it_round_trips_yaml() {
+        assert_round_trip(yaml::serialize, yaml::deserialize);
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn it_round_trips_msgpack() {
+        assert_round_trip(msgpack::serialize, msgpack::deserialize);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn it_round_trips_cbor() {
+        assert_round_trip(cbor::serialize, cbor::deserialize);
+    }
+}
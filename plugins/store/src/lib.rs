@@ -11,6 +11,8 @@
     html_favicon_url = "https://github.com/tauri-apps/tauri/raw/dev/app-icon.png"
 )]
 
+#[cfg(feature = "encryption")]
+pub use encryption::EncryptionKeyFn;
 pub use error::{Error, Result};
 use serde::{Deserialize, Serialize};
 pub use serde_json::Value as JsonValue;
@@ -20,14 +22,29 @@ use std::{
     sync::{Arc, Mutex},
     time::Duration,
 };
-pub use store::{resolve_store_path, DeserializeFn, SerializeFn, Store, StoreBuilder};
+pub use store::{resolve_store_path, DeserializeFn, MigrationFn, SerializeFn, Store, StoreBuilder};
+use subscription::KeyFilter;
+pub use subscription::Subscription;
 use tauri::{
+    ipc::Channel,
     plugin::{self, TauriPlugin},
-    AppHandle, Manager, ResourceId, RunEvent, Runtime, State,
+    AppHandle, Manager, ResourceId, RunEvent, Runtime, State, Webview,
 };
+use transaction::BatchOperation;
+pub use transaction::Transaction;
 
+#[cfg(feature = "encryption")]
+mod encryption;
 mod error;
+pub mod formats;
+mod pointer;
 mod store;
+mod subscription;
+mod transaction;
+#[cfg(feature = "validation")]
+mod validation;
+#[cfg(feature = "watch")]
+mod watcher;
 
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -37,15 +54,61 @@ struct ChangePayload<'a> {
     key: &'a str,
     value: Option<&'a JsonValue>,
     exists: bool,
+    /// JSON Pointer of the nested value that changed under `key`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pointer: Option<&'a str>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BatchChangePayload<'a> {
+    path: &'a Path,
+    resource_id: Option<u32>,
+    changes: Vec<KeyChange<'a>>,
+}
+
+#[derive(Serialize, Clone)]
+struct KeyChange<'a> {
+    key: &'a str,
+    value: Option<&'a JsonValue>,
+    exists: bool,
+}
+
+/// A [`KeyChange`] sent to the channel of a subscription.
+#[derive(Serialize, Clone)]
+struct SubscriptionChange {
+    key: String,
+    value: Option<JsonValue>,
+    exists: bool,
+}
+
+#[cfg(feature = "watch")]
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ConflictPayload<'a> {
+    path: &'a Path,
+    resource_id: Option<u32>,
+    key: &'a str,
+    local: Option<&'a JsonValue>,
+    disk: Option<&'a JsonValue>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct RecoveryPayload<'a> {
+    path: &'a Path,
+    error: String,
+    restored_from_backup: bool,
 }
 
-#[derive(Debug)]
 struct StoreState {
     stores: Arc<Mutex<HashMap<PathBuf, ResourceId>>>,
     serialize_fns: HashMap<String, SerializeFn>,
     deserialize_fns: HashMap<String, DeserializeFn>,
     default_serialize: SerializeFn,
     default_deserialize: DeserializeFn,
+    #[cfg(feature = "encryption")]
+    encryption_keys: HashMap<String, EncryptionKeyFn>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -55,6 +118,7 @@ enum AutoSave {
     Bool(bool),
 }
 
+#[allow(clippy::too_many_arguments)]
 fn builder<R: Runtime>(
     app: AppHandle<R>,
     store_state: State<'_, StoreState>,
@@ -63,6 +127,10 @@ fn builder<R: Runtime>(
     serialize_fn_name: Option<String>,
     deserialize_fn_name: Option<String>,
     create_new: bool,
+    backup: bool,
+    encryption_key_name: Option<String>,
+    watch: bool,
+    file_lock: bool,
 ) -> Result<StoreBuilder<R>> {
     let mut builder = app.store_builder(path);
     if let Some(auto_save) = auto_save {
@@ -97,10 +165,46 @@ fn builder<R: Runtime>(
         builder = builder.create_new();
     }
 
+    if backup {
+        builder = builder.backup();
+    }
+
+    if let Some(encryption_key_name) = encryption_key_name {
+        #[cfg(feature = "encryption")]
+        {
+            let key = store_state
+                .encryption_keys
+                .get(&encryption_key_name)
+                .ok_or_else(|| crate::Error::EncryptionKeyNotFound(encryption_key_name))?;
+            builder = builder.encryption_key(key.clone());
+        }
+        #[cfg(not(feature = "encryption"))]
+        return Err(crate::Error::EncryptionKeyNotFound(encryption_key_name));
+    }
+
+    if watch {
+        #[cfg(feature = "watch")]
+        {
+            builder = builder.watch();
+        }
+        #[cfg(not(feature = "watch"))]
+        return Err(crate::Error::WatchNotEnabled);
+    }
+
+    if file_lock {
+        #[cfg(feature = "file-lock")]
+        {
+            builder = builder.file_lock();
+        }
+        #[cfg(not(feature = "file-lock"))]
+        return Err(crate::Error::FileLockNotEnabled);
+    }
+
     Ok(builder)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn load<R: Runtime>(
     app: AppHandle<R>,
     store_state: State<'_, StoreState>,
@@ -109,6 +213,10 @@ async fn load<R: Runtime>(
     serialize_fn_name: Option<String>,
     deserialize_fn_name: Option<String>,
     create_new: Option<bool>,
+    backup: Option<bool>,
+    encryption_key_name: Option<String>,
+    watch: Option<bool>,
+    file_lock: Option<bool>,
 ) -> Result<ResourceId> {
     let builder = builder(
         app,
@@ -118,6 +226,10 @@ async fn load<R: Runtime>(
         serialize_fn_name,
         deserialize_fn_name,
         create_new.unwrap_or_default(),
+        backup.unwrap_or_default(),
+        encryption_key_name,
+        watch.unwrap_or_default(),
+        file_lock.unwrap_or_default(),
     )?;
     let (_, rid) = builder.build_inner()?;
     Ok(rid)
@@ -139,10 +251,13 @@ async fn set<R: Runtime>(
     rid: ResourceId,
     key: String,
     value: JsonValue,
+    ttl: Option<u64>,
 ) -> Result<()> {
     let store = app.resources_table().get::<Store<R>>(rid)?;
-    store.set(key, value);
-    Ok(())
+    match ttl {
+        Some(ttl) => store.try_set_with_ttl(key, value, Duration::from_millis(ttl)),
+        None => store.try_set(key, value),
+    }
 }
 
 #[tauri::command]
@@ -169,6 +284,107 @@ async fn delete<R: Runtime>(app: AppHandle<R>, rid: ResourceId, key: String) ->
     Ok(store.delete(key))
 }
 
+#[tauri::command]
+async fn set_pointer<R: Runtime>(
+    app: AppHandle<R>,
+    rid: ResourceId,
+    pointer: String,
+    value: JsonValue,
+) -> Result<()> {
+    let store = app.resources_table().get::<Store<R>>(rid)?;
+    store.set_pointer(pointer, value)
+}
+
+#[tauri::command]
+async fn get_pointer<R: Runtime>(
+    app: AppHandle<R>,
+    rid: ResourceId,
+    pointer: String,
+) -> Result<(Option<JsonValue>, bool)> {
+    let store = app.resources_table().get::<Store<R>>(rid)?;
+    let value = store.get_pointer(pointer)?;
+    let exists = value.is_some();
+    Ok((value, exists))
+}
+
+#[tauri::command]
+async fn has_pointer<R: Runtime>(
+    app: AppHandle<R>,
+    rid: ResourceId,
+    pointer: String,
+) -> Result<bool> {
+    let store = app.resources_table().get::<Store<R>>(rid)?;
+    store.has_pointer(pointer)
+}
+
+#[tauri::command]
+async fn delete_pointer<R: Runtime>(
+    app: AppHandle<R>,
+    rid: ResourceId,
+    pointer: String,
+) -> Result<bool> {
+    let store = app.resources_table().get::<Store<R>>(rid)?;
+    store.delete_pointer(pointer)
+}
+
+#[tauri::command]
+async fn patch<R: Runtime>(app: AppHandle<R>, rid: ResourceId, patch: JsonValue) -> Result<()> {
+    let store = app.resources_table().get::<Store<R>>(rid)?;
+    store.patch(patch)
+}
+
+#[tauri::command]
+async fn batch<R: Runtime>(
+    app: AppHandle<R>,
+    rid: ResourceId,
+    operations: Vec<BatchOperation>,
+) -> Result<()> {
+    let store = app.resources_table().get::<Store<R>>(rid)?;
+    store.transaction(|transaction| {
+        operations
+            .into_iter()
+            .try_for_each(|operation| transaction.apply(operation))
+    })?
+}
+
+/// Sends the changes of the store to `on_change` until the subscription,
+/// which belongs to the calling webview, is closed or the channel fails.
+#[tauri::command]
+async fn subscribe<R: Runtime>(
+    app: AppHandle<R>,
+    webview: Webview<R>,
+    rid: ResourceId,
+    prefix: Option<String>,
+    on_change: Channel<SubscriptionChange>,
+) -> Result<ResourceId> {
+    let store = app.resources_table().get::<Store<R>>(rid)?;
+    let filter = match prefix {
+        Some(prefix) => KeyFilter::Prefix(prefix),
+        None => KeyFilter::Any,
+    };
+    let subscription = store.subscribe(
+        filter,
+        Arc::new(move |key: &str, value: Option<&JsonValue>| {
+            // the webview is most likely gone
+            on_change
+                .send(SubscriptionChange {
+                    key: key.to_string(),
+                    value: value.cloned(),
+                    exists: value.is_some(),
+                })
+                .is_ok()
+        }),
+    );
+    Ok(webview.resources_table().add(subscription))
+}
+
+#[tauri::command]
+async fn unsubscribe<R: Runtime>(webview: Webview<R>, rid: ResourceId) -> Result<()> {
+    let subscription = webview.resources_table().take::<Subscription>(rid)?;
+    subscription.unsubscribe();
+    Ok(())
+}
+
 #[tauri::command]
 async fn clear<R: Runtime>(app: AppHandle<R>, rid: ResourceId) -> Result<()> {
     let store = app.resources_table().get::<Store<R>>(rid)?;
@@ -323,15 +539,20 @@ pub struct Builder {
     deserialize_fns: HashMap<String, DeserializeFn>,
     default_serialize: SerializeFn,
     default_deserialize: DeserializeFn,
+    #[cfg(feature = "encryption")]
+    encryption_keys: HashMap<String, EncryptionKeyFn>,
 }
 
 impl Default for Builder {
     fn default() -> Self {
+        let (serialize_fns, deserialize_fns) = formats::builtin();
         Self {
-            serialize_fns: Default::default(),
-            deserialize_fns: Default::default(),
+            serialize_fns,
+            deserialize_fns,
             default_serialize,
             default_deserialize,
+            #[cfg(feature = "encryption")]
+            encryption_keys: Default::default(),
         }
     }
 }
@@ -370,6 +591,32 @@ impl Builder {
         self
     }
 
+    /// Register an encryption key to encrypt stores from the JavaScript side,
+    /// see [`StoreBuilder::encryption`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// tauri::Builder::default()
+    ///     .plugin(
+    ///         tauri_plugin_store::Builder::default()
+    ///             // read the key from the OS keyring or a stronghold vault instead
+    ///             .register_encryption_key("secrets".to_owned(), || Ok([0; 32]))
+    ///             .build(),
+    ///     );
+    /// ```
+    #[cfg(feature = "encryption")]
+    pub fn register_encryption_key<F>(mut self, name: String, key: F) -> Self
+    where
+        F: Fn() -> std::result::Result<[u8; 32], Box<dyn std::error::Error + Send + Sync>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.encryption_keys.insert(name, Arc::new(key));
+        self
+    }
+
     /// Use this serialize function for stores by default
     ///
     /// # Examples
@@ -414,8 +661,28 @@ impl Builder {
     pub fn build<R: Runtime>(self) -> TauriPlugin<R> {
         plugin::Builder::new("store")
             .invoke_handler(tauri::generate_handler![
-                load, get_store, set, get, has, delete, clear, reset, keys, values, length,
-                entries, reload, save,
+                load,
+                get_store,
+                set,
+                get,
+                has,
+                delete,
+                set_pointer,
+                get_pointer,
+                has_pointer,
+                delete_pointer,
+                patch,
+                batch,
+                subscribe,
+                unsubscribe,
+                clear,
+                reset,
+                keys,
+                values,
+                length,
+                entries,
+                reload,
+                save,
             ])
             .setup(move |app_handle, _api| {
                 app_handle.manage(StoreState {
@@ -424,6 +691,8 @@ impl Builder {
                     deserialize_fns: self.deserialize_fns,
                     default_serialize: self.default_serialize,
                     default_deserialize: self.default_deserialize,
+                    #[cfg(feature = "encryption")]
+                    encryption_keys: self.encryption_keys,
                 });
                 Ok(())
             })
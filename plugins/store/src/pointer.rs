@@ -0,0 +1,278 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Addresses values nested in the store with JSON Pointers ([RFC 6901](https://www.rfc-editor.org/rfc/rfc6901))
+//! and applies JSON Merge Patches ([RFC 7396](https://www.rfc-editor.org/rfc/rfc7396)).
+//!
+//! The first token of a pointer is the store key, so `/settings/editor/fontSize`
+//! points to the `fontSize` field of the `editor` object in the `settings` entry.
+
+use serde_json::{Map, Value as JsonValue};
+use std::collections::HashMap;
+
+use crate::{Error, Result};
+
+/// Splits `pointer` into the store key and the tokens addressing a value inside of it.
+pub(crate) fn split(pointer: &str) -> Result<(String, Vec<String>)> {
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(Error::InvalidPointer(pointer.into()));
+    };
+    let mut tokens = rest.split('/').map(unescape);
+    let key = tokens.next().unwrap_or_default();
+    Ok((key, tokens.collect()))
+}
+
+/// Builds a pointer from its unescaped tokens.
+pub(crate) fn join<'a>(tokens: impl IntoIterator<Item = &'a str>) -> String {
+    tokens
+        .into_iter()
+        .map(|token| format!("/{}", escape(token)))
+        .collect()
+}
+
+fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Returns the value at `pointer`.
+pub(crate) fn get<'a>(
+    cache: &'a HashMap<String, JsonValue>,
+    pointer: &str,
+) -> Result<Option<&'a JsonValue>> {
+    let (key, tokens) = split(pointer)?;
+    let mut value = cache.get(&key);
+    for token in &tokens {
+        value = value.and_then(|value| match value {
+            JsonValue::Object(map) => map.get(token),
+            JsonValue::Array(array) => array_index(array.len(), token).map(|i| &array[i]),
+            _ => None,
+        });
+    }
+    Ok(value)
+}
+
+/// Sets the value at `pointer`, creating the missing parent objects.
+///
+/// Array elements are addressed by index, `-` appends to the array.
+pub(crate) fn set(
+    cache: &mut HashMap<String, JsonValue>,
+    pointer: &str,
+    value: JsonValue,
+) -> Result<()> {
+    let (key, tokens) = split(pointer)?;
+    let Some((last, parents)) = tokens.split_last() else {
+        cache.insert(key, value);
+        return Ok(());
+    };
+
+    let mut target = cache
+        .entry(key)
+        .or_insert_with(|| JsonValue::Object(Map::new()));
+    for token in parents {
+        target = match target {
+            JsonValue::Object(map) => map
+                .entry(token.clone())
+                .or_insert_with(|| JsonValue::Object(Map::new())),
+            JsonValue::Array(array) => array_index(array.len(), token)
+                .and_then(|i| array.get_mut(i))
+                .ok_or_else(|| Error::InvalidPointer(pointer.into()))?,
+            _ => return Err(Error::InvalidPointer(pointer.into())),
+        };
+    }
+
+    match target {
+        JsonValue::Object(map) => {
+            map.insert(last.clone(), value);
+        }
+        JsonValue::Array(array) if last == "-" => array.push(value),
+        JsonValue::Array(array) => {
+            let slot = array_index(array.len(), last)
+                .and_then(|i| array.get_mut(i))
+                .ok_or_else(|| Error::InvalidPointer(pointer.into()))?;
+            *slot = value;
+        }
+        _ => return Err(Error::InvalidPointer(pointer.into())),
+    }
+    Ok(())
+}
+
+/// Removes the value at `pointer`, returning whether it existed.
+pub(crate) fn delete(cache: &mut HashMap<String, JsonValue>, pointer: &str) -> Result<bool> {
+    let (key, tokens) = split(pointer)?;
+    let Some((last, parents)) = tokens.split_last() else {
+        return Ok(cache.remove(&key).is_some());
+    };
+
+    let mut target = cache.get_mut(&key);
+    for token in parents {
+        target = target.and_then(|value| match value {
+            JsonValue::Object(map) => map.get_mut(token),
+            JsonValue::Array(array) => array_index(array.len(), token).map(|i| &mut array[i]),
+            _ => None,
+        });
+    }
+
+    Ok(match target {
+        Some(JsonValue::Object(map)) => map.remove(last).is_some(),
+        Some(JsonValue::Array(array)) => match array_index(array.len(), last) {
+            Some(i) => {
+                array.remove(i);
+                true
+            }
+            None => false,
+        },
+        _ => false,
+    })
+}
+
+/// Parses an array index token, which must not have leading zeros.
+fn array_index(len: usize, token: &str) -> Option<usize> {
+    if token.len() > 1 && token.starts_with('0') {
+        return None;
+    }
+    token.parse().ok().filter(|i| *i < len)
+}
+
+/// Applies the merge `patch` to the store, returning the pointers of the values that changed.
+pub(crate) fn merge_patch(
+    cache: &mut HashMap<String, JsonValue>,
+    patch: JsonValue,
+) -> Result<Vec<String>> {
+    let JsonValue::Object(patch) = patch else {
+        return Err(Error::InvalidPatch);
+    };
+
+    let mut changed = Vec::new();
+    for (key, patch) in patch {
+        let pointer = join([key.as_str()]);
+        if patch.is_null() {
+            if cache.remove(&key).is_some() {
+                changed.push(pointer);
+            }
+        } else {
+            let target = cache.entry(key).or_insert(JsonValue::Null);
+            merge(target, patch, pointer, &mut changed);
+        }
+    }
+    Ok(changed)
+}
+
+fn merge(target: &mut JsonValue, patch: JsonValue, pointer: String, changed: &mut Vec<String>) {
+    let JsonValue::Object(patch) = patch else {
+        if *target != patch {
+            *target = patch;
+            changed.push(pointer);
+        }
+        return;
+    };
+
+    if !target.is_object() {
+        *target = JsonValue::Object(Map::new());
+        changed.push(pointer.clone());
+    }
+    let JsonValue::Object(map) = target else {
+        unreachable!()
+    };
+    for (key, patch) in patch {
+        let child = format!("{pointer}/{}", escape(&key));
+        if patch.is_null() {
+            if map.remove(&key).is_some() {
+                changed.push(child);
+            }
+        } else {
+            merge(
+                map.entry(key).or_insert(JsonValue::Null),
+                patch,
+                child,
+                changed,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn store(value: JsonValue) -> HashMap<String, JsonValue> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn it_gets_sets_and_deletes_nested_values() {
+        let mut cache =
+            store(json!({ "settings": { "editor": { "fontSize": 12 } }, "a/b": [1, 2] }));
+
+        assert_eq!(
+            get(&cache, "/settings/editor/fontSize").unwrap(),
+            Some(&json!(12))
+        );
+        assert_eq!(get(&cache, "/a~1b/1").unwrap(), Some(&json!(2)));
+        assert_eq!(get(&cache, "/settings/theme").unwrap(), None);
+        assert!(get(&cache, "settings").is_err());
+
+        set(&mut cache, "/settings/editor/fontSize", json!(14)).unwrap();
+        set(&mut cache, "/settings/window/width", json!(800)).unwrap();
+        set(&mut cache, "/a~1b/-", json!(3)).unwrap();
+        assert!(set(&mut cache, "/a~1b/5", json!(0)).is_err());
+        assert!(set(&mut cache, "/settings/editor/fontSize/x", json!(0)).is_err());
+        assert_eq!(
+            cache,
+            store(json!({
+                "settings": { "editor": { "fontSize": 14 }, "window": { "width": 800 } },
+                "a/b": [1, 2, 3]
+            }))
+        );
+
+        assert!(delete(&mut cache, "/settings/window/width").unwrap());
+        assert!(!delete(&mut cache, "/settings/window/height").unwrap());
+        assert!(delete(&mut cache, "/a~1b/0").unwrap());
+        assert!(delete(&mut cache, "/settings").unwrap());
+        assert_eq!(cache, store(json!({ "a/b": [2, 3] })));
+    }
+
+    #[test]
+    fn it_applies_merge_patch() {
+        let mut cache = store(json!({
+            "title": "Goodbye!",
+            "author": { "givenName": "John", "familyName": "Doe" },
+            "tags": ["example", "sample"],
+            "content": "This will be unchanged"
+        }));
+
+        let mut changed = merge_patch(
+            &mut cache,
+            json!({
+                "title": "Hello!",
+                "phoneNumber": "+01-123-456-7890",
+                "author": { "familyName": null },
+                "tags": ["example"]
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(
+            cache,
+            store(json!({
+                "title": "Hello!",
+                "author": { "givenName": "John" },
+                "tags": ["example"],
+                "content": "This will be unchanged",
+                "phoneNumber": "+01-123-456-7890"
+            }))
+        );
+        changed.sort();
+        assert_eq!(
+            changed,
+            ["/author/familyName", "/phoneNumber", "/tags", "/title"]
+        );
+
+        assert!(merge_patch(&mut cache, json!(["not", "an", "object"])).is_err());
+    }
+}
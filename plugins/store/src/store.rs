@@ -2,14 +2,23 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-use crate::{ChangePayload, StoreState};
+use crate::{
+    pointer,
+    subscription::{KeyFilter, Listeners},
+    transaction, BatchChangePayload, ChangePayload, KeyChange, RecoveryPayload, StoreState,
+    Subscription, Transaction,
+};
+#[cfg(feature = "file-lock")]
+use fs4::fs_std::FileExt;
 use serde_json::Value as JsonValue;
 use std::{
     collections::HashMap,
-    fs,
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::Write,
     path::{Path, PathBuf},
-    sync::{Arc, Mutex},
-    time::Duration,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use tauri::{path::BaseDirectory, AppHandle, Emitter, Manager, Resource, ResourceId, Runtime};
 use tokio::{
@@ -22,6 +31,17 @@ pub type SerializeFn =
     fn(&HashMap<String, JsonValue>) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>;
 pub type DeserializeFn =
     fn(&[u8]) -> Result<HashMap<String, JsonValue>, Box<dyn std::error::Error + Send + Sync>>;
+pub type MigrationFn = Arc<dyn Fn(&mut HashMap<String, JsonValue>) + Send + Sync>;
+
+/// Key of the schema version in the store file.
+const VERSION_KEY: &str = "$version";
+/// Key of the expiry timestamps of the entries with a time-to-live in the store file.
+const EXPIRES_KEY: &str = "$expires";
+/// Keys of the store metadata in the store file.
+///
+/// Stored keys made of one or more `$` followed by the name of a reserved key, like `$version`,
+/// are saved with one more `$` so they are not read back as metadata.
+const RESERVED_KEYS: [&str; 2] = [VERSION_KEY, EXPIRES_KEY];
 
 pub fn resolve_store_path<R: Runtime>(
     app: &AppHandle<R>,
@@ -37,13 +57,40 @@ pub struct StoreBuilder<R: Runtime> {
     defaults: Option<HashMap<String, JsonValue>>,
     serialize_fn: SerializeFn,
     deserialize_fn: DeserializeFn,
+    /// The default functions, used for the store files written with them before their format was inferred.
+    fallback_fns: Option<(SerializeFn, DeserializeFn)>,
     auto_save: Option<Duration>,
+    purge_interval: Option<Duration>,
     create_new: bool,
+    backup: bool,
+    schema: Schema,
+    #[cfg(feature = "encryption")]
+    encryption_key: Option<crate::EncryptionKeyFn>,
+    #[cfg(feature = "watch")]
+    watch: bool,
+    #[cfg(feature = "file-lock")]
+    file_lock: bool,
+    #[cfg(feature = "validation")]
+    json_schema: Option<JsonValue>,
+    #[cfg(feature = "validation")]
+    key_schemas: HashMap<String, JsonValue>,
+    #[cfg(feature = "validation")]
+    replace_invalid: bool,
 }
 
 impl<R: Runtime> StoreBuilder<R> {
     /// Creates a new [`StoreBuilder`].
     ///
+    /// The store is saved with the builtin [format](crate::formats) matching the extension of `path`,
+    /// for instance TOML for `settings.toml` when the `toml` feature is enabled,
+    /// and with the plugin's default serialize and deserialize functions otherwise.
+    /// Note that saving a TOML store fails while it contains `null` values, which TOML cannot represent.
+    /// The functions registered with [`Builder::register_serialize_fn`](crate::Builder::register_serialize_fn)
+    /// are never inferred from the extension.
+    ///
+    /// An existing store file that cannot be read in the inferred format, for instance a `settings.toml`
+    /// written as JSON by a previous version, is read and saved with the default functions instead.
+    ///
     /// # Examples
     /// ```
     /// tauri::Builder::default()
@@ -56,16 +103,33 @@ impl<R: Runtime> StoreBuilder<R> {
     pub fn new<M: Manager<R>, P: AsRef<Path>>(manager: &M, path: P) -> Self {
         let app = manager.app_handle().clone();
         let state = app.state::<StoreState>();
-        let serialize_fn = state.default_serialize;
-        let deserialize_fn = state.default_deserialize;
+        let default_fns = (state.default_serialize, state.default_deserialize);
+        let inferred_fns = crate::formats::infer(path.as_ref());
+        let (serialize_fn, deserialize_fn) = inferred_fns.unwrap_or(default_fns);
         Self {
             app,
             path: path.as_ref().to_path_buf(),
             defaults: None,
             serialize_fn,
             deserialize_fn,
+            fallback_fns: inferred_fns.map(|_| default_fns),
             auto_save: Some(Duration::from_millis(100)),
+            purge_interval: Some(Duration::from_secs(60)),
             create_new: false,
+            backup: false,
+            schema: Schema::default(),
+            #[cfg(feature = "encryption")]
+            encryption_key: None,
+            #[cfg(feature = "watch")]
+            watch: false,
+            #[cfg(feature = "file-lock")]
+            file_lock: false,
+            #[cfg(feature = "validation")]
+            json_schema: None,
+            #[cfg(feature = "validation")]
+            key_schemas: HashMap::new(),
+            #[cfg(feature = "validation")]
+            replace_invalid: false,
         }
     }
 
@@ -127,6 +191,7 @@ impl<R: Runtime> StoreBuilder<R> {
     /// ```
     pub fn serialize(mut self, serialize: SerializeFn) -> Self {
         self.serialize_fn = serialize;
+        self.fallback_fns = None;
         self
     }
 
@@ -145,6 +210,7 @@ impl<R: Runtime> StoreBuilder<R> {
     /// ```
     pub fn deserialize(mut self, deserialize: DeserializeFn) -> Self {
         self.deserialize_fn = deserialize;
+        self.fallback_fns = None;
         self
     }
 
@@ -172,12 +238,262 @@ impl<R: Runtime> StoreBuilder<R> {
         self
     }
 
+    /// Removes the [expired entries](Store::set_with_ttl) from the store at the given interval, 60 seconds by default.
+    ///
+    /// The removal only starts once the store has an entry with a time-to-live.
+    ///
+    /// Expired entries are always treated as absent, this only frees them and emits their `store://change` event.
+    ///
+    /// # Examples
+    /// ```
+    /// tauri::Builder::default()
+    ///   .plugin(tauri_plugin_store::Builder::default().build())
+    ///   .setup(|app| {
+    ///     let store = tauri_plugin_store::StoreBuilder::new(app, "cache.json")
+    ///       .purge_interval(std::time::Duration::from_secs(5))
+    ///       .build()?;
+    ///     Ok(())
+    ///   });
+    /// ```
+    pub fn purge_interval(mut self, interval: Duration) -> Self {
+        self.purge_interval = Some(interval);
+        self
+    }
+
+    /// Disable the periodic removal of expired entries, which are then only removed when the store is loaded.
+    pub fn disable_purge_interval(mut self) -> Self {
+        self.purge_interval = None;
+        self
+    }
+
     /// Force create a new store with default values even if it already exists.
     pub fn create_new(mut self) -> Self {
         self.create_new = true;
         self
     }
 
+    /// Keeps a copy of the previous save next to the store file, with a `.bak` extension appended.
+    ///
+    /// If the store file cannot be deserialized when the store is loaded,
+    /// the backup is loaded instead and a `store://recovery` event is emitted.
+    ///
+    /// # Examples
+    /// ```
+    /// tauri::Builder::default()
+    ///   .plugin(tauri_plugin_store::Builder::default().build())
+    ///   .setup(|app| {
+    ///     let store = tauri_plugin_store::StoreBuilder::new(app, "store.json")
+    ///       .backup()
+    ///       .build()?;
+    ///     Ok(())
+    ///   });
+    /// ```
+    pub fn backup(mut self) -> Self {
+        self.backup = true;
+        self
+    }
+
+    /// Sets the schema version of the store, which is persisted in the store file under the `$version` key.
+    ///
+    /// Defaults to the highest version of the [migrations](Self::migration).
+    ///
+    /// A store file with a newer version, saved by a newer version of the app, is loaded without migrations
+    /// and keeps its version when saved.
+    ///
+    /// # Examples
+    /// ```
+    /// tauri::Builder::default()
+    ///   .plugin(tauri_plugin_store::Builder::default().build())
+    ///   .setup(|app| {
+    ///     let store = tauri_plugin_store::StoreBuilder::new(app, "store.json")
+    ///       .schema_version(1)
+    ///       .build()?;
+    ///     Ok(())
+    ///   });
+    /// ```
+    pub fn schema_version(mut self, version: u32) -> Self {
+        self.schema.version = Some(version);
+        self
+    }
+
+    /// Adds a migration that upgrades the store to `version`.
+    ///
+    /// When the store is loaded, the migrations between the version persisted in the store file
+    /// and the [schema version](Self::schema_version) run in order on the stored values,
+    /// before the defaults are applied. Stores saved without a version are at version 0.
+    ///
+    /// # Examples
+    /// ```
+    /// tauri::Builder::default()
+    ///   .plugin(tauri_plugin_store::Builder::default().build())
+    ///   .setup(|app| {
+    ///     let store = tauri_plugin_store::StoreBuilder::new(app, "store.json")
+    ///       .migration(1, |store| {
+    ///         if let Some(theme) = store.remove("darkMode") {
+    ///           store.insert("theme".to_string(), if theme == true { "dark" } else { "light" }.into());
+    ///         }
+    ///       })
+    ///       .build()?;
+    ///     Ok(())
+    ///   });
+    /// ```
+    pub fn migration<F>(mut self, version: u32, migrate: F) -> Self
+    where
+        F: Fn(&mut HashMap<String, JsonValue>) + Send + Sync + 'static,
+    {
+        let index = self
+            .schema
+            .migrations
+            .partition_point(|(v, _)| *v <= version);
+        self.schema
+            .migrations
+            .insert(index, (version, Arc::new(migrate)));
+        self
+    }
+
+    /// Encrypts the store file with XChaCha20-Poly1305 using the key returned by `key`,
+    /// which is called whenever the store is loaded or saved.
+    ///
+    /// Encryption applies on top of the [serialize](Self::serialize) and [deserialize](Self::deserialize) functions.
+    /// A store file that is not encrypted with this key fails to load with [`Error::Encryption`](crate::Error::Encryption),
+    /// as does any store when `key` fails, and the store file is left untouched.
+    ///
+    /// # Examples
+    /// ```
+    /// tauri::Builder::default()
+    ///   .plugin(tauri_plugin_store::Builder::default().build())
+    ///   .setup(|app| {
+    ///     let store = tauri_plugin_store::StoreBuilder::new(app, "secrets.bin")
+    ///       // read the key from the OS keyring or a stronghold vault instead
+    ///       .encryption(|| Ok([0; 32]))
+    ///       .build()?;
+    ///     Ok(())
+    ///   });
+    /// ```
+    #[cfg(feature = "encryption")]
+    pub fn encryption<F>(mut self, key: F) -> Self
+    where
+        F: Fn() -> std::result::Result<[u8; 32], Box<dyn std::error::Error + Send + Sync>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.encryption_key = Some(Arc::new(key));
+        self
+    }
+
+    #[cfg(feature = "encryption")]
+    pub(crate) fn encryption_key(mut self, key: crate::EncryptionKeyFn) -> Self {
+        self.encryption_key = Some(key);
+        self
+    }
+
+    /// Watches the store file and picks up the changes made to it by other processes,
+    /// for instance another instance of the app or a text editor.
+    ///
+    /// A `store://change` event is emitted for each key updated from the store file.
+    /// Keys that were also changed in memory since the last save keep their value in memory,
+    /// which overwrites the store file on the next save, and a `store://conflict` event is emitted.
+    ///
+    /// Combine it with `file_lock`, behind the `file-lock` feature, when several processes save the store.
+    ///
+    /// # Examples
+    /// ```
+    /// tauri::Builder::default()
+    ///   .plugin(tauri_plugin_store::Builder::default().build())
+    ///   .setup(|app| {
+    ///     let store = tauri_plugin_store::StoreBuilder::new(app, "store.json")
+    ///       .watch()
+    ///       .build()?;
+    ///     Ok(())
+    ///   });
+    /// ```
+    #[cfg(feature = "watch")]
+    pub fn watch(mut self) -> Self {
+        self.watch = true;
+        self
+    }
+
+    /// Holds an advisory lock on a `.lock` file next to the store file while the store is saved or read,
+    /// so processes that also lock it never save the store at the same time or read it while it is being replaced.
+    ///
+    /// # Examples
+    /// ```
+    /// tauri::Builder::default()
+    ///   .plugin(tauri_plugin_store::Builder::default().build())
+    ///   .setup(|app| {
+    ///     let store = tauri_plugin_store::StoreBuilder::new(app, "store.json")
+    ///       .file_lock()
+    ///       .build()?;
+    ///     Ok(())
+    ///   });
+    /// ```
+    #[cfg(feature = "file-lock")]
+    pub fn file_lock(mut self) -> Self {
+        self.file_lock = true;
+        self
+    }
+
+    /// Validates the whole store, as a JSON object, against a [JSON Schema](https://json-schema.org).
+    ///
+    /// The store is validated whenever it is loaded and on every change made with [`Store::try_set`],
+    /// the JSON Pointer methods, [`Store::patch`] and [`Store::transaction`], which fail with
+    /// [`Error::Validation`](crate::Error::Validation) and leave the store untouched if the result is invalid.
+    /// Writes from the webview always go through validation.
+    ///
+    /// A store file with invalid values fails to load with [`Error::Validation`](crate::Error::Validation)
+    /// and is left untouched, unless [`replace_invalid_with_defaults`](Self::replace_invalid_with_defaults) is set.
+    ///
+    /// # Examples
+    /// ```
+    /// tauri::Builder::default()
+    ///   .plugin(tauri_plugin_store::Builder::default().build())
+    ///   .setup(|app| {
+    ///     let store = tauri_plugin_store::StoreBuilder::new(app, "settings.json")
+    ///       .json_schema(serde_json::json!({
+    ///         "type": "object",
+    ///         "properties": {
+    ///           "theme": { "enum": ["light", "dark"] }
+    ///         }
+    ///       }))
+    ///       .build()?;
+    ///     Ok(())
+    ///   });
+    /// ```
+    #[cfg(feature = "validation")]
+    pub fn json_schema(mut self, schema: JsonValue) -> Self {
+        self.json_schema = Some(schema);
+        self
+    }
+
+    /// Validates the value of `key` against a [JSON Schema](https://json-schema.org),
+    /// see [`json_schema`](Self::json_schema).
+    ///
+    /// # Examples
+    /// ```
+    /// tauri::Builder::default()
+    ///   .plugin(tauri_plugin_store::Builder::default().build())
+    ///   .setup(|app| {
+    ///     let store = tauri_plugin_store::StoreBuilder::new(app, "settings.json")
+    ///       .key_schema("fontSize", serde_json::json!({ "type": "integer", "minimum": 8, "maximum": 72 }))
+    ///       .build()?;
+    ///     Ok(())
+    ///   });
+    /// ```
+    #[cfg(feature = "validation")]
+    pub fn key_schema(mut self, key: impl Into<String>, schema: JsonValue) -> Self {
+        self.key_schemas.insert(key.into(), schema);
+        self
+    }
+
+    /// Replaces the invalid values of the store file with their default value when the store is loaded,
+    /// instead of failing to load it.
+    #[cfg(feature = "validation")]
+    pub fn replace_invalid_with_defaults(mut self) -> Self {
+        self.replace_invalid = true;
+        self
+    }
+
     pub(crate) fn build_inner(mut self) -> crate::Result<(Arc<Store<R>>, ResourceId)> {
         let stores = self.app.state::<StoreState>().stores.clone();
         let mut stores = stores.lock().unwrap();
@@ -192,6 +508,17 @@ impl<R: Runtime> StoreBuilder<R> {
             return Ok((self.app.resources_table().get(*rid).unwrap(), *rid));
         }
 
+        #[cfg(feature = "validation")]
+        let validation = if self.json_schema.is_some() || !self.key_schemas.is_empty() {
+            Some(Arc::new(crate::validation::Validation::new(
+                self.json_schema.as_ref(),
+                &self.key_schemas,
+                self.replace_invalid,
+            )?))
+        } else {
+            None
+        };
+
         // if stores.contains_key(&self.path) {
         //     return Err(crate::Error::AlreadyExists(self.path));
         // }
@@ -202,19 +529,47 @@ impl<R: Runtime> StoreBuilder<R> {
             self.defaults.take(),
             self.serialize_fn,
             self.deserialize_fn,
+            self.fallback_fns,
+            self.backup,
+            self.schema,
+            #[cfg(feature = "file-lock")]
+            self.file_lock,
+            #[cfg(feature = "encryption")]
+            self.encryption_key.take(),
+            #[cfg(feature = "watch")]
+            self.watch,
+            #[cfg(feature = "validation")]
+            validation,
         );
 
         if !self.create_new {
-            let _ = store_inner.load();
+            store_inner.load_or_recover()?;
         }
 
-        let store = Store {
-            auto_save: self.auto_save,
-            auto_save_debounce_sender: Arc::new(Mutex::new(None)),
-            store: Arc::new(Mutex::new(store_inner)),
+        let store_inner = Arc::new(Mutex::new(store_inner));
+
+        #[cfg(feature = "watch")]
+        let watcher = if self.watch {
+            let store_inner = Arc::downgrade(&store_inner);
+            Some(crate::watcher::watch(&self.path, move || {
+                if let Some(store_inner) = store_inner.upgrade() {
+                    store_inner.lock().unwrap().sync_from_disk();
+                }
+            })?)
+        } else {
+            None
         };
 
-        let store = Arc::new(store);
+        let store = Arc::new_cyclic(|this| Store {
+            auto_save: self.auto_save,
+            auto_save_debounce_sender: Arc::new(Mutex::new(None)),
+            store: store_inner,
+            purge_interval: Mutex::new(self.purge_interval),
+            this: this.clone(),
+            #[cfg(feature = "watch")]
+            _watcher: watcher,
+        });
+        store.start_purge();
         let rid = self.app.resources_table().add_arc(store.clone());
         stores.insert(self.path, rid);
 
@@ -240,6 +595,54 @@ impl<R: Runtime> StoreBuilder<R> {
     }
 }
 
+/// The schema version of a store and the migrations to reach it.
+#[derive(Clone, Default)]
+struct Schema {
+    version: Option<u32>,
+    /// Sorted by version.
+    migrations: Vec<(u32, MigrationFn)>,
+}
+
+impl Schema {
+    fn version(&self) -> Option<u32> {
+        self.version
+            .or_else(|| self.migrations.last().map(|(version, _)| *version))
+    }
+
+    /// Runs the migrations the values of a store file at version `current` need,
+    /// returning the version of the migrated values and whether the store must be saved to persist it.
+    ///
+    /// Values with a newer version than the schema are left untouched and keep their version.
+    fn migrate(
+        &self,
+        path: &Path,
+        current: Option<u32>,
+        values: &mut HashMap<String, JsonValue>,
+    ) -> (Option<u32>, bool) {
+        let Some(version) = self.version() else {
+            return (current, false);
+        };
+
+        let current = current.unwrap_or(0);
+        if current > version {
+            log::warn!(
+                "store {path:?} has version {current} which is newer than the supported version {version}"
+            );
+            return (Some(current), false);
+        }
+
+        for (_, migrate) in self
+            .migrations
+            .iter()
+            .filter(|(v, _)| current < *v && *v <= version)
+        {
+            migrate(values);
+        }
+
+        (Some(version), current != version)
+    }
+}
+
 enum AutoSaveMessage {
     Reset,
     Cancel,
@@ -250,72 +653,438 @@ struct StoreInner<R: Runtime> {
     app: AppHandle<R>,
     path: PathBuf,
     cache: HashMap<String, JsonValue>,
+    /// Expiry timestamps of the entries with a time-to-live, in milliseconds since the Unix epoch.
+    expires: HashMap<String, u64>,
+    listeners: Arc<Mutex<Listeners>>,
     defaults: Option<HashMap<String, JsonValue>>,
     serialize_fn: SerializeFn,
     deserialize_fn: DeserializeFn,
+    fallback_fns: Option<(SerializeFn, DeserializeFn)>,
+    backup: bool,
+    schema: Schema,
+    /// The schema version persisted when the store is saved.
+    version: Option<u32>,
+    #[cfg(feature = "file-lock")]
+    file_lock: bool,
+    #[cfg(feature = "encryption")]
+    encryption_key: Option<crate::EncryptionKeyFn>,
+    /// The values last loaded from or saved to the store file, when it is watched.
+    #[cfg(feature = "watch")]
+    disk: Option<HashMap<String, JsonValue>>,
+    #[cfg(feature = "validation")]
+    validation: Option<Arc<crate::validation::Validation>>,
 }
 
 impl<R: Runtime> StoreInner<R> {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         app: AppHandle<R>,
         path: PathBuf,
         defaults: Option<HashMap<String, JsonValue>>,
         serialize_fn: SerializeFn,
         deserialize_fn: DeserializeFn,
+        fallback_fns: Option<(SerializeFn, DeserializeFn)>,
+        backup: bool,
+        schema: Schema,
+        #[cfg(feature = "file-lock")] file_lock: bool,
+        #[cfg(feature = "encryption")] encryption_key: Option<crate::EncryptionKeyFn>,
+        #[cfg(feature = "watch")] watch: bool,
+        #[cfg(feature = "validation")] validation: Option<Arc<crate::validation::Validation>>,
     ) -> Self {
         Self {
             app,
             path,
             cache: defaults.clone().unwrap_or_default(),
+            expires: HashMap::new(),
+            listeners: Default::default(),
             defaults,
             serialize_fn,
             deserialize_fn,
+            fallback_fns,
+            backup,
+            version: schema.version(),
+            schema,
+            #[cfg(feature = "file-lock")]
+            file_lock,
+            #[cfg(feature = "encryption")]
+            encryption_key,
+            #[cfg(feature = "watch")]
+            disk: watch.then(HashMap::new),
+            #[cfg(feature = "validation")]
+            validation,
         }
     }
 
     /// Saves the store to disk at the store's `path`.
-    pub fn save(&self) -> crate::Result<()> {
-        fs::create_dir_all(self.path.parent().expect("invalid store path"))?;
+    ///
+    /// The store is written to a temporary file which then replaces the store file,
+    /// so a crash while saving never leaves a truncated store behind.
+    pub fn save(&mut self) -> crate::Result<()> {
+        let parent = self.path.parent().expect("invalid store path");
+        fs::create_dir_all(parent)?;
+        let _lock = self.lock(true)?;
+
+        let bytes = if self.version.is_none()
+            && self.expires.is_empty()
+            && !self.cache.keys().any(|key| is_reserved(key))
+        {
+            (self.serialize_fn)(&self.cache)
+        } else {
+            let mut cache = self.cache.clone();
+            escape_reserved(&mut cache);
+            if let Some(version) = self.version {
+                cache.insert(VERSION_KEY.into(), version.into());
+            }
+            if !self.expires.is_empty() {
+                let expires = self
+                    .expires
+                    .iter()
+                    .filter(|(key, _)| self.cache.contains_key(*key))
+                    .map(|(key, expires_at)| (key.clone(), (*expires_at).into()))
+                    .collect();
+                cache.insert(EXPIRES_KEY.into(), JsonValue::Object(expires));
+            }
+            (self.serialize_fn)(&cache)
+        }
+        .map_err(crate::Error::Serialize)?;
+
+        #[cfg(feature = "encryption")]
+        let bytes = match &self.encryption_key {
+            Some(key) => crate::encryption::encrypt(key, &bytes)?,
+            None => bytes,
+        };
 
-        let bytes = (self.serialize_fn)(&self.cache).map_err(crate::Error::Serialize)?;
-        fs::write(&self.path, bytes)?;
+        let tmp_path = sibling_path(&self.path, ".tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        drop(file);
+
+        if self.backup && self.path.exists() {
+            let backup_path = sibling_path(&self.path, ".bak");
+            fs::copy(&self.path, &backup_path)?;
+            OpenOptions::new()
+                .write(true)
+                .open(&backup_path)?
+                .sync_all()?;
+        }
+
+        fs::rename(&tmp_path, &self.path)?;
+        // persist the rename itself
+        #[cfg(unix)]
+        File::open(parent)?.sync_all()?;
+
+        #[cfg(feature = "watch")]
+        if let Some(disk) = &mut self.disk {
+            disk.clone_from(&self.cache);
+        }
 
         Ok(())
     }
 
     /// Update the store from the on-disk state
     pub fn load(&mut self) -> crate::Result<()> {
-        let bytes = fs::read(&self.path)?;
+        let StoreFile {
+            values: mut cache,
+            version,
+            mut expires,
+        } = match self.read(&self.path) {
+            Err(crate::Error::Deserialize(e)) => self
+                .read_with_fallback_fns()
+                .ok_or(crate::Error::Deserialize(e))?,
+            file => file?,
+        };
+        remove_expired(&mut cache, &mut expires, now());
+        let (version, migrated) = self.schema.migrate(&self.path, version, &mut cache);
+        self.validate_loaded(&mut cache)?;
+        self.version = version;
+        #[cfg(feature = "watch")]
+        if let Some(disk) = &mut self.disk {
+            disk.clone_from(&cache);
+        }
+        for key in cache.keys() {
+            self.expires.remove(key);
+        }
+        self.expires.extend(expires);
+        self.cache.extend(cache);
+        self.purge_expired();
+
+        // persist the migrated values so the migrations do not run again on the next load
+        if migrated {
+            if let Err(e) = self.save() {
+                log::error!("failed to save migrated store {:?}: {e}", self.path);
+            }
+        }
 
-        self.cache
-            .extend((self.deserialize_fn)(&bytes).map_err(crate::Error::Deserialize)?);
+        Ok(())
+    }
+
+    /// Reads the store file with the default functions when its format was inferred from its extension,
+    /// and keeps using them if it was written with them.
+    fn read_with_fallback_fns(&mut self) -> Option<StoreFile> {
+        let (serialize_fn, deserialize_fn) = self.fallback_fns?;
+        let inferred_deserialize_fn = std::mem::replace(&mut self.deserialize_fn, deserialize_fn);
+        match self.read(&self.path) {
+            Ok(file) => {
+                log::warn!(
+                    "store {:?} was not written in the format of its extension, keeping its format",
+                    self.path
+                );
+                self.serialize_fn = serialize_fn;
+                self.fallback_fns = None;
+                Some(file)
+            }
+            Err(_) => {
+                self.deserialize_fn = inferred_deserialize_fn;
+                None
+            }
+        }
+    }
+
+    fn read(&self, path: &Path) -> crate::Result<StoreFile> {
+        let lock = self.lock(false)?;
+        let bytes = fs::read(path)?;
+        drop(lock);
+
+        #[cfg(feature = "encryption")]
+        let bytes = match &self.encryption_key {
+            Some(key) => crate::encryption::decrypt(key, &bytes)?,
+            None => bytes,
+        };
+
+        let values = (self.deserialize_fn)(&bytes).map_err(crate::Error::Deserialize)?;
+        Ok(StoreFile::new(values))
+    }
+
+    /// Takes the advisory lock of the store file if [file locking](StoreBuilder::file_lock) is enabled,
+    /// which is released when the returned file is dropped.
+    #[cfg(feature = "file-lock")]
+    fn lock(&self, exclusive: bool) -> crate::Result<Option<File>> {
+        if !self.file_lock {
+            return Ok(None);
+        }
+
+        let file = match OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(sibling_path(&self.path, ".lock"))
+        {
+            Ok(file) => file,
+            // the store directory does not exist yet, so there is nothing to read
+            Err(e) if !exclusive && e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if exclusive {
+            FileExt::lock_exclusive(&file)?;
+        } else {
+            FileExt::lock_shared(&file)?;
+        }
+        Ok(Some(file))
+    }
+
+    #[cfg(not(feature = "file-lock"))]
+    fn lock(&self, _exclusive: bool) -> crate::Result<Option<File>> {
+        Ok(None)
+    }
+
+    /// Merges the changes made to the store file by another process.
+    #[cfg(feature = "watch")]
+    fn sync_from_disk(&mut self) {
+        let Some(base) = self.disk.take() else {
+            return;
+        };
+
+        let StoreFile {
+            values: mut disk,
+            version,
+            mut expires,
+        } = match self.read(&self.path) {
+            Ok(disk) => disk,
+            // the store file was removed, its keys are back to their default value
+            Err(crate::Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                StoreFile::new(HashMap::new())
+            }
+            Err(e) => {
+                // most likely written in place by another process, try again on its next change
+                log::warn!("failed to reload store {:?}: {e}", self.path);
+                self.disk = Some(base);
+                return;
+            }
+        };
+        remove_expired(&mut disk, &mut expires, now());
+        let (version, _) = self.schema.migrate(&self.path, version, &mut disk);
+        if let Err(e) = self.validate_loaded(&mut disk) {
+            log::warn!("ignoring invalid changes to store {:?}: {e}", self.path);
+            self.disk = Some(base);
+            return;
+        }
+        self.version = version;
+
+        let merge = crate::watcher::merge(&base, &disk, self.defaults.as_ref(), &mut self.cache);
+        self.disk = Some(disk);
 
+        for key in &merge.changed {
+            match expires.get(key) {
+                Some(expires_at) => self.expires.insert(key.clone(), *expires_at),
+                None => self.expires.remove(key),
+            };
+            let _ = self.emit_change_event(key, self.cache.get(key), None);
+        }
+        for key in &merge.conflicts {
+            let _ = self.emit_conflict_event(key);
+        }
+    }
+
+    #[cfg(feature = "watch")]
+    fn emit_conflict_event(&self, key: &str) -> crate::Result<()> {
+        let state = self.app.state::<StoreState>();
+        let stores = state.stores.lock().unwrap();
+        self.app.emit(
+            "store://conflict",
+            crate::ConflictPayload {
+                path: &self.path,
+                resource_id: stores.get(&self.path).copied(),
+                key,
+                local: self.cache.get(key),
+                disk: self.disk.as_ref().and_then(|disk| disk.get(key)),
+            },
+        )?;
         Ok(())
     }
 
-    /// Inserts a key-value pair into the store.
-    pub fn set(&mut self, key: impl Into<String>, value: impl Into<JsonValue>) {
+    /// Loads the store, falling back to its backup if the store file cannot be deserialized.
+    ///
+    /// The corrupted file is kept with a `.corrupted` extension appended
+    /// and a `store://recovery` event is emitted.
+    /// Other errors, like a missing encryption key, are returned and leave the store file untouched.
+    fn load_or_recover(&mut self) -> crate::Result<()> {
+        let error = match self.load() {
+            Ok(()) => return Ok(()),
+            // nothing was saved yet
+            Err(crate::Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e @ crate::Error::Deserialize(_)) => e,
+            Err(e) => return Err(e),
+        };
+        log::error!("failed to load store {:?}: {error}", self.path);
+
+        let _ = fs::rename(&self.path, sibling_path(&self.path, ".corrupted"));
+
+        let restored = self.backup
+            && match self.read(&sibling_path(&self.path, ".bak")) {
+                Ok(StoreFile {
+                    values: mut backup,
+                    version,
+                    mut expires,
+                }) => {
+                    remove_expired(&mut backup, &mut expires, now());
+                    let (version, _) = self.schema.migrate(&self.path, version, &mut backup);
+                    match self.validate_loaded(&mut backup) {
+                        Ok(()) => {
+                            self.version = version;
+                            self.expires = expires;
+                            self.cache.extend(backup);
+                            // put the backup back in place right away
+                            let _ = self.save();
+                            true
+                        }
+                        Err(_) => false,
+                    }
+                }
+                Err(_) => false,
+            };
+
+        let _ = self.app.emit(
+            "store://recovery",
+            RecoveryPayload {
+                path: &self.path,
+                error: error.to_string(),
+                restored_from_backup: restored,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Inserts a key-value pair into the store, which expires after `ttl` if given.
+    pub fn set(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<JsonValue>,
+        ttl: Option<Duration>,
+    ) {
         let key = key.into();
         let value = value.into();
         self.cache.insert(key.clone(), value.clone());
-        let _ = self.emit_change_event(&key, Some(&value));
+        self.set_ttl(&key, ttl);
+        let _ = self.emit_change_event(&key, Some(&value), None);
+    }
+
+    /// Inserts a key-value pair into the store if the store stays valid.
+    pub fn try_set(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<JsonValue>,
+        ttl: Option<Duration>,
+    ) -> crate::Result<()> {
+        let key = key.into();
+        let value = value.into();
+        self.validated(&[&key], |cache| {
+            cache.insert(key.clone(), value.clone());
+            Ok(())
+        })?;
+        self.set_ttl(&key, ttl);
+        let _ = self.emit_change_event(&key, Some(&value), None);
+        Ok(())
+    }
+
+    fn set_ttl(&mut self, key: &str, ttl: Option<Duration>) {
+        match ttl {
+            Some(ttl) => {
+                let ttl = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+                self.expires
+                    .insert(key.to_string(), now().saturating_add(ttl));
+            }
+            None => {
+                self.expires.remove(key);
+            }
+        }
+    }
+
+    /// Returns `true` if the entry of `key` has a time-to-live which elapsed by `now`.
+    fn is_expired(&self, key: &str, now: u64) -> bool {
+        self.expires
+            .get(key)
+            .is_some_and(|expires_at| *expires_at <= now)
+    }
+
+    /// Removes the expired entries, returning whether there were any.
+    pub fn purge_expired(&mut self) -> bool {
+        let expired = remove_expired(&mut self.cache, &mut self.expires, now());
+        for key in &expired {
+            let _ = self.emit_change_event(key, None, None);
+        }
+        !expired.is_empty()
     }
 
     /// Returns a reference to the value corresponding to the key.
     pub fn get(&self, key: impl AsRef<str>) -> Option<&JsonValue> {
-        self.cache.get(key.as_ref())
+        let key = key.as_ref();
+        self.cache.get(key).filter(|_| !self.is_expired(key, now()))
     }
 
     /// Returns `true` if the given `key` exists in the store.
     pub fn has(&self, key: impl AsRef<str>) -> bool {
-        self.cache.contains_key(key.as_ref())
+        self.get(key).is_some()
     }
 
     /// Removes a key-value pair from the store.
     pub fn delete(&mut self, key: impl AsRef<str>) -> bool {
+        self.purge_expired();
+        self.expires.remove(key.as_ref());
         let flag = self.cache.remove(key.as_ref()).is_some();
         if flag {
-            let _ = self.emit_change_event(key.as_ref(), None);
+            let _ = self.emit_change_event(key.as_ref(), None, None);
         }
         flag
     }
@@ -326,8 +1095,9 @@ impl<R: Runtime> StoreInner<R> {
     pub fn clear(&mut self) {
         let keys: Vec<String> = self.cache.keys().cloned().collect();
         self.cache.clear();
+        self.expires.clear();
         for key in &keys {
-            let _ = self.emit_change_event(key, None);
+            let _ = self.emit_change_event(key, None, None);
         }
     }
 
@@ -338,15 +1108,16 @@ impl<R: Runtime> StoreInner<R> {
         if let Some(defaults) = &self.defaults {
             for (key, value) in &self.cache {
                 if defaults.get(key) != Some(value) {
-                    let _ = self.emit_change_event(key, defaults.get(key));
+                    let _ = self.emit_change_event(key, defaults.get(key), None);
                 }
             }
             for (key, value) in defaults {
                 if !self.cache.contains_key(key) {
-                    let _ = self.emit_change_event(key, Some(value));
+                    let _ = self.emit_change_event(key, Some(value), None);
                 }
             }
             self.cache.clone_from(defaults);
+            self.expires.clear();
         } else {
             self.clear()
         }
@@ -354,30 +1125,223 @@ impl<R: Runtime> StoreInner<R> {
 
     /// An iterator visiting all keys in arbitrary order.
     pub fn keys(&self) -> impl Iterator<Item = &String> {
-        self.cache.keys()
+        self.entries().map(|(key, _)| key)
     }
 
     /// An iterator visiting all values in arbitrary order.
     pub fn values(&self) -> impl Iterator<Item = &JsonValue> {
-        self.cache.values()
+        self.entries().map(|(_, value)| value)
     }
 
     /// An iterator visiting all key-value pairs in arbitrary order.
     pub fn entries(&self) -> impl Iterator<Item = (&String, &JsonValue)> {
-        self.cache.iter()
+        let now = now();
+        self.cache
+            .iter()
+            .filter(move |(key, _)| !self.is_expired(key, now))
     }
 
     /// Returns the number of elements in the store.
     pub fn len(&self) -> usize {
-        self.cache.len()
+        self.entries().count()
     }
 
     /// Returns true if the store contains no elements.
     pub fn is_empty(&self) -> bool {
-        self.cache.is_empty()
+        self.entries().next().is_none()
+    }
+
+    /// Sets the value at the given JSON Pointer, creating the missing parent objects.
+    ///
+    /// The first token of the pointer is the store key, e.g. `/settings/editor/fontSize`.
+    pub fn set_pointer(&mut self, pointer: &str, value: impl Into<JsonValue>) -> crate::Result<()> {
+        let (key, _) = pointer::split(pointer)?;
+        let value = value.into();
+        self.purge_expired();
+        self.validated(&[&key], |cache| pointer::set(cache, pointer, value))?;
+        self.emit_pointer_change_event(pointer)
+    }
+
+    /// Returns a reference to the value at the given JSON Pointer.
+    pub fn get_pointer(&self, pointer: &str) -> crate::Result<Option<&JsonValue>> {
+        let (key, _) = pointer::split(pointer)?;
+        if self.is_expired(&key, now()) {
+            return Ok(None);
+        }
+        pointer::get(&self.cache, pointer)
+    }
+
+    /// Returns `true` if a value exists at the given JSON Pointer.
+    pub fn has_pointer(&self, pointer: &str) -> crate::Result<bool> {
+        Ok(self.get_pointer(pointer)?.is_some())
+    }
+
+    /// Removes the value at the given JSON Pointer.
+    pub fn delete_pointer(&mut self, pointer: &str) -> crate::Result<bool> {
+        let (key, _) = pointer::split(pointer)?;
+        self.purge_expired();
+        let deleted = self.validated(&[&key], |cache| pointer::delete(cache, pointer))?;
+        if !self.cache.contains_key(&key) {
+            self.expires.remove(&key);
+        }
+        if deleted {
+            let _ = self.emit_pointer_change_event(pointer);
+        }
+        Ok(deleted)
+    }
+
+    /// Applies a JSON Merge Patch ([RFC 7396](https://www.rfc-editor.org/rfc/rfc7396)) to the store.
+    ///
+    /// `null` values in the patch remove the corresponding entries, objects are merged recursively
+    /// and every other value replaces the current one. A change event is emitted for each changed value.
+    pub fn patch(&mut self, patch: JsonValue) -> crate::Result<()> {
+        let keys: Vec<String> = patch
+            .as_object()
+            .map(|patch| patch.keys().cloned().collect())
+            .unwrap_or_default();
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        self.purge_expired();
+        let changed = self.validated(&keys, |cache| pointer::merge_patch(cache, patch))?;
+        for key in keys {
+            if !self.cache.contains_key(key) {
+                self.expires.remove(key);
+            }
+        }
+        for pointer in changed {
+            let _ = self.emit_pointer_change_event(&pointer);
+        }
+        Ok(())
+    }
+
+    /// Applies the mutations of `f` at once, or none of them if it returns an error
+    /// or if the store ends up invalid, which is the only error of the outer result.
+    ///
+    /// A single `store://batch-change` event is emitted for all the keys that changed.
+    pub fn transaction<T, E>(
+        &mut self,
+        f: impl FnOnce(&mut Transaction<'_>) -> Result<T, E>,
+    ) -> crate::Result<Result<T, E>> {
+        self.purge_expired();
+        let mut transaction = Transaction {
+            cache: self.cache.clone(),
+            defaults: self.defaults.as_ref(),
+        };
+        let result = match f(&mut transaction) {
+            Ok(result) => result,
+            Err(e) => return Ok(Err(e)),
+        };
+
+        let changed: Vec<String> = transaction::changed_keys(&self.cache, &transaction.cache)
+            .into_iter()
+            .map(String::from)
+            .collect();
+        #[cfg(feature = "validation")]
+        if let Some(validation) = &self.validation {
+            validation.validate(&transaction.cache, changed.iter().map(String::as_str))?;
+        }
+
+        self.cache = transaction.cache;
+        // the changed keys are set or deleted without a time-to-live
+        for key in &changed {
+            self.expires.remove(key);
+        }
+        if !changed.is_empty() {
+            let changed: Vec<&str> = changed.iter().map(String::as_str).collect();
+            let _ = self.emit_batch_change_event(&changed);
+        }
+        Ok(Ok(result))
+    }
+
+    /// Applies `f` to the cache, reverting the values of `keys` if the store ends up invalid.
+    fn validated<T>(
+        &mut self,
+        keys: &[&str],
+        f: impl FnOnce(&mut HashMap<String, JsonValue>) -> crate::Result<T>,
+    ) -> crate::Result<T> {
+        #[cfg(feature = "validation")]
+        if let Some(validation) = self.validation.clone() {
+            let previous: Vec<(&str, Option<JsonValue>)> = keys
+                .iter()
+                .map(|key| (*key, self.cache.get(*key).cloned()))
+                .collect();
+            let result = f(&mut self.cache).and_then(|value| {
+                validation.validate(&self.cache, keys.iter().copied())?;
+                Ok(value)
+            });
+            if result.is_err() {
+                for (key, value) in previous {
+                    match value {
+                        Some(value) => self.cache.insert(key.to_string(), value),
+                        None => self.cache.remove(key),
+                    };
+                }
+            }
+            return result;
+        }
+
+        #[cfg(not(feature = "validation"))]
+        let _ = keys;
+        f(&mut self.cache)
+    }
+
+    /// Validates the values loaded from the store file against the schemas of the store.
+    fn validate_loaded(&self, values: &mut HashMap<String, JsonValue>) -> crate::Result<()> {
+        #[cfg(feature = "validation")]
+        if let Some(validation) = &self.validation {
+            validation.validate_loaded(values, self.defaults.as_ref())?;
+        }
+
+        #[cfg(not(feature = "validation"))]
+        let _ = values;
+        Ok(())
+    }
+
+    fn emit_batch_change_event(&self, keys: &[&str]) -> crate::Result<()> {
+        let listeners = self.listeners.lock().unwrap();
+        for key in keys {
+            listeners.notify(key, self.cache.get(*key));
+        }
+        drop(listeners);
+
+        let state = self.app.state::<StoreState>();
+        let stores = state.stores.lock().unwrap();
+        self.app.emit(
+            "store://batch-change",
+            BatchChangePayload {
+                path: &self.path,
+                resource_id: stores.get(&self.path).copied(),
+                changes: keys
+                    .iter()
+                    .map(|&key| {
+                        let value = self.cache.get(key);
+                        KeyChange {
+                            key,
+                            value,
+                            exists: value.is_some(),
+                        }
+                    })
+                    .collect(),
+            },
+        )?;
+        Ok(())
+    }
+
+    fn emit_pointer_change_event(&self, pointer: &str) -> crate::Result<()> {
+        let (key, tokens) = pointer::split(pointer)?;
+        let nested = (!tokens.is_empty()).then_some(pointer);
+        self.emit_change_event(&key, self.cache.get(&key), nested)
     }
 
-    fn emit_change_event(&self, key: &str, value: Option<&JsonValue>) -> crate::Result<()> {
+    /// Emits `store://change` with the new value of `key`,
+    /// along with the pointer of the nested value that changed if only part of it did.
+    fn emit_change_event(
+        &self,
+        key: &str,
+        value: Option<&JsonValue>,
+        pointer: Option<&str>,
+    ) -> crate::Result<()> {
+        self.listeners.lock().unwrap().notify(key, value);
+
         let state = self.app.state::<StoreState>();
         let stores = state.stores.lock().unwrap();
         let exists = value.is_some();
@@ -389,12 +1353,129 @@ impl<R: Runtime> StoreInner<R> {
                 key,
                 value,
                 exists,
+                pointer,
             },
         )?;
         Ok(())
     }
 }
 
+/// The values of a store file, without the store metadata.
+struct StoreFile {
+    values: HashMap<String, JsonValue>,
+    version: Option<u32>,
+    expires: HashMap<String, u64>,
+}
+
+impl StoreFile {
+    fn new(mut values: HashMap<String, JsonValue>) -> Self {
+        let version = values
+            .remove(VERSION_KEY)
+            .and_then(|v| v.as_u64())
+            .map(|v| u32::try_from(v).unwrap_or(u32::MAX));
+        let expires = take_expires(&mut values);
+        unescape_reserved(&mut values);
+        Self {
+            values,
+            version,
+            expires,
+        }
+    }
+}
+
+/// Returns `true` if `key` is a [reserved key](RESERVED_KEYS) or an escaped one.
+fn is_reserved(key: &str) -> bool {
+    let name = key.trim_start_matches('$');
+    name.len() < key.len() && RESERVED_KEYS.iter().any(|reserved| &reserved[1..] == name)
+}
+
+/// Prepends a `$` to the stored keys which would be read back as store metadata.
+fn escape_reserved(values: &mut HashMap<String, JsonValue>) {
+    let keys: Vec<String> = values
+        .keys()
+        .filter(|key| is_reserved(key))
+        .cloned()
+        .collect();
+    let entries: Vec<(String, JsonValue)> = keys
+        .into_iter()
+        .filter_map(|key| values.remove_entry(&key))
+        .collect();
+    for (key, value) in entries {
+        values.insert(format!("${key}"), value);
+    }
+}
+
+/// Reverts [`escape_reserved`] once the store metadata is removed from the values of a store file.
+fn unescape_reserved(values: &mut HashMap<String, JsonValue>) {
+    let keys: Vec<String> = values
+        .keys()
+        .filter(|key| is_reserved(key))
+        .cloned()
+        .collect();
+    let entries: Vec<(String, JsonValue)> = keys
+        .into_iter()
+        .filter_map(|key| values.remove_entry(&key))
+        .collect();
+    for (key, value) in entries {
+        values.insert(key[1..].to_string(), value);
+    }
+}
+
+/// Milliseconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| u64::try_from(now.as_millis()).unwrap_or(u64::MAX))
+}
+
+/// Removes the expiry timestamps from the values loaded from a store file.
+fn take_expires(values: &mut HashMap<String, JsonValue>) -> HashMap<String, u64> {
+    let Some(JsonValue::Object(expires)) = values.remove(EXPIRES_KEY) else {
+        return HashMap::new();
+    };
+    expires
+        .into_iter()
+        .filter_map(|(key, expires_at)| Some((key, expires_at.as_u64()?)))
+        .collect()
+}
+
+/// Removes the entries which expired by `now`, returning their keys.
+fn remove_expired(
+    values: &mut HashMap<String, JsonValue>,
+    expires: &mut HashMap<String, u64>,
+    now: u64,
+) -> Vec<String> {
+    let mut expired = Vec::new();
+    expires.retain(|key, expires_at| {
+        let keep = *expires_at > now;
+        if !keep && values.remove(key).is_some() {
+            expired.push(key.clone());
+        }
+        keep
+    });
+    expired
+}
+
+/// Periodically removes the expired entries of `store` until it is dropped.
+fn spawn_purge<R: Runtime>(store: Weak<Store<R>>, interval: Duration) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            sleep(interval).await;
+            let Some(store) = store.upgrade() else {
+                return;
+            };
+            store.purge_expired();
+        }
+    });
+}
+
+/// `path` with `suffix` appended to its file name.
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
 impl<R: Runtime> std::fmt::Debug for StoreInner<R> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Store")
@@ -408,6 +1489,11 @@ pub struct Store<R: Runtime> {
     auto_save: Option<Duration>,
     auto_save_debounce_sender: Arc<Mutex<Option<UnboundedSender<AutoSaveMessage>>>>,
     store: Arc<Mutex<StoreInner<R>>>,
+    /// The interval at which the expired entries are removed, taken once it starts.
+    purge_interval: Mutex<Option<Duration>>,
+    this: Weak<Store<R>>,
+    #[cfg(feature = "watch")]
+    _watcher: Option<crate::watcher::StoreWatcher>,
 }
 
 impl<R: Runtime> Resource for Store<R> {
@@ -420,19 +1506,135 @@ impl<R: Runtime> Resource for Store<R> {
 }
 
 impl<R: Runtime> Store<R> {
-    // /// Do something with the inner store,
-    // /// useful for batching some work if you need higher performance
-    // pub fn with_store<T>(&self, f: impl FnOnce(&mut StoreInner<R>) -> T) -> T {
-    //     let mut store = self.store.lock().unwrap();
-    //     f(&mut store)
-    // }
-
     /// Inserts a key-value pair into the store.
     pub fn set(&self, key: impl Into<String>, value: impl Into<JsonValue>) {
-        self.store.lock().unwrap().set(key.into(), value.into());
+        self.store
+            .lock()
+            .unwrap()
+            .set(key.into(), value.into(), None);
         let _ = self.trigger_auto_save();
     }
 
+    /// Inserts a key-value pair into the store which expires after `ttl`.
+    ///
+    /// The expiry is persisted in the store file under the `$expires` key.
+    /// Once expired, the entry is treated as absent and it is removed when the store is loaded
+    /// and at the [purge interval](StoreBuilder::purge_interval).
+    /// Setting the key again without a time-to-live makes it permanent.
+    ///
+    /// # Examples
+    /// ```
+    /// use tauri_plugin_store::StoreExt;
+    ///
+    /// tauri::Builder::default()
+    ///   .plugin(tauri_plugin_store::Builder::default().build())
+    ///   .setup(|app| {
+    ///     let store = app.store("cache.json")?;
+    ///     store.set_with_ttl("token", "secret", std::time::Duration::from_secs(3600));
+    ///     Ok(())
+    ///   });
+    /// ```
+    pub fn set_with_ttl(&self, key: impl Into<String>, value: impl Into<JsonValue>, ttl: Duration) {
+        self.store
+            .lock()
+            .unwrap()
+            .set(key.into(), value.into(), Some(ttl));
+        self.start_purge();
+        let _ = self.trigger_auto_save();
+    }
+
+    /// Inserts a key-value pair into the store, failing if the store would no longer match its
+    /// [JSON Schema](StoreBuilder::json_schema), unlike [`set`](Self::set) which does not validate values.
+    pub fn try_set(
+        &self,
+        key: impl Into<String>,
+        value: impl Into<JsonValue>,
+    ) -> crate::Result<()> {
+        self.store.lock().unwrap().try_set(key, value, None)?;
+        let _ = self.trigger_auto_save();
+        Ok(())
+    }
+
+    /// Like [`try_set`](Self::try_set), with an entry which expires after `ttl`, see [`set_with_ttl`](Self::set_with_ttl).
+    pub fn try_set_with_ttl(
+        &self,
+        key: impl Into<String>,
+        value: impl Into<JsonValue>,
+        ttl: Duration,
+    ) -> crate::Result<()> {
+        self.store.lock().unwrap().try_set(key, value, Some(ttl))?;
+        self.start_purge();
+        let _ = self.trigger_auto_save();
+        Ok(())
+    }
+
+    /// Calls `f` with the key and the new value, or `None` if it was removed, whenever a value of the store changes.
+    ///
+    /// The handler is called on a background task, in order, and it may use the store.
+    ///
+    /// # Examples
+    /// ```
+    /// use tauri_plugin_store::StoreExt;
+    ///
+    /// tauri::Builder::default()
+    ///   .plugin(tauri_plugin_store::Builder::default().build())
+    ///   .setup(|app| {
+    ///     let store = app.store("store.json")?;
+    ///     let subscription = store.on_change(|key, value| {
+    ///       println!("{key} changed to {value:?}");
+    ///     });
+    ///     // later
+    ///     subscription.unsubscribe();
+    ///     Ok(())
+    ///   });
+    /// ```
+    pub fn on_change<F>(&self, f: F) -> Subscription
+    where
+        F: Fn(&str, Option<&JsonValue>) + Send + Sync + 'static,
+    {
+        self.subscribe(
+            KeyFilter::Any,
+            Arc::new(move |key: &str, value: Option<&JsonValue>| {
+                f(key, value);
+                true
+            }),
+        )
+    }
+
+    /// Calls `f` with the new value of `key`, or `None` if it was removed, whenever it changes.
+    ///
+    /// See [`on_change`](Self::on_change).
+    pub fn on_key_change<F>(&self, key: impl Into<String>, f: F) -> Subscription
+    where
+        F: Fn(Option<&JsonValue>) + Send + Sync + 'static,
+    {
+        self.subscribe(
+            KeyFilter::Key(key.into()),
+            Arc::new(move |_: &str, value: Option<&JsonValue>| {
+                f(value);
+                true
+            }),
+        )
+    }
+
+    pub(crate) fn subscribe(
+        &self,
+        filter: KeyFilter,
+        handler: crate::subscription::Handler,
+    ) -> Subscription {
+        let listeners = self.store.lock().unwrap().listeners.clone();
+        Listeners::subscribe(&listeners, filter, handler)
+    }
+
+    /// Removes the expired entries from the store, returning whether there were any.
+    pub fn purge_expired(&self) -> bool {
+        let purged = self.store.lock().unwrap().purge_expired();
+        if purged {
+            let _ = self.trigger_auto_save();
+        }
+        purged
+    }
+
     /// Returns the value for the given `key` or `None` if the key does not exist.
     pub fn get(&self, key: impl AsRef<str>) -> Option<JsonValue> {
         self.store.lock().unwrap().get(key).cloned()
@@ -452,6 +1654,97 @@ impl<R: Runtime> Store<R> {
         deleted
     }
 
+    /// Sets the value at the given JSON Pointer, creating the missing parent objects.
+    ///
+    /// The first token of the pointer is the store key, e.g. `/settings/editor/fontSize`.
+    pub fn set_pointer(
+        &self,
+        pointer: impl AsRef<str>,
+        value: impl Into<JsonValue>,
+    ) -> crate::Result<()> {
+        self.store
+            .lock()
+            .unwrap()
+            .set_pointer(pointer.as_ref(), value)?;
+        let _ = self.trigger_auto_save();
+        Ok(())
+    }
+
+    /// Returns the value at the given JSON Pointer or `None` if it does not exist.
+    pub fn get_pointer(&self, pointer: impl AsRef<str>) -> crate::Result<Option<JsonValue>> {
+        Ok(self
+            .store
+            .lock()
+            .unwrap()
+            .get_pointer(pointer.as_ref())?
+            .cloned())
+    }
+
+    /// Returns `true` if a value exists at the given JSON Pointer.
+    pub fn has_pointer(&self, pointer: impl AsRef<str>) -> crate::Result<bool> {
+        self.store.lock().unwrap().has_pointer(pointer.as_ref())
+    }
+
+    /// Removes the value at the given JSON Pointer.
+    pub fn delete_pointer(&self, pointer: impl AsRef<str>) -> crate::Result<bool> {
+        let deleted = self
+            .store
+            .lock()
+            .unwrap()
+            .delete_pointer(pointer.as_ref())?;
+        if deleted {
+            let _ = self.trigger_auto_save();
+        }
+        Ok(deleted)
+    }
+
+    /// Applies a JSON Merge Patch ([RFC 7396](https://www.rfc-editor.org/rfc/rfc7396)) to the store.
+    ///
+    /// `null` values in the patch remove the corresponding entries, objects are merged recursively
+    /// and every other value replaces the current one.
+    pub fn patch(&self, patch: JsonValue) -> crate::Result<()> {
+        self.store.lock().unwrap().patch(patch)?;
+        let _ = self.trigger_auto_save();
+        Ok(())
+    }
+
+    /// Applies several mutations at once, useful for batching some work if you need higher performance.
+    ///
+    /// The mutations are applied when `f` returns, with a single `store://batch-change` event
+    /// and a single auto save. If `f` returns an error, the store is left untouched and the error
+    /// is returned in the inner result.
+    ///
+    /// The store is also left untouched if it no longer matches its [JSON Schema](StoreBuilder::json_schema),
+    /// which is the only error of the outer result.
+    ///
+    /// # Examples
+    /// ```
+    /// use tauri_plugin_store::StoreExt;
+    ///
+    /// tauri::Builder::default()
+    ///   .plugin(tauri_plugin_store::Builder::default().build())
+    ///   .setup(|app| {
+    ///     let store = app.store("store.json")?;
+    ///     store.transaction(|tx| {
+    ///       tx.set("theme", "dark");
+    ///       tx.set_pointer("/editor/fontSize", 14)?;
+    ///       tx.delete("legacy");
+    ///       Ok::<_, tauri_plugin_store::Error>(())
+    ///     })??;
+    ///     Ok(())
+    ///   });
+    /// ```
+    pub fn transaction<T, E>(
+        &self,
+        f: impl FnOnce(&mut Transaction<'_>) -> Result<T, E>,
+    ) -> crate::Result<Result<T, E>> {
+        let result = self.store.lock().unwrap().transaction(f)?;
+        if result.is_ok() {
+            let _ = self.trigger_auto_save();
+        }
+        Ok(result)
+    }
+
     /// Clears the store, removing all key-value pairs.
     ///
     /// Note: To clear the storage and reset it to its `default` value, use [`reset`](Self::reset) instead.
@@ -500,7 +1793,9 @@ impl<R: Runtime> Store<R> {
 
     /// Update the store from the on-disk state
     pub fn reload(&self) -> crate::Result<()> {
-        self.store.lock().unwrap().load()
+        self.store.lock().unwrap().load()?;
+        self.start_purge();
+        Ok(())
     }
 
     /// Saves the store to disk at the store's `path`.
@@ -560,6 +1855,15 @@ impl<R: Runtime> Store<R> {
         Ok(())
     }
 
+    /// Starts removing the expired entries at the [purge interval](StoreBuilder::purge_interval)
+    /// once the store has an entry with a time-to-live.
+    fn start_purge(&self) {
+        let mut purge_interval = self.purge_interval.lock().unwrap();
+        if purge_interval.is_some() && !self.store.lock().unwrap().expires.is_empty() {
+            spawn_purge(self.this.clone(), purge_interval.take().unwrap());
+        }
+    }
+
     fn apply_pending_auto_save(&self) {
         // Cancel and save if auto save is pending
         if let Some(sender) = self.auto_save_debounce_sender.lock().unwrap().take() {
@@ -574,3 +1878,389 @@ impl<R: Runtime> Drop for Store<R> {
         self.apply_pending_auto_save();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
+
+    fn app() -> tauri::App<MockRuntime> {
+        mock_builder()
+            .plugin(crate::Builder::default().build())
+            .build(mock_context(noop_assets()))
+            .unwrap()
+    }
+
+    /// An empty directory for the stores of the test `name`.
+    fn store_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("tauri-plugin-store-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn it_saves_atomically_and_keeps_a_backup() {
+        let app = app();
+        let path = store_dir("save").join("store.json");
+        // left behind by a save which did not complete
+        fs::write(sibling_path(&path, ".tmp"), "{ \"corrupted").unwrap();
+
+        let store = StoreBuilder::new(&app, &path)
+            .disable_auto_save()
+            .backup()
+            .build()
+            .unwrap();
+        store.set("theme", "dark");
+        store.save().unwrap();
+        assert!(!sibling_path(&path, ".tmp").exists());
+        assert!(!sibling_path(&path, ".bak").exists());
+
+        store.set("theme", "light");
+        store.save().unwrap();
+        assert!(!sibling_path(&path, ".tmp").exists());
+
+        let saved: JsonValue = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(saved, json!({ "theme": "light" }));
+        let backup: JsonValue =
+            serde_json::from_slice(&fs::read(sibling_path(&path, ".bak")).unwrap()).unwrap();
+        assert_eq!(backup, json!({ "theme": "dark" }));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn it_keeps_the_format_of_stores_written_before_it_was_inferred() {
+        let app = app();
+        let path = store_dir("inferred-format").join("settings.toml");
+        fs::write(&path, r#"{ "theme": "dark", "font": null }"#).unwrap();
+
+        let store = StoreBuilder::new(&app, &path)
+            .disable_auto_save()
+            .build()
+            .unwrap();
+        assert_eq!(store.get("theme"), Some(json!("dark")));
+        assert!(!sibling_path(&path, ".corrupted").exists());
+
+        store.set("theme", "light");
+        store.save().unwrap();
+        let saved: JsonValue = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(saved, json!({ "theme": "light", "font": null }));
+    }
+
+    #[test]
+    fn it_recovers_corrupted_stores_from_the_backup() {
+        let app = app();
+        let path = store_dir("recover").join("store.json");
+        fs::write(&path, "{ \"theme\": ").unwrap();
+        fs::write(sibling_path(&path, ".bak"), r#"{ "theme": "dark" }"#).unwrap();
+
+        let store = StoreBuilder::new(&app, &path)
+            .disable_auto_save()
+            .backup()
+            .build()
+            .unwrap();
+        assert_eq!(store.get("theme"), Some(json!("dark")));
+        assert_eq!(
+            fs::read_to_string(sibling_path(&path, ".corrupted")).unwrap(),
+            "{ \"theme\": "
+        );
+        // the backup is put back in place
+        let saved: JsonValue = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(saved, json!({ "theme": "dark" }));
+    }
+
+    #[test]
+    fn it_starts_empty_without_a_backup() {
+        let app = app();
+        let path = store_dir("no-backup").join("store.json");
+        fs::write(&path, "{ \"theme\": ").unwrap();
+
+        let store = StoreBuilder::new(&app, &path)
+            .disable_auto_save()
+            .build()
+            .unwrap();
+        assert!(store.is_empty());
+        assert!(!path.exists());
+        assert!(sibling_path(&path, ".corrupted").exists());
+    }
+
+    fn schema() -> Schema {
+        let mut schema = Schema::default();
+        for version in [2, 1] {
+            let migrate: MigrationFn = Arc::new(move |values: &mut HashMap<String, JsonValue>| {
+                values
+                    .entry("migrations".to_string())
+                    .or_insert_with(|| json!([]))
+                    .as_array_mut()
+                    .unwrap()
+                    .push(version.into());
+            });
+            let index = schema.migrations.partition_point(|(v, _)| *v <= version);
+            schema.migrations.insert(index, (version, migrate));
+        }
+        schema
+    }
+
+    #[test]
+    fn it_runs_the_migrations_in_order() {
+        let path = Path::new("store.json");
+        let schema = schema();
+        assert_eq!(schema.version(), Some(2));
+
+        let mut values = HashMap::new();
+        assert_eq!(schema.migrate(path, None, &mut values), (Some(2), true));
+        assert_eq!(values["migrations"], json!([1, 2]));
+
+        let mut values = HashMap::new();
+        assert_eq!(schema.migrate(path, Some(1), &mut values), (Some(2), true));
+        assert_eq!(values["migrations"], json!([2]));
+
+        let mut values = HashMap::new();
+        assert_eq!(schema.migrate(path, Some(2), &mut values), (Some(2), false));
+        assert!(values.is_empty());
+    }
+
+    #[test]
+    fn it_keeps_newer_versions() {
+        let path = Path::new("store.json");
+        let mut values = HashMap::new();
+        assert_eq!(
+            schema().migrate(path, Some(3), &mut values),
+            (Some(3), false)
+        );
+        assert!(values.is_empty());
+
+        assert_eq!(
+            Schema::default().migrate(path, Some(3), &mut values),
+            (Some(3), false)
+        );
+        assert_eq!(
+            Schema::default().migrate(path, None, &mut values),
+            (None, false)
+        );
+    }
+
+    #[test]
+    fn it_escapes_reserved_keys() {
+        let mut values: HashMap<String, JsonValue> = serde_json::from_value(json!({
+            "$version": 1,
+            "$$version": 2,
+            "version": 3,
+            "$other": 4
+        }))
+        .unwrap();
+        escape_reserved(&mut values);
+        assert_eq!(
+            serde_json::to_value(&values).unwrap(),
+            json!({ "$$version": 1, "$$$version": 2, "version": 3, "$other": 4 })
+        );
+
+        values.insert(VERSION_KEY.into(), 5.into());
+        let file = StoreFile::new(values);
+        assert_eq!(file.version, Some(5));
+        assert_eq!(
+            serde_json::to_value(&file.values).unwrap(),
+            json!({ "$version": 1, "$$version": 2, "version": 3, "$other": 4 })
+        );
+    }
+
+    #[test]
+    fn it_saves_newer_versions_unchanged() {
+        let app = app();
+        let path = store_dir("newer-version").join("store.json");
+        fs::write(
+            &path,
+            r#"{ "$version": 3, "$$version": "user", "theme": "dark" }"#,
+        )
+        .unwrap();
+
+        let store = StoreBuilder::new(&app, &path)
+            .disable_auto_save()
+            .migration(1, |values| {
+                values.insert("migrated".to_string(), true.into());
+            })
+            .build()
+            .unwrap();
+        assert_eq!(store.get("migrated"), None);
+        assert_eq!(store.get(VERSION_KEY), Some(json!("user")));
+
+        store.set("theme", "light");
+        store.save().unwrap();
+        let saved: JsonValue = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(
+            saved,
+            json!({ "$version": 3, "$$version": "user", "theme": "light" })
+        );
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn it_does_not_recover_stores_encrypted_with_another_key() {
+        let app = app();
+        let path = store_dir("encryption").join("secrets.bin");
+        let store = StoreBuilder::new(&app, &path)
+            .disable_auto_save()
+            .encryption(|| Ok([1; 32]))
+            .build()
+            .unwrap();
+        store.set("token", "secret");
+        store.save().unwrap();
+        store.close_resource();
+        let saved = fs::read(&path).unwrap();
+
+        let result = StoreBuilder::new(&app, &path)
+            .encryption(|| Ok([2; 32]))
+            .build();
+        assert!(matches!(result, Err(crate::Error::Encryption(_))));
+        let result = StoreBuilder::new(&app, &path)
+            .encryption(|| Err("the keyring is locked".into()))
+            .build();
+        assert!(matches!(result, Err(crate::Error::Encryption(_))));
+        assert_eq!(fs::read(&path).unwrap(), saved);
+        assert!(!sibling_path(&path, ".corrupted").exists());
+
+        let store = StoreBuilder::new(&app, &path)
+            .disable_auto_save()
+            .encryption(|| Ok([1; 32]))
+            .build()
+            .unwrap();
+        assert_eq!(store.get("token"), Some(json!("secret")));
+    }
+
+    #[cfg(feature = "validation")]
+    #[test]
+    fn it_does_not_recover_invalid_stores() {
+        let app = app();
+        let path = store_dir("validation").join("settings.json");
+        let invalid = r#"{ "fontSize": 200 }"#;
+        fs::write(&path, invalid).unwrap();
+        let schema = json!({ "type": "integer", "maximum": 72 });
+
+        let result = StoreBuilder::new(&app, &path)
+            .key_schema("fontSize", schema.clone())
+            .build();
+        assert!(matches!(result, Err(crate::Error::Validation { .. })));
+        assert_eq!(fs::read_to_string(&path).unwrap(), invalid);
+        assert!(!sibling_path(&path, ".corrupted").exists());
+
+        let store = StoreBuilder::new(&app, &path)
+            .disable_auto_save()
+            .default("fontSize", 14)
+            .key_schema("fontSize", schema)
+            .replace_invalid_with_defaults()
+            .build()
+            .unwrap();
+        assert_eq!(store.get("fontSize"), Some(json!(14)));
+        let result = store.transaction(|tx| {
+            tx.set("fontSize", 100);
+            Ok::<_, crate::Error>(())
+        });
+        assert!(matches!(result, Err(crate::Error::Validation { .. })));
+        assert_eq!(store.get("fontSize"), Some(json!(14)));
+    }
+
+    #[test]
+    fn it_runs_transactions_with_any_error() {
+        #[derive(Debug, PartialEq)]
+        struct Cancelled;
+
+        let app = app();
+        let path = store_dir("transaction").join("store.json");
+        let store = StoreBuilder::new(&app, &path)
+            .disable_auto_save()
+            .build()
+            .unwrap();
+        let result = store.transaction(|tx| {
+            tx.set("theme", "dark");
+            Err::<(), _>(Cancelled)
+        });
+        assert_eq!(result.unwrap(), Err(Cancelled));
+        assert_eq!(store.get("theme"), None);
+
+        store
+            .transaction(|tx| {
+                tx.set("theme", "dark");
+                Ok::<_, Cancelled>(())
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(store.get("theme"), Some(json!("dark")));
+    }
+
+    #[test]
+    fn it_hides_expired_entries() {
+        let app = app();
+        let path = store_dir("ttl").join("cache.json");
+        let store = StoreBuilder::new(&app, &path)
+            .disable_auto_save()
+            .build()
+            .unwrap();
+        assert!(store.purge_interval.lock().unwrap().is_some());
+
+        store.set("settings", "a");
+        store.set_with_ttl("token", "b", Duration::ZERO);
+        store.set_with_ttl("response", "c", Duration::from_secs(3600));
+        // started by the first entry with a time-to-live
+        assert!(store.purge_interval.lock().unwrap().is_none());
+
+        assert_eq!(store.get("token"), None);
+        assert!(!store.has("token"));
+        assert_eq!(store.get("response"), Some(json!("c")));
+        let mut keys = store.keys();
+        keys.sort();
+        assert_eq!(keys, ["response", "settings"]);
+        assert_eq!(store.length(), 2);
+        assert!(store.entries().iter().all(|(key, _)| key != "token"));
+        assert_eq!(store.get_pointer("/token").unwrap(), None);
+
+        assert!(store.purge_expired());
+        assert!(!store.purge_expired());
+    }
+
+    #[test]
+    fn it_escapes_stored_expires_keys() {
+        let app = app();
+        let path = store_dir("ttl-reserved").join("cache.json");
+        let store = StoreBuilder::new(&app, &path)
+            .disable_auto_save()
+            .build()
+            .unwrap();
+        store.set(EXPIRES_KEY, json!({ "token": 0 }));
+        store.set("token", "a");
+        store.set_with_ttl("response", "b", Duration::from_secs(3600));
+        store.save().unwrap();
+        store.close_resource();
+
+        let store = StoreBuilder::new(&app, &path)
+            .disable_auto_save()
+            .build()
+            .unwrap();
+        assert_eq!(store.get(EXPIRES_KEY), Some(json!({ "token": 0 })));
+        assert_eq!(store.get("token"), Some(json!("a")));
+        assert_eq!(store.get("response"), Some(json!("b")));
+        // started for the entries with a time-to-live in the store file
+        assert!(store.purge_interval.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn it_removes_expired_entries() {
+        let mut values: HashMap<String, JsonValue> = serde_json::from_value(json!({
+            "token": "a",
+            "response": "b",
+            "settings": "c",
+            "$expires": { "token": 1000, "response": 3000, "missing": 1000 }
+        }))
+        .unwrap();
+
+        let mut expires = take_expires(&mut values);
+        assert!(!values.contains_key(EXPIRES_KEY));
+        assert_eq!(expires.len(), 3);
+
+        let expired = remove_expired(&mut values, &mut expires, 2000);
+        assert_eq!(expired, ["token"]);
+        assert_eq!(expires, HashMap::from([("response".to_string(), 3000)]));
+        assert_eq!(values.len(), 2);
+    }
+}
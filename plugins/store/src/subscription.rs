@@ -0,0 +1,228 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Change listeners of a single store, see [`Store::on_change`](crate::Store::on_change).
+
+use serde_json::Value as JsonValue;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak},
+};
+use tauri::Resource;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+/// Returns whether the listener should keep being notified.
+pub(crate) type Handler = Arc<dyn Fn(&str, Option<&JsonValue>) -> bool + Send + Sync>;
+
+/// The keys a listener is notified about.
+pub(crate) enum KeyFilter {
+    Any,
+    Key(String),
+    Prefix(String),
+}
+
+impl KeyFilter {
+    fn matches(&self, key: &str) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Key(k) => k == key,
+            Self::Prefix(prefix) => key.starts_with(prefix.as_str()),
+        }
+    }
+}
+
+/// The listeners of a store.
+///
+/// Handlers are called in order on a dedicated task rather than while the store is locked,
+/// so they can use the store.
+#[derive(Default)]
+pub(crate) struct Listeners {
+    next_id: u32,
+    listeners: HashMap<u32, (KeyFilter, Handler)>,
+    sender: Option<UnboundedSender<(String, Option<JsonValue>)>>,
+}
+
+impl Listeners {
+    pub(crate) fn subscribe(
+        this: &Arc<Mutex<Self>>,
+        filter: KeyFilter,
+        handler: Handler,
+    ) -> Subscription {
+        let mut listeners = this.lock().unwrap();
+        let id = listeners.next_id;
+        listeners.next_id += 1;
+        listeners.listeners.insert(id, (filter, handler));
+        if listeners.sender.is_none() {
+            listeners.sender = Some(spawn_dispatch(Arc::downgrade(this)));
+        }
+
+        Subscription {
+            id,
+            listeners: Arc::downgrade(this),
+        }
+    }
+
+    /// Queues the change of `key` for the listeners interested in it.
+    pub(crate) fn notify(&self, key: &str, value: Option<&JsonValue>) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        if self
+            .listeners
+            .values()
+            .any(|(filter, _)| filter.matches(key))
+        {
+            let _ = sender.send((key.to_string(), value.cloned()));
+        }
+    }
+
+    fn handlers(&self, key: &str) -> Vec<(u32, Handler)> {
+        let mut listeners: Vec<_> = self
+            .listeners
+            .iter()
+            .filter(|(_, (filter, _))| filter.matches(key))
+            .collect();
+        // in subscription order
+        listeners.sort_by_key(|(id, _)| **id);
+        listeners
+            .into_iter()
+            .map(|(id, (_, handler))| (*id, handler.clone()))
+            .collect()
+    }
+}
+
+/// Calls the handlers of the changes sent to the returned channel until the listeners are dropped.
+fn spawn_dispatch(
+    listeners: Weak<Mutex<Listeners>>,
+) -> UnboundedSender<(String, Option<JsonValue>)> {
+    let (sender, mut receiver) = unbounded_channel::<(String, Option<JsonValue>)>();
+    tauri::async_runtime::spawn(async move {
+        // the channel closes when the listeners, which own the sender, are dropped
+        while let Some((key, value)) = receiver.recv().await {
+            let Some(listeners) = listeners.upgrade() else {
+                return;
+            };
+            let handlers = listeners.lock().unwrap().handlers(&key);
+            // the handlers may use the store, which notifies the listeners
+            let removed: Vec<u32> = handlers
+                .into_iter()
+                .filter(|(_, handler)| !handler(&key, value.as_ref()))
+                .map(|(id, _)| id)
+                .collect();
+            let mut listeners = listeners.lock().unwrap();
+            for id in removed {
+                listeners.listeners.remove(&id);
+            }
+        }
+    });
+    sender
+}
+
+/// A change listener registered with [`Store::on_change`](crate::Store::on_change)
+/// or [`Store::on_key_change`](crate::Store::on_key_change).
+///
+/// The listener stays registered until [`unsubscribe`](Self::unsubscribe) is called or the store is dropped.
+pub struct Subscription {
+    id: u32,
+    listeners: Weak<Mutex<Listeners>>,
+}
+
+impl Subscription {
+    /// Removes the listener.
+    pub fn unsubscribe(&self) {
+        if let Some(listeners) = self.listeners.upgrade() {
+            listeners.lock().unwrap().listeners.remove(&self.id);
+        }
+    }
+}
+
+impl Resource for Subscription {
+    fn close(self: Arc<Self>) {
+        self.unsubscribe();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_filters_keys() {
+        assert!(KeyFilter::Any.matches("theme"));
+        assert!(KeyFilter::Key("theme".into()).matches("theme"));
+        assert!(!KeyFilter::Key("theme".into()).matches("themes"));
+        assert!(KeyFilter::Prefix("cache.".into()).matches("cache.users"));
+        assert!(!KeyFilter::Prefix("cache.".into()).matches("settings"));
+    }
+
+    type Received = std::sync::mpsc::Receiver<(&'static str, String)>;
+
+    /// Subscribes a listener which sends the keys it receives with `name`.
+    fn subscribe(
+        listeners: &Arc<Mutex<Listeners>>,
+        name: &'static str,
+        filter: KeyFilter,
+        sender: std::sync::mpsc::Sender<(&'static str, String)>,
+    ) -> Subscription {
+        let handler = Arc::new(move |key: &str, _: Option<&JsonValue>| {
+            sender.send((name, key.to_string())).is_ok()
+        });
+        Listeners::subscribe(listeners, filter, handler)
+    }
+
+    fn next(received: &Received) -> (&'static str, String) {
+        received
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap()
+    }
+
+    #[test]
+    fn it_notifies_matching_listeners_until_unsubscribed() {
+        let listeners = Arc::new(Mutex::new(Listeners::default()));
+        let (sender, received) = std::sync::mpsc::channel();
+        let cache = subscribe(
+            &listeners,
+            "cache",
+            KeyFilter::Prefix("cache.".into()),
+            sender.clone(),
+        );
+        let _theme = subscribe(
+            &listeners,
+            "theme",
+            KeyFilter::Key("theme".into()),
+            sender.clone(),
+        );
+        let _any = subscribe(&listeners, "any", KeyFilter::Any, sender);
+
+        listeners.lock().unwrap().notify("cache.users", None);
+        assert_eq!(next(&received), ("cache", "cache.users".into()));
+        assert_eq!(next(&received), ("any", "cache.users".into()));
+        listeners.lock().unwrap().notify("theme", None);
+        assert_eq!(next(&received), ("theme", "theme".into()));
+        assert_eq!(next(&received), ("any", "theme".into()));
+
+        cache.unsubscribe();
+        listeners.lock().unwrap().notify("cache.users", None);
+        listeners.lock().unwrap().notify("settings", None);
+        // changes are dispatched in order
+        assert_eq!(next(&received), ("any", "cache.users".into()));
+        assert_eq!(next(&received), ("any", "settings".into()));
+    }
+
+    #[test]
+    fn it_removes_listeners_which_fail() {
+        let listeners = Arc::new(Mutex::new(Listeners::default()));
+        let (sender, received) = std::sync::mpsc::channel();
+        let (closed_sender, closed) = std::sync::mpsc::channel();
+        drop(closed);
+        let _closed = subscribe(&listeners, "closed", KeyFilter::Any, closed_sender);
+        let _any = subscribe(&listeners, "any", KeyFilter::Any, sender);
+
+        listeners.lock().unwrap().notify("theme", None);
+        assert_eq!(next(&received), ("any", "theme".into()));
+        listeners.lock().unwrap().notify("theme", None);
+        assert_eq!(next(&received), ("any", "theme".into()));
+        assert_eq!(listeners.lock().unwrap().listeners.len(), 1);
+    }
+}
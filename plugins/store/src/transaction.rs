@@ -0,0 +1,196 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Applies several mutations to a store at once, see [`Store::transaction`](crate::Store::transaction).
+
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+use crate::pointer;
+
+/// A pending set of mutations of a store.
+///
+/// The mutations are applied to a copy of the store, which replaces it once the transaction succeeds.
+pub struct Transaction<'a> {
+    pub(crate) cache: HashMap<String, JsonValue>,
+    pub(crate) defaults: Option<&'a HashMap<String, JsonValue>>,
+}
+
+impl Transaction<'_> {
+    /// Inserts a key-value pair into the store.
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<JsonValue>) {
+        self.cache.insert(key.into(), value.into());
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    pub fn get(&self, key: impl AsRef<str>) -> Option<&JsonValue> {
+        self.cache.get(key.as_ref())
+    }
+
+    /// Returns `true` if the given `key` exists in the store.
+    pub fn has(&self, key: impl AsRef<str>) -> bool {
+        self.cache.contains_key(key.as_ref())
+    }
+
+    /// Removes a key-value pair from the store.
+    pub fn delete(&mut self, key: impl AsRef<str>) -> bool {
+        self.cache.remove(key.as_ref()).is_some()
+    }
+
+    /// Sets the value at the given JSON Pointer, creating the missing parent objects.
+    pub fn set_pointer(
+        &mut self,
+        pointer: impl AsRef<str>,
+        value: impl Into<JsonValue>,
+    ) -> crate::Result<()> {
+        pointer::set(&mut self.cache, pointer.as_ref(), value.into())
+    }
+
+    /// Returns a reference to the value at the given JSON Pointer.
+    pub fn get_pointer(&self, pointer: impl AsRef<str>) -> crate::Result<Option<&JsonValue>> {
+        pointer::get(&self.cache, pointer.as_ref())
+    }
+
+    /// Returns `true` if a value exists at the given JSON Pointer.
+    pub fn has_pointer(&self, pointer: impl AsRef<str>) -> crate::Result<bool> {
+        Ok(self.get_pointer(pointer)?.is_some())
+    }
+
+    /// Removes the value at the given JSON Pointer.
+    pub fn delete_pointer(&mut self, pointer: impl AsRef<str>) -> crate::Result<bool> {
+        pointer::delete(&mut self.cache, pointer.as_ref())
+    }
+
+    /// Applies a JSON Merge Patch ([RFC 7396](https://www.rfc-editor.org/rfc/rfc7396)) to the store.
+    pub fn patch(&mut self, patch: JsonValue) -> crate::Result<()> {
+        pointer::merge_patch(&mut self.cache, patch).map(|_| ())
+    }
+
+    /// Clears the store, removing all key-value pairs.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Resets the store to its `default` value.
+    pub fn reset(&mut self) {
+        match self.defaults {
+            Some(defaults) => self.cache.clone_from(defaults),
+            None => self.cache.clear(),
+        }
+    }
+
+    /// An iterator visiting all keys in arbitrary order.
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.cache.keys()
+    }
+
+    /// An iterator visiting all key-value pairs in arbitrary order.
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &JsonValue)> {
+        self.cache.iter()
+    }
+
+    /// Returns the number of elements in the store.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Returns true if the store contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    /// Applies an operation sent by the `batch` command.
+    pub(crate) fn apply(&mut self, operation: BatchOperation) -> crate::Result<()> {
+        match operation {
+            BatchOperation::Set { key, value } => self.set(key, value),
+            BatchOperation::Delete { key } => {
+                self.delete(key);
+            }
+            BatchOperation::SetPointer { pointer, value } => self.set_pointer(pointer, value)?,
+            BatchOperation::DeletePointer { pointer } => {
+                self.delete_pointer(pointer)?;
+            }
+            BatchOperation::Patch { patch } => self.patch(patch)?,
+            BatchOperation::Clear => self.clear(),
+            BatchOperation::Reset => self.reset(),
+        }
+        Ok(())
+    }
+}
+
+/// A mutation of the `batch` command.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub(crate) enum BatchOperation {
+    Set { key: String, value: JsonValue },
+    Delete { key: String },
+    SetPointer { pointer: String, value: JsonValue },
+    DeletePointer { pointer: String },
+    Patch { patch: JsonValue },
+    Clear,
+    Reset,
+}
+
+/// The keys whose value differs between `before` and `after`.
+pub(crate) fn changed_keys<'a>(
+    before: &'a HashMap<String, JsonValue>,
+    after: &'a HashMap<String, JsonValue>,
+) -> Vec<&'a str> {
+    let mut keys: Vec<&str> = before
+        .keys()
+        .chain(after.keys().filter(|key| !before.contains_key(*key)))
+        .filter(|key| before.get(*key) != after.get(*key))
+        .map(String::as_str)
+        .collect();
+    keys.sort_unstable();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn it_applies_batch_operations() {
+        let defaults = HashMap::from([("theme".to_string(), json!("dark"))]);
+        let before = HashMap::from([
+            ("theme".to_string(), json!("light")),
+            ("settings".to_string(), json!({ "fontSize": 12 })),
+        ]);
+        let mut transaction = Transaction {
+            cache: before.clone(),
+            defaults: Some(&defaults),
+        };
+
+        let operations: Vec<BatchOperation> = serde_json::from_value(json!([
+            { "op": "reset" },
+            { "op": "set", "key": "count", "value": 1 },
+            { "op": "setPointer", "pointer": "/settings/fontSize", "value": 14 },
+            { "op": "patch", "patch": { "theme": null } }
+        ]))
+        .unwrap();
+        for operation in operations {
+            transaction.apply(operation).unwrap();
+        }
+
+        assert_eq!(
+            transaction.cache,
+            HashMap::from([
+                ("count".to_string(), json!(1)),
+                ("settings".to_string(), json!({ "fontSize": 14 })),
+            ])
+        );
+        assert_eq!(
+            changed_keys(&before, &transaction.cache),
+            ["count", "settings", "theme"]
+        );
+        assert!(transaction
+            .apply(BatchOperation::DeletePointer {
+                pointer: "count".into()
+            })
+            .is_err());
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Validates store values against JSON Schemas, see [`StoreBuilder::json_schema`](crate::StoreBuilder::json_schema).
+
+use jsonschema::{ValidationError, Validator};
+use serde_json::{Map, Value as JsonValue};
+use std::collections::HashMap;
+
+use crate::{pointer, Error, Result};
+
+/// The compiled schemas of a store.
+pub(crate) struct Validation {
+    store: Option<Validator>,
+    keys: HashMap<String, Validator>,
+    replace_invalid: bool,
+}
+
+impl Validation {
+    pub(crate) fn new(
+        store: Option<&JsonValue>,
+        keys: &HashMap<String, JsonValue>,
+        replace_invalid: bool,
+    ) -> Result<Self> {
+        let compile = |schema| {
+            jsonschema::validator_for(schema).map_err(|e| Error::InvalidSchema(e.to_string()))
+        };
+        Ok(Self {
+            store: store.map(compile).transpose()?,
+            keys: keys
+                .iter()
+                .map(|(key, schema)| Ok((key.clone(), compile(schema)?)))
+                .collect::<Result<_>>()?,
+            replace_invalid,
+        })
+    }
+
+    /// Validates the store after the values of `keys` changed.
+    pub(crate) fn validate<'a>(
+        &self,
+        cache: &HashMap<String, JsonValue>,
+        keys: impl IntoIterator<Item = &'a str>,
+    ) -> Result<()> {
+        for key in keys {
+            if let (Some(validator), Some(value)) = (self.keys.get(key), cache.get(key)) {
+                validator
+                    .validate(value)
+                    .map_err(|e| error(&pointer::join([key]), e))?;
+            }
+        }
+
+        if let Some(validator) = &self.store {
+            validator
+                .validate(&to_object(cache))
+                .map_err(|e| error("", e))?;
+        }
+        Ok(())
+    }
+
+    /// Validates the `values` loaded from the store file, which extend the `defaults`.
+    ///
+    /// Invalid values are removed so their default value applies if `replace_invalid` is set,
+    /// otherwise the first invalid value is returned as an error.
+    pub(crate) fn validate_loaded(
+        &self,
+        values: &mut HashMap<String, JsonValue>,
+        defaults: Option<&HashMap<String, JsonValue>>,
+    ) -> Result<()> {
+        let mut invalid_keys = Vec::new();
+        for (key, value) in values.iter() {
+            let Some(validator) = self.keys.get(key) else {
+                continue;
+            };
+            if let Err(e) = validator.validate(value) {
+                invalid_keys.push(self.reject(key, error(&pointer::join([key.as_str()]), e))?);
+            }
+        }
+        for key in invalid_keys {
+            values.remove(&key);
+        }
+
+        let Some(validator) = &self.store else {
+            return Ok(());
+        };
+        loop {
+            let mut store = defaults.cloned().unwrap_or_default();
+            store.extend(values.clone());
+            let store = to_object(&store);
+            let Err(e) = validator.validate(&store) else {
+                return Ok(());
+            };
+
+            // an error below a loaded key is fixed by removing it, otherwise give up
+            let e = error("", e);
+            let key = match &e {
+                Error::Validation { path, .. } => pointer::split(path).ok().map(|(key, _)| key),
+                _ => None,
+            };
+            match key.filter(|key| values.contains_key(key)) {
+                Some(key) => {
+                    let key = self.reject(&key, e)?;
+                    values.remove(&key);
+                }
+                None => return Err(e),
+            }
+        }
+    }
+
+    /// Returns the invalid key to remove if `replace_invalid` is set, otherwise the error.
+    fn reject(&self, key: &str, error: Error) -> Result<String> {
+        if self.replace_invalid {
+            log::warn!("replacing invalid store value with its default: {error}");
+            Ok(key.to_string())
+        } else {
+            Err(error)
+        }
+    }
+}
+
+fn to_object(cache: &HashMap<String, JsonValue>) -> JsonValue {
+    JsonValue::Object(
+        cache
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect::<Map<_, _>>(),
+    )
+}
+
+/// `prefix` is the pointer of the validated value in the store.
+fn error(prefix: &str, error: ValidationError<'_>) -> Error {
+    Error::Validation {
+        path: format!("{prefix}{}", error.instance_path),
+        message: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(value: JsonValue) -> HashMap<String, JsonValue> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn it_validates_values() {
+        let keys = values(json!({
+            "fontSize": { "type": "integer", "minimum": 8, "maximum": 72 }
+        }));
+        let store = json!({
+            "properties": { "editor": { "properties": { "theme": { "enum": ["light", "dark"] } } } }
+        });
+        let validation = Validation::new(Some(&store), &keys, false).unwrap();
+
+        let cache = values(json!({ "fontSize": 12, "editor": { "theme": "dark" } }));
+        assert!(validation.validate(&cache, ["fontSize"]).is_ok());
+
+        let cache = values(json!({ "fontSize": 300 }));
+        assert!(matches!(
+            validation.validate(&cache, ["fontSize"]),
+            Err(Error::Validation { path, .. }) if path == "/fontSize"
+        ));
+
+        let cache = values(json!({ "editor": { "theme": "blue" } }));
+        assert!(matches!(
+            validation.validate(&cache, ["editor"]),
+            Err(Error::Validation { path, .. }) if path == "/editor/theme"
+        ));
+
+        assert!(Validation::new(Some(&json!({ "type": 1 })), &keys, false).is_err());
+    }
+
+    #[test]
+    fn it_replaces_invalid_loaded_values() {
+        let keys = values(json!({ "fontSize": { "type": "integer" } }));
+        let store = json!({ "properties": { "theme": { "enum": ["light", "dark"] } } });
+        let defaults = values(json!({ "fontSize": 12, "theme": "light" }));
+        let loaded = values(json!({ "fontSize": "huge", "theme": "blue", "other": true }));
+
+        let validation = Validation::new(Some(&store), &keys, false).unwrap();
+        assert!(validation
+            .validate_loaded(&mut loaded.clone(), Some(&defaults))
+            .is_err());
+
+        let validation = Validation::new(Some(&store), &keys, true).unwrap();
+        let mut replaced = loaded.clone();
+        validation
+            .validate_loaded(&mut replaced, Some(&defaults))
+            .unwrap();
+        assert_eq!(replaced, values(json!({ "other": true })));
+    }
+}
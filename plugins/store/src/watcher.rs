@@ -0,0 +1,140 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Picks up changes made to store files by other processes, see [`StoreBuilder::watch`](crate::StoreBuilder::watch).
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use notify_debouncer_full::{new_debouncer, Debouncer, FileIdMap};
+use serde_json::Value as JsonValue;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::Path,
+    sync::mpsc::channel,
+    thread::spawn,
+    time::Duration,
+};
+
+/// Bursts of file system events closer than this are handled once.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Watches a store file until dropped.
+pub(crate) struct StoreWatcher {
+    _debouncer: Debouncer<RecommendedWatcher, FileIdMap>,
+}
+
+/// Calls `on_change` whenever the file at `path` is modified, created or removed.
+pub(crate) fn watch(
+    path: &Path,
+    on_change: impl Fn() + Send + 'static,
+) -> crate::Result<StoreWatcher> {
+    let parent = path.parent().expect("invalid store path");
+    let file_name = path.file_name().expect("invalid store path").to_owned();
+    fs::create_dir_all(parent)?;
+
+    let (tx, rx) = channel();
+    let mut debouncer = new_debouncer(DEBOUNCE, None, tx)?;
+    // saving replaces the store file, so watch its directory rather than the file itself
+    debouncer
+        .watcher()
+        .watch(parent, RecursiveMode::NonRecursive)?;
+
+    spawn(move || {
+        // the channel closes when the watcher is dropped
+        while let Ok(result) = rx.recv() {
+            let Ok(events) = result else {
+                continue;
+            };
+            let touches_store = events.iter().any(|event| {
+                event
+                    .paths
+                    .iter()
+                    .any(|path| path.file_name() == Some(&file_name))
+            });
+            if touches_store {
+                on_change();
+            }
+        }
+    });
+
+    Ok(StoreWatcher {
+        _debouncer: debouncer,
+    })
+}
+
+/// Outcome of [`merge`].
+#[derive(Debug, Default)]
+pub(crate) struct Merge {
+    /// Keys updated with their value on disk.
+    pub(crate) changed: Vec<String>,
+    /// Keys changed both on disk and in memory, which keep their value in memory.
+    pub(crate) conflicts: Vec<String>,
+}
+
+/// Merges the changes made on disk since `base`, the last state loaded from or saved to disk, into `cache`.
+///
+/// Keys missing from the store file have their default value, like when a store is loaded.
+pub(crate) fn merge(
+    base: &HashMap<String, JsonValue>,
+    disk: &HashMap<String, JsonValue>,
+    defaults: Option<&HashMap<String, JsonValue>>,
+    cache: &mut HashMap<String, JsonValue>,
+) -> Merge {
+    let value = |values: &'_ HashMap<String, JsonValue>, key: &str| {
+        values
+            .get(key)
+            .or_else(|| defaults.and_then(|defaults| defaults.get(key)))
+            .cloned()
+    };
+
+    let keys: BTreeSet<&String> = base.keys().chain(disk.keys()).collect();
+    let mut merge = Merge::default();
+    for key in keys {
+        let before = value(base, key);
+        let after = value(disk, key);
+        let local = cache.get(key.as_str()).cloned();
+        if before == after || local == after {
+            continue;
+        }
+
+        if local == before {
+            match after {
+                Some(after) => cache.insert(key.clone(), after),
+                None => cache.remove(key.as_str()),
+            };
+            merge.changed.push(key.clone());
+        } else {
+            merge.conflicts.push(key.clone());
+        }
+    }
+    merge
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(value: JsonValue) -> HashMap<String, JsonValue> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn it_merges_changes_made_on_disk() {
+        let defaults = values(json!({ "theme": "light" }));
+        let base = values(json!({ "count": 1, "name": "a", "draft": "x" }));
+        let disk = values(json!({ "count": 2, "name": "b", "theme": "dark" }));
+        // `name` and `draft` have unsaved changes
+        let mut cache = values(json!({ "count": 1, "name": "c", "draft": "y", "theme": "light" }));
+
+        let merge = merge(&base, &disk, Some(&defaults), &mut cache);
+
+        assert_eq!(merge.changed, ["count", "theme"]);
+        assert_eq!(merge.conflicts, ["draft", "name"]);
+        assert_eq!(
+            cache,
+            values(json!({ "count": 2, "name": "c", "draft": "y", "theme": "dark" }))
+        );
+    }
+}
@@ -2,7 +2,21 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-const COMMANDS: &[&str] = &["check", "download", "install", "download_and_install"];
+const COMMANDS: &[&str] = &[
+    "check",
+    "download",
+    "install",
+    "download_and_install",
+    "download_and_install_on_exit",
+    "cancel_install_on_exit",
+    "install_downloaded_update",
+    "new_download_handle",
+    "pause_download",
+    "resume_download",
+    "cancel_download",
+    "confirm_healthy",
+    "failed_update",
+];
 
 fn main() {
     tauri_plugin::Builder::new(COMMANDS)
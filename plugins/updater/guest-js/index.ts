@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: MIT
 
 import { invoke, Channel, Resource } from '@tauri-apps/api/core'
+import { type UnlistenFn, listen } from '@tauri-apps/api/event'
 
 /** Options used when checking for updates */
 interface CheckOptions {
@@ -22,6 +23,10 @@ interface CheckOptions {
    * Target identifier for the running application. This is sent to the backend.
    */
   target?: string
+  /**
+   * Release channel to check, for example `beta` or `nightly`. Defaults to `stable`.
+   */
+  channel?: string
 }
 
 /** Options used when downloading an update */
@@ -34,6 +39,17 @@ interface DownloadOptions {
    * Timeout in milliseconds
    */
   timeout?: number
+  /**
+   * Handle to pause, resume or cancel the download
+   */
+  handle?: DownloadHandle
+}
+
+/** The notes of a release between the current version and the update */
+interface ReleaseNotes {
+  version: string
+  date?: string
+  notes?: string
 }
 
 interface UpdateMetadata {
@@ -43,20 +59,58 @@ interface UpdateMetadata {
   version: string
   date?: string
   body?: string
+  changelog: ReleaseNotes[]
 }
 
 /** Updater download event */
 type DownloadEvent =
+  /** Sent again when the download starts over, for instance when an update patch cannot be applied */
   | { event: 'Started'; data: { contentLength?: number } }
-  | { event: 'Progress'; data: { chunkLength: number } }
+  | {
+      event: 'Progress'
+      data: {
+        chunkLength: number
+        /** Bytes downloaded so far */
+        downloaded: number
+        contentLength?: number
+        bytesPerSecond: number
+        /** Estimated remaining time in milliseconds */
+        eta?: number
+      }
+    }
   | { event: 'Finished' }
 
+/** Pauses, resumes or cancels a running download, see {@link DownloadOptions.handle} */
+class DownloadHandle extends Resource {
+  static async new(): Promise<DownloadHandle> {
+    const rid = await invoke<number>('plugin:updater|new_download_handle')
+    return new DownloadHandle(rid)
+  }
+
+  /** Pauses the download until {@link DownloadHandle.resume} is called */
+  async pause(): Promise<void> {
+    await invoke('plugin:updater|pause_download', { rid: this.rid })
+  }
+
+  /** Resumes a paused download */
+  async resume(): Promise<void> {
+    await invoke('plugin:updater|resume_download', { rid: this.rid })
+  }
+
+  /** Cancels the download, which then rejects */
+  async cancel(): Promise<void> {
+    await invoke('plugin:updater|cancel_download', { rid: this.rid })
+  }
+}
+
 class Update extends Resource {
   available: boolean
   currentVersion: string
   version: string
   date?: string
   body?: string
+  /** Notes of every release between the current version and the update, newest first */
+  changelog: ReleaseNotes[]
   private downloadedBytes?: Resource
 
   constructor(metadata: UpdateMetadata) {
@@ -66,6 +120,7 @@ class Update extends Resource {
     this.version = metadata.version
     this.date = metadata.date
     this.body = metadata.body
+    this.changelog = metadata.changelog
   }
 
   /** Download the updater package */
@@ -80,7 +135,8 @@ class Update extends Resource {
     const downloadedBytesRid = await invoke<number>('plugin:updater|download', {
       onEvent: channel,
       rid: this.rid,
-      ...options
+      ...options,
+      handle: options?.handle?.rid
     })
     this.downloadedBytes = new Resource(downloadedBytesRid)
   }
@@ -112,7 +168,28 @@ class Update extends Resource {
     await invoke('plugin:updater|download_and_install', {
       onEvent: channel,
       rid: this.rid,
-      ...options
+      ...options,
+      handle: options?.handle?.rid
+    })
+  }
+
+  /**
+   * Downloads the updater package and installs it when the app exits,
+   * so the next launch is on the new version without interrupting the current session.
+   */
+  async downloadAndInstallOnExit(
+    onEvent?: (progress: DownloadEvent) => void,
+    options?: DownloadOptions
+  ): Promise<void> {
+    const channel = new Channel<DownloadEvent>()
+    if (onEvent) {
+      channel.onmessage = onEvent
+    }
+    await invoke('plugin:updater|download_and_install_on_exit', {
+      onEvent: channel,
+      rid: this.rid,
+      ...options,
+      handle: options?.handle?.rid
     })
   }
 
@@ -133,5 +210,100 @@ async function check(options?: CheckOptions): Promise<Update | null> {
   }).then((meta) => (meta.available ? new Update(meta) : null))
 }
 
-export type { CheckOptions, DownloadOptions, DownloadEvent }
-export { check, Update }
+/** An update that was rolled back because it was not confirmed healthy in time */
+interface FailedUpdate {
+  /** The version that was rolled back */
+  version: string
+  /** The version that was restored */
+  previousVersion: string
+}
+
+/**
+ * Confirms the running version is healthy so the previous version is not restored.
+ *
+ * Only has an effect when the updater plugin is configured with a health check window.
+ */
+async function confirmHealthy(): Promise<void> {
+  await invoke('plugin:updater|confirm_healthy')
+}
+
+/**
+ * Resolves to the update that was rolled back because it was not confirmed healthy in time,
+ * if the previous version was restored before this launch.
+ */
+async function failedUpdate(): Promise<FailedUpdate | null> {
+  return await invoke<FailedUpdate | null>('plugin:updater|failed_update')
+}
+
+/**
+ * Cancels the installation staged by {@link Update.downloadAndInstallOnExit},
+ * resolves to the version that would have been installed.
+ */
+async function cancelInstallOnExit(): Promise<string | null> {
+  return await invoke<string | null>('plugin:updater|cancel_install_on_exit')
+}
+
+/**
+ * Installs the update downloaded by the background checks with the `Download` check policy,
+ * resolves to its version, or `null` if no update was downloaded.
+ */
+async function installDownloadedUpdate(): Promise<string | null> {
+  return await invoke<string | null>('plugin:updater|install_downloaded_update')
+}
+
+/** An update found by the background checks */
+interface BackgroundUpdate {
+  currentVersion: string
+  version: string
+  date?: string
+  body?: string
+}
+
+/**
+ * Listens to the updates found by the background checks,
+ * only emitted when the updater plugin is configured with a check interval.
+ */
+async function onUpdateAvailable(
+  handler: (update: BackgroundUpdate) => void
+): Promise<UnlistenFn> {
+  return await listen<BackgroundUpdate>('updater://update-available', (event) => {
+    handler(event.payload)
+  })
+}
+
+/**
+ * Listens to the updates downloaded by the background checks,
+ * only emitted when the updater plugin check policy downloads updates.
+ *
+ * With the `Download` check policy, install it with {@link installDownloadedUpdate}.
+ */
+async function onUpdateDownloaded(
+  handler: (update: BackgroundUpdate) => void
+): Promise<UnlistenFn> {
+  return await listen<BackgroundUpdate>(
+    'updater://update-downloaded',
+    (event) => {
+      handler(event.payload)
+    }
+  )
+}
+
+export type {
+  BackgroundUpdate,
+  CheckOptions,
+  DownloadOptions,
+  DownloadEvent,
+  FailedUpdate,
+  ReleaseNotes
+}
+export {
+  cancelInstallOnExit,
+  check,
+  confirmHealthy,
+  failedUpdate,
+  installDownloadedUpdate,
+  onUpdateAvailable,
+  onUpdateDownloaded,
+  DownloadHandle,
+  Update
+}
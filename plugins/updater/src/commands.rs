@@ -2,18 +2,24 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-use crate::{Result, Update, UpdaterExt};
+use crate::{
+    download::ThroughputTracker, DownloadHandle, FailedUpdate, Result, Update, UpdaterExt,
+};
 
 use http::{HeaderMap, HeaderName, HeaderValue};
 use serde::Serialize;
 use tauri::{ipc::Channel, Manager, Resource, ResourceId, Runtime, Webview};
 
-use std::{str::FromStr, time::Duration};
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
 use url::Url;
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(tag = "event", content = "data")]
 pub enum DownloadEvent {
+    /// Sent again when the download starts over, for instance when the update patch cannot be applied.
     #[serde(rename_all = "camelCase")]
     Started {
         content_length: Option<u64>,
@@ -21,10 +27,48 @@ pub enum DownloadEvent {
     #[serde(rename_all = "camelCase")]
     Progress {
         chunk_length: usize,
+        /// Bytes downloaded so far.
+        downloaded: u64,
+        content_length: Option<u64>,
+        bytes_per_second: u64,
+        /// Estimated remaining time in milliseconds.
+        eta: Option<u64>,
     },
     Finished,
 }
 
+/// Reports the download progress, including its speed and estimated remaining time, to `on_event`.
+///
+/// A zero-length chunk starts the progress over, with a new [`DownloadEvent::Started`] event.
+fn report_progress(on_event: &Channel<DownloadEvent>) -> impl FnMut(usize, Option<u64>) + '_ {
+    let mut tracker: Option<ThroughputTracker> = None;
+    move |chunk_length, content_length| {
+        if chunk_length == 0 {
+            tracker = None;
+            return;
+        }
+        let now = Instant::now();
+        let throughput = match tracker.as_mut() {
+            Some(tracker) => tracker.update(chunk_length, content_length, now),
+            None => {
+                let _ = on_event.send(DownloadEvent::Started { content_length });
+                // the first chunk, which holds the bytes resumed from a previous download if any,
+                // was received before measuring started
+                tracker
+                    .insert(ThroughputTracker::new(chunk_length as u64, now))
+                    .throughput(content_length)
+            }
+        };
+        let _ = on_event.send(DownloadEvent::Progress {
+            chunk_length,
+            downloaded: throughput.downloaded,
+            content_length,
+            bytes_per_second: throughput.bytes_per_second,
+            eta: throughput.eta.map(|eta| eta.as_millis() as u64),
+        });
+    }
+}
+
 #[derive(Serialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct Metadata {
@@ -34,6 +78,15 @@ pub(crate) struct Metadata {
     version: String,
     date: Option<String>,
     body: Option<String>,
+    changelog: Vec<ReleaseNotesMetadata>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReleaseNotesMetadata {
+    version: String,
+    date: Option<String>,
+    notes: Option<String>,
 }
 
 struct DownloadedBytes(pub Vec<u8>);
@@ -46,6 +99,7 @@ pub(crate) async fn check<R: Runtime>(
     timeout: Option<u64>,
     proxy: Option<String>,
     target: Option<String>,
+    channel: Option<String>,
 ) -> Result<Metadata> {
     let mut builder = webview.updater_builder();
     if let Some(headers) = headers {
@@ -63,6 +117,9 @@ pub(crate) async fn check<R: Runtime>(
     if let Some(target) = target {
         builder = builder.target(target);
     }
+    if let Some(channel) = channel {
+        builder = builder.channel(channel);
+    }
 
     let updater = builder.build()?;
     let update = updater.check().await?;
@@ -73,6 +130,15 @@ pub(crate) async fn check<R: Runtime>(
         metadata.version.clone_from(&update.version);
         metadata.date = update.date.map(|d| d.to_string());
         metadata.body.clone_from(&update.body);
+        metadata.changelog = update
+            .changelog
+            .iter()
+            .map(|release| ReleaseNotesMetadata {
+                version: release.version.clone(),
+                date: release.date.map(|d| d.to_string()),
+                notes: release.notes.clone(),
+            })
+            .collect();
         metadata.rid = Some(webview.resources_table().add(update));
     }
 
@@ -86,6 +152,7 @@ pub(crate) async fn download<R: Runtime>(
     on_event: Channel<DownloadEvent>,
     headers: Option<Vec<(String, String)>>,
     timeout: Option<u64>,
+    handle: Option<ResourceId>,
 ) -> Result<ResourceId> {
     let update = webview.resources_table().get::<Update>(rid)?;
 
@@ -103,20 +170,15 @@ pub(crate) async fn download<R: Runtime>(
         update.timeout = Some(Duration::from_millis(timeout));
     }
 
-    let mut first_chunk = true;
+    if let Some(handle) = handle {
+        update.download_handle =
+            Some((*webview.resources_table().get::<DownloadHandle>(handle)?).clone());
+    }
+
     let bytes = update
-        .download(
-            |chunk_length, content_length| {
-                if first_chunk {
-                    first_chunk = !first_chunk;
-                    let _ = on_event.send(DownloadEvent::Started { content_length });
-                }
-                let _ = on_event.send(DownloadEvent::Progress { chunk_length });
-            },
-            || {
-                let _ = on_event.send(DownloadEvent::Finished);
-            },
-        )
+        .download(report_progress(&on_event), || {
+            let _ = on_event.send(DownloadEvent::Finished);
+        })
         .await?;
 
     Ok(webview.resources_table().add(DownloadedBytes(bytes)))
@@ -144,6 +206,7 @@ pub(crate) async fn download_and_install<R: Runtime>(
     on_event: Channel<DownloadEvent>,
     headers: Option<Vec<(String, String)>>,
     timeout: Option<u64>,
+    handle: Option<ResourceId>,
 ) -> Result<()> {
     let update = webview.resources_table().get::<Update>(rid)?;
 
@@ -161,22 +224,111 @@ pub(crate) async fn download_and_install<R: Runtime>(
         update.timeout = Some(Duration::from_millis(timeout));
     }
 
-    let mut first_chunk = true;
+    if let Some(handle) = handle {
+        update.download_handle =
+            Some((*webview.resources_table().get::<DownloadHandle>(handle)?).clone());
+    }
 
     update
-        .download_and_install(
-            |chunk_length, content_length| {
-                if first_chunk {
-                    first_chunk = !first_chunk;
-                    let _ = on_event.send(DownloadEvent::Started { content_length });
-                }
-                let _ = on_event.send(DownloadEvent::Progress { chunk_length });
-            },
-            || {
-                let _ = on_event.send(DownloadEvent::Finished);
-            },
-        )
+        .download_and_install(report_progress(&on_event), || {
+            let _ = on_event.send(DownloadEvent::Finished);
+        })
+        .await?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn download_and_install_on_exit<R: Runtime>(
+    webview: Webview<R>,
+    rid: ResourceId,
+    on_event: Channel<DownloadEvent>,
+    headers: Option<Vec<(String, String)>>,
+    timeout: Option<u64>,
+    handle: Option<ResourceId>,
+) -> Result<()> {
+    let update = webview.resources_table().get::<Update>(rid)?;
+
+    let mut update = (*update).clone();
+
+    if let Some(headers) = headers {
+        let mut map = HeaderMap::new();
+        for (k, v) in headers {
+            map.append(HeaderName::from_str(&k)?, HeaderValue::from_str(&v)?);
+        }
+        update.headers = map;
+    }
+
+    if let Some(timeout) = timeout {
+        update.timeout = Some(Duration::from_millis(timeout));
+    }
+
+    if let Some(handle) = handle {
+        update.download_handle =
+            Some((*webview.resources_table().get::<DownloadHandle>(handle)?).clone());
+    }
+
+    let path = update
+        .download_to_file(report_progress(&on_event), || {
+            let _ = on_event.send(DownloadEvent::Finished);
+        })
         .await?;
 
+    webview.install_on_exit(update, path);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn cancel_install_on_exit<R: Runtime>(webview: Webview<R>) -> Option<String> {
+    webview.cancel_install_on_exit()
+}
+
+#[tauri::command]
+pub(crate) async fn install_downloaded_update<R: Runtime>(
+    webview: Webview<R>,
+) -> Result<Option<String>> {
+    webview.install_downloaded_update()
+}
+
+#[tauri::command]
+pub(crate) fn new_download_handle<R: Runtime>(webview: Webview<R>) -> ResourceId {
+    webview.resources_table().add(DownloadHandle::new())
+}
+
+#[tauri::command]
+pub(crate) fn pause_download<R: Runtime>(webview: Webview<R>, rid: ResourceId) -> Result<()> {
+    webview
+        .resources_table()
+        .get::<DownloadHandle>(rid)?
+        .pause();
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn resume_download<R: Runtime>(webview: Webview<R>, rid: ResourceId) -> Result<()> {
+    webview
+        .resources_table()
+        .get::<DownloadHandle>(rid)?
+        .resume();
     Ok(())
 }
+
+#[tauri::command]
+pub(crate) fn cancel_download<R: Runtime>(webview: Webview<R>, rid: ResourceId) -> Result<()> {
+    webview
+        .resources_table()
+        .get::<DownloadHandle>(rid)?
+        .cancel();
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn confirm_healthy<R: Runtime>(webview: Webview<R>) {
+    webview.confirm_healthy();
+}
+
+#[tauri::command]
+pub(crate) fn failed_update<R: Runtime>(webview: Webview<R>) -> Option<FailedUpdate> {
+    webview.failed_update()
+}
@@ -4,7 +4,8 @@
 
 use std::{ffi::OsString, fmt::Display};
 
-use serde::{Deserialize, Deserializer};
+use serde::{de::Error as DeError, Deserialize, Deserializer};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 use url::Url;
 
 /// Install modes for the Windows update.
@@ -80,6 +81,18 @@ pub struct WindowsConfig {
     pub install_mode: WindowsUpdateInstallMode,
 }
 
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LinuxConfig {
+    /// Additional arguments given to `dpkg` or `rpm` when installing a `deb` or `rpm` update.
+    #[serde(
+        default,
+        alias = "installer-args",
+        deserialize_with = "deserialize_os_string"
+    )]
+    pub installer_args: Vec<OsString>,
+}
+
 fn deserialize_os_string<'de, D>(deserializer: D) -> Result<Vec<OsString>, D::Error>
 where
     D: Deserializer<'de>,
@@ -90,8 +103,50 @@ where
         .collect::<Vec<_>>())
 }
 
+/// A public key trusted to sign updates, optionally only during a validity window.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedKey {
+    /// Signature public key.
+    pub pubkey: String,
+    /// The key is not trusted before this date.
+    #[serde(default, alias = "not-before", deserialize_with = "deserialize_date")]
+    pub not_before: Option<OffsetDateTime>,
+    /// The key is not trusted after this date.
+    #[serde(default, alias = "not-after", deserialize_with = "deserialize_date")]
+    pub not_after: Option<OffsetDateTime>,
+}
+
+impl TrustedKey {
+    /// A key trusted without any validity window.
+    pub fn new(pubkey: impl Into<String>) -> Self {
+        Self {
+            pubkey: pubkey.into(),
+            not_before: None,
+            not_after: None,
+        }
+    }
+
+    /// Whether the key is trusted at the given date.
+    pub fn is_valid_at(&self, date: OffsetDateTime) -> bool {
+        self.not_before
+            .map_or(true, |not_before| date >= not_before)
+            && self.not_after.map_or(true, |not_after| date <= not_after)
+    }
+}
+
+fn deserialize_date<'de, D>(deserializer: D) -> Result<Option<OffsetDateTime>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|date| OffsetDateTime::parse(&date, &Rfc3339).map_err(DeError::custom))
+        .transpose()
+}
+
 /// Updater configuration.
 #[derive(Debug, Clone, Default)]
+#[non_exhaustive]
 pub struct Config {
     /// Dangerously allow using insecure transport protocols for update endpoints.
     pub dangerous_insecure_transport_protocol: bool,
@@ -99,8 +154,23 @@ pub struct Config {
     pub endpoints: Vec<Url>,
     /// Signature public key.
     pub pubkey: String,
+    /// Additional public keys trusted to sign updates, used to rotate keys.
+    pub pubkeys: Vec<TrustedKey>,
     /// The Windows configuration for the updater.
     pub windows: Option<WindowsConfig>,
+    /// The Linux configuration for the updater.
+    pub linux: Option<LinuxConfig>,
+}
+
+impl Config {
+    /// All the keys trusted to sign updates, [`Config::pubkey`] followed by [`Config::pubkeys`].
+    pub fn trusted_keys(&self) -> impl Iterator<Item = TrustedKey> + '_ {
+        Some(&self.pubkey)
+            .filter(|pubkey| !pubkey.is_empty())
+            .map(TrustedKey::new)
+            .into_iter()
+            .chain(self.pubkeys.iter().cloned())
+    }
 }
 
 impl<'de> Deserialize<'de> for Config {
@@ -115,12 +185,22 @@ impl<'de> Deserialize<'de> for Config {
             pub dangerous_insecure_transport_protocol: bool,
             #[serde(default)]
             pub endpoints: Vec<Url>,
+            #[serde(default)]
             pub pubkey: String,
+            #[serde(default)]
+            pub pubkeys: Vec<TrustedKey>,
             pub windows: Option<WindowsConfig>,
+            pub linux: Option<LinuxConfig>,
         }
 
         let config = Config::deserialize(deserializer)?;
 
+        if config.pubkey.is_empty() && config.pubkeys.is_empty() {
+            return Err(DeError::custom(
+                "missing field `pubkey`, at least one public key must be set with `pubkey` or `pubkeys`",
+            ));
+        }
+
         validate_endpoints(
             &config.endpoints,
             config.dangerous_insecure_transport_protocol,
@@ -131,7 +211,9 @@ impl<'de> Deserialize<'de> for Config {
             dangerous_insecure_transport_protocol: config.dangerous_insecure_transport_protocol,
             endpoints: config.endpoints,
             pubkey: config.pubkey,
+            pubkeys: config.pubkeys,
             windows: config.windows,
+            linux: config.linux,
         })
     }
 }
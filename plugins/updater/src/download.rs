@@ -0,0 +1,222 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Control over a running download and the throughput reported by the download progress events.
+
+use std::{
+    sync::{Arc, Mutex},
+    task::{Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use tauri::Resource;
+
+use crate::{Error, Result};
+
+/// Samples closer than this are merged so the speed does not jump around between chunks.
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+/// Gaps longer than this, for instance while the download is paused, do not count towards the speed.
+const STALL_INTERVAL: Duration = Duration::from_secs(2);
+/// Weight of the latest sample in the moving average of the speed.
+const SMOOTHING: f64 = 0.3;
+
+#[derive(Debug, Default)]
+struct HandleState {
+    paused: bool,
+    cancelled: bool,
+    wakers: Vec<Waker>,
+}
+
+/// Pauses, resumes or cancels a running download, see [`Update::download_handle`](crate::Update::download_handle).
+///
+/// Clones control the same download.
+#[derive(Debug, Clone, Default)]
+pub struct DownloadHandle {
+    state: Arc<Mutex<HandleState>>,
+}
+
+impl Resource for DownloadHandle {}
+
+impl DownloadHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops reading the download until [`DownloadHandle::resume`] is called.
+    ///
+    /// The server may close the connection during a long pause, in which case
+    /// [`Update::download_to_file`](crate::Update::download_to_file) resumes where it stopped
+    /// while [`Update::download`](crate::Update::download) fails.
+    pub fn pause(&self) {
+        self.state.lock().unwrap().paused = true;
+    }
+
+    /// Resumes a paused download.
+    pub fn resume(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.paused = false;
+        state.wakers.drain(..).for_each(Waker::wake);
+    }
+
+    /// Cancels the download, which then fails with [`Error::DownloadCancelled`].
+    pub fn cancel(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.cancelled = true;
+        state.wakers.drain(..).for_each(Waker::wake);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().unwrap().paused
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.lock().unwrap().cancelled
+    }
+
+    /// Waits while the download is paused and fails once it is cancelled.
+    pub(crate) async fn checkpoint(&self) -> Result<()> {
+        std::future::poll_fn(|cx| {
+            let mut state = self.state.lock().unwrap();
+            if state.cancelled {
+                Poll::Ready(Err(Error::DownloadCancelled))
+            } else if state.paused {
+                if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                    state.wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            } else {
+                Poll::Ready(Ok(()))
+            }
+        })
+        .await
+    }
+}
+
+/// Cumulative progress of a download along with its speed and estimated remaining time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Throughput {
+    pub(crate) downloaded: u64,
+    pub(crate) bytes_per_second: u64,
+    /// Estimated remaining time, unknown until the speed and content length are.
+    pub(crate) eta: Option<Duration>,
+}
+
+/// Computes the [`Throughput`] of a download from its chunks.
+pub(crate) struct ThroughputTracker {
+    downloaded: u64,
+    sample_start: Instant,
+    sample_bytes: u64,
+    bytes_per_second: Option<f64>,
+}
+
+impl ThroughputTracker {
+    /// Starts measuring after `downloaded` bytes were received, which do not count towards the speed.
+    pub(crate) fn new(downloaded: u64, now: Instant) -> Self {
+        Self {
+            downloaded,
+            sample_start: now,
+            sample_bytes: 0,
+            bytes_per_second: None,
+        }
+    }
+
+    pub(crate) fn update(
+        &mut self,
+        chunk_length: usize,
+        content_length: Option<u64>,
+        now: Instant,
+    ) -> Throughput {
+        self.downloaded += chunk_length as u64;
+        self.sample_bytes += chunk_length as u64;
+
+        let elapsed = now.saturating_duration_since(self.sample_start);
+        if elapsed >= STALL_INTERVAL {
+            // the download was paused or stalled, start measuring again
+            self.sample_start = now;
+            self.sample_bytes = 0;
+        } else if elapsed >= SAMPLE_INTERVAL {
+            let rate = self.sample_bytes as f64 / elapsed.as_secs_f64();
+            self.bytes_per_second = Some(match self.bytes_per_second {
+                Some(average) => SMOOTHING * rate + (1.0 - SMOOTHING) * average,
+                None => rate,
+            });
+            self.sample_start = now;
+            self.sample_bytes = 0;
+        }
+
+        self.throughput(content_length)
+    }
+
+    pub(crate) fn throughput(&self, content_length: Option<u64>) -> Throughput {
+        let bytes_per_second = self.bytes_per_second.unwrap_or_default();
+        let eta = content_length
+            .filter(|_| bytes_per_second > 0.0)
+            .map(|len| {
+                Duration::from_secs_f64(
+                    len.saturating_sub(self.downloaded) as f64 / bytes_per_second,
+                )
+            });
+
+        Throughput {
+            downloaded: self.downloaded,
+            bytes_per_second: bytes_per_second as u64,
+            eta,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_tracks_throughput() {
+        let start = Instant::now();
+        let mut tracker = ThroughputTracker::new(0, start);
+
+        let throughput = tracker.update(500, Some(4000), start + Duration::from_millis(100));
+        assert_eq!(throughput.downloaded, 500);
+        assert_eq!(throughput.eta, None);
+
+        let throughput = tracker.update(500, Some(4000), start + Duration::from_millis(1000));
+        assert_eq!(throughput.downloaded, 1000);
+        assert_eq!(throughput.bytes_per_second, 1000);
+        assert_eq!(throughput.eta, Some(Duration::from_secs(3)));
+
+        // a pause does not drag the speed down
+        let throughput = tracker.update(1000, Some(4000), start + Duration::from_secs(60));
+        assert_eq!(throughput.bytes_per_second, 1000);
+        assert_eq!(throughput.eta, Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn it_does_not_count_resumed_bytes_towards_speed() {
+        let start = Instant::now();
+        let mut tracker = ThroughputTracker::new(3000, start);
+        assert_eq!(tracker.throughput(Some(4000)).downloaded, 3000);
+
+        let throughput = tracker.update(500, Some(4000), start + Duration::from_millis(1000));
+        assert_eq!(throughput.downloaded, 3500);
+        assert_eq!(throughput.bytes_per_second, 500);
+        assert_eq!(throughput.eta, Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn it_cancels_paused_download() {
+        let handle = DownloadHandle::new();
+        handle.pause();
+        assert!(handle.is_paused());
+
+        let waiting = handle.clone();
+        let checkpoint =
+            std::thread::spawn(move || tauri::async_runtime::block_on(waiting.checkpoint()));
+        handle.cancel();
+
+        assert!(matches!(
+            checkpoint.join().unwrap(),
+            Err(Error::DownloadCancelled)
+        ));
+        assert!(handle.is_cancelled());
+    }
+}
@@ -45,12 +45,33 @@ pub enum Error {
     /// Download failed
     #[error("`{0}`")]
     Network(String),
+    /// The download was cancelled with [`DownloadHandle::cancel`](crate::DownloadHandle::cancel).
+    #[error("The download was cancelled.")]
+    DownloadCancelled,
+    /// No binary patch can be applied to the installed package.
+    #[error("No binary patch applies to the installed package.")]
+    PatchNotFound,
+    /// The server answered a resumed download with a range that does not match the partially downloaded package.
+    #[error("The server returned an unexpected `Content-Range` when resuming the download.")]
+    InvalidContentRange,
     /// `minisign_verify` errors.
     #[error(transparent)]
     Minisign(#[from] minisign_verify::Error),
     /// `base64` errors.
     #[error(transparent)]
     Base64(#[from] base64::DecodeError),
+    /// The update was signed with a key that is not trusted.
+    #[error("The update was signed with the public key `{0}` which is not trusted.")]
+    UntrustedSigningKey(String),
+    /// The update was signed with a trusted key outside of its validity window.
+    #[error("The update was signed with the public key `{0}` which is not valid at this time.")]
+    SigningKeyNotValid(String),
+    /// The signature could not be verified with the trusted key it claims to be made with.
+    #[error("The signature could not be verified with the public key `{key_id}`: {source}")]
+    InvalidSignature {
+        key_id: String,
+        source: minisign_verify::Error,
+    },
     /// UTF8 Errors in signature.
     #[error("The signature {0} could not be decoded, please check if it is a valid base64 string. The signature must be the contents of the `.sig` file generated by the Tauri bundler, as a string.")]
     SignatureUtf8(String),
@@ -65,6 +86,9 @@ pub enum Error {
     BinaryNotFoundInArchive,
     #[error("invalid updater binary format")]
     InvalidUpdaterFormat,
+    /// The package manager failed to install the `deb` or `rpm` update.
+    #[error("failed to install the update package: {0}")]
+    PackageInstallFailed(String),
     #[error(transparent)]
     Http(#[from] http::Error),
     #[error(transparent)]
@@ -13,22 +13,38 @@
     html_favicon_url = "https://github.com/tauri-apps/tauri/raw/dev/app-icon.png"
 )]
 
-use std::ffi::OsString;
+use std::{
+    collections::hash_map::RandomState,
+    ffi::OsString,
+    hash::{BuildHasher, Hasher},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
 
 use tauri::{
     plugin::{Builder as PluginBuilder, TauriPlugin},
-    Manager, Runtime,
+    Manager, RunEvent, Runtime,
 };
 
 mod commands;
 mod config;
+mod download;
 mod error;
+mod rollback;
+mod scheduler;
 mod updater;
 
-pub use config::Config;
+pub use config::{Config, TrustedKey};
+pub use download::DownloadHandle;
 pub use error::{Error, Result};
+pub use rollback::FailedUpdate;
+pub use scheduler::{UpdatePolicy, UPDATE_AVAILABLE_EVENT, UPDATE_DOWNLOADED_EVENT};
 pub use updater::*;
 
+use rollback::{RollbackState, Startup};
+use scheduler::Schedule;
+
 /// Extensions to [`tauri::App`], [`tauri::AppHandle`], [`tauri::WebviewWindow`], [`tauri::Webview`] and [`tauri::Window`] to access the updater APIs.
 pub trait UpdaterExt<R: Runtime> {
     /// Gets the updater builder to build and updater
@@ -65,13 +81,43 @@ pub trait UpdaterExt<R: Runtime> {
     ///   });
     /// ```
     fn updater(&self) -> Result<Updater>;
+
+    /// Confirms the running version is healthy so the previous version is not restored,
+    /// see [`Builder::health_check_window`].
+    fn confirm_healthy(&self);
+
+    /// The update that was rolled back because it was not confirmed healthy in time,
+    /// if the previous version was restored before this launch.
+    fn failed_update(&self) -> Option<FailedUpdate>;
+
+    /// Installs the update downloaded to `path` by [`Update::download_to_file`] when the app exits,
+    /// so the next launch is on the new version without interrupting the current session.
+    ///
+    /// Replaces the update previously staged, if any.
+    fn install_on_exit(&self, update: Update, path: PathBuf);
+
+    /// Cancels the installation staged by [`UpdaterExt::install_on_exit`],
+    /// returning the version that would have been installed.
+    fn cancel_install_on_exit(&self) -> Option<String>;
+
+    /// Installs the update downloaded by the background checks with [`UpdatePolicy::Download`],
+    /// returning its version, or `None` if no update was downloaded.
+    fn install_downloaded_update(&self) -> Result<Option<String>>;
 }
 
 impl<R: Runtime, T: Manager<R>> UpdaterExt<R> for T {
     fn updater_builder(&self) -> UpdaterBuilder {
         let app = self.app_handle();
         let package_info = app.package_info();
-        let UpdaterState { config, target } = self.state::<UpdaterState>().inner();
+        let UpdaterState {
+            config,
+            target,
+            channel,
+            install_id,
+            health_check_window,
+            state_dir,
+            linux_installer,
+        } = self.state::<UpdaterState>().inner();
 
         let mut builder = UpdaterBuilder::new(
             package_info.name.clone(),
@@ -83,6 +129,14 @@ impl<R: Runtime, T: Manager<R>> UpdaterExt<R> for T {
             builder = builder.target(target);
         }
 
+        if let Some(channel) = channel {
+            builder = builder.channel(channel);
+        }
+
+        if let Some(install_id) = install_id {
+            builder = builder.install_id(install_id);
+        }
+
         let args = self.env().args_os;
         if !args.is_empty() {
             builder = builder.current_exe_args(args);
@@ -102,6 +156,34 @@ impl<R: Runtime, T: Manager<R>> UpdaterExt<R> for T {
             }
         }
 
+        #[cfg(target_os = "linux")]
+        {
+            // detecting the installer runs the package managers, only do it once
+            let installer = linux_installer.get_or_init(|| {
+                app.env()
+                    .appimage
+                    .map(PathBuf::from)
+                    .or_else(|| tauri::utils::platform::current_exe().ok())
+                    .map_or(LinuxInstaller::AppImage, |path| {
+                        LinuxInstaller::detect(&path)
+                    })
+            });
+            builder = builder.linux_installer(*installer);
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = linux_installer;
+
+        if let Ok(cache_dir) = app.path().app_cache_dir() {
+            builder = builder.download_dir(cache_dir.join("updates"));
+        }
+
+        if let Some(state_dir) = state_dir {
+            builder = builder.state_dir(state_dir);
+        }
+        if let Some(window) = health_check_window {
+            builder = builder.health_check_window(*window);
+        }
+
         let app_handle = app.app_handle().clone();
         builder = builder.on_before_exit(move || {
             app_handle.cleanup_before_exit();
@@ -113,18 +195,120 @@ impl<R: Runtime, T: Manager<R>> UpdaterExt<R> for T {
     fn updater(&self) -> Result<Updater> {
         self.updater_builder().build()
     }
+
+    fn confirm_healthy(&self) {
+        if let Some(rollback) = self.try_state::<RollbackState>() {
+            rollback.confirm_healthy();
+        }
+    }
+
+    fn failed_update(&self) -> Option<FailedUpdate> {
+        self.try_state::<RollbackState>()
+            .and_then(|rollback| rollback.failed_update.clone())
+    }
+
+    fn install_on_exit(&self, mut update: Update, path: PathBuf) {
+        update.installing_on_exit = true;
+        if let Some(staged) = self.try_state::<StagedUpdate>() {
+            staged.0.lock().unwrap().replace((update, path));
+        }
+    }
+
+    fn cancel_install_on_exit(&self) -> Option<String> {
+        self.try_state::<StagedUpdate>()
+            .and_then(|staged| staged.0.lock().unwrap().take())
+            .map(|(update, _)| update.version)
+    }
+
+    fn install_downloaded_update(&self) -> Result<Option<String>> {
+        let Some(downloaded) = self.try_state::<DownloadedUpdate>() else {
+            return Ok(None);
+        };
+        let mut downloaded = downloaded.0.lock().unwrap();
+        if let Some((update, path)) = downloaded.as_ref() {
+            // kept on failure so the installation can be retried
+            update.install_from_file(path)?;
+        }
+        Ok(downloaded.take().map(|(update, _)| update.version))
+    }
+}
+
+/// The update downloaded by the background checks, see [`UpdaterExt::install_downloaded_update`].
+#[derive(Default)]
+struct DownloadedUpdate(Mutex<Option<(Update, PathBuf)>>);
+
+/// The update staged by [`UpdaterExt::install_on_exit`].
+#[derive(Default)]
+struct StagedUpdate(Mutex<Option<(Update, PathBuf)>>);
+
+impl StagedUpdate {
+    fn version(&self) -> Option<String> {
+        self.0
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|(update, _)| update.version.clone())
+    }
+
+    fn install(&self) -> Result<()> {
+        match self.0.lock().unwrap().take() {
+            Some((update, path)) => update.install_from_file(path),
+            None => Ok(()),
+        }
+    }
 }
 
 struct UpdaterState {
     target: Option<String>,
+    channel: Option<String>,
+    install_id: Option<String>,
+    health_check_window: Option<Duration>,
+    state_dir: Option<PathBuf>,
+    /// How the app was installed on Linux, detected on first use.
+    linux_installer: OnceLock<LinuxInstaller>,
     config: Config,
 }
 
+/// Reads the identifier of this install used for staged rollouts, generating it on first use.
+fn read_or_create_install_id(path: &Path) -> Option<String> {
+    if let Ok(id) = std::fs::read_to_string(path) {
+        let id = id.trim();
+        if !id.is_empty() {
+            return Some(id.to_string());
+        }
+    }
+
+    let id = format!("{:016x}{:016x}", random_u64(), random_u64());
+
+    std::fs::create_dir_all(path.parent()?).ok()?;
+    std::fs::write(path, &id).ok()?;
+    Some(id)
+}
+
+/// A random number, good enough for anonymous ids and jitter since `RandomState` is randomly seeded.
+pub(crate) fn random_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    hasher.finish()
+}
+
 #[derive(Default)]
 pub struct Builder {
     target: Option<String>,
+    channel: Option<String>,
     pubkey: Option<String>,
+    trusted_keys: Vec<TrustedKey>,
     installer_args: Vec<OsString>,
+    linux_installer_args: Vec<OsString>,
+    health_check_window: Option<Duration>,
+    check_interval: Option<Duration>,
+    check_jitter: Duration,
+    check_policy: UpdatePolicy,
 }
 
 impl Builder {
@@ -137,11 +321,23 @@ impl Builder {
         self
     }
 
+    /// Sets the default release channel, see [`UpdaterBuilder::channel`].
+    pub fn channel(mut self, channel: impl Into<String>) -> Self {
+        self.channel.replace(channel.into());
+        self
+    }
+
     pub fn pubkey<S: Into<String>>(mut self, pubkey: S) -> Self {
         self.pubkey.replace(pubkey.into());
         self
     }
 
+    /// Adds a public key trusted to sign updates in addition to the configured ones.
+    pub fn trusted_key(mut self, key: TrustedKey) -> Self {
+        self.trusted_keys.push(key);
+        self
+    }
+
     pub fn installer_args<I, S>(mut self, args: I) -> Self
     where
         I: IntoIterator<Item = S>,
@@ -165,20 +361,124 @@ impl Builder {
         self
     }
 
+    /// Adds arguments given to `dpkg` or `rpm` when installing a `deb` or `rpm` update.
+    pub fn linux_installer_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<OsString>,
+    {
+        self.linux_installer_args
+            .extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Keeps the previous version after installing an update and restores it if the new version
+    /// does not call [`UpdaterExt::confirm_healthy`] within `window` after its first launch.
+    ///
+    /// The failure is then reported by [`UpdaterExt::failed_update`] on the next launch.
+    /// Only supported for AppImage installs on Linux.
+    pub fn health_check_window(mut self, window: Duration) -> Self {
+        self.health_check_window.replace(window);
+        self
+    }
+
+    /// Checks for updates in the background every `interval`, emitting [`UPDATE_AVAILABLE_EVENT`]
+    /// when an update is found, see [`Builder::check_policy`].
+    ///
+    /// The time of the last check is persisted so restarting the app does not check more often than `interval`.
+    pub fn check_interval(mut self, interval: Duration) -> Self {
+        self.check_interval.replace(interval);
+        self
+    }
+
+    /// Delays each background check by a random duration up to `jitter`,
+    /// so many installs do not reach the update server at the same time.
+    pub fn check_jitter(mut self, jitter: Duration) -> Self {
+        self.check_jitter = jitter;
+        self
+    }
+
+    /// What the background checks do when an update is available. Defaults to [`UpdatePolicy::Notify`].
+    pub fn check_policy(mut self, policy: UpdatePolicy) -> Self {
+        self.check_policy = policy;
+        self
+    }
+
     pub fn build<R: Runtime>(self) -> TauriPlugin<R, Config> {
         let pubkey = self.pubkey;
+        let trusted_keys = self.trusted_keys;
         let target = self.target;
+        let channel = self.channel;
         let installer_args = self.installer_args;
+        let linux_installer_args = self.linux_installer_args;
+        let health_check_window = self.health_check_window;
+        let schedule = self.check_interval.map(|interval| Schedule {
+            interval,
+            jitter: self.check_jitter,
+            policy: self.check_policy,
+        });
         PluginBuilder::<R, Config>::new("updater")
             .setup(move |app, api| {
                 let mut config = api.config().clone();
                 if let Some(pubkey) = pubkey {
                     config.pubkey = pubkey;
                 }
+                config.pubkeys.extend(trusted_keys);
                 if let Some(windows) = &mut config.windows {
                     windows.installer_args.extend_from_slice(&installer_args);
                 }
-                app.manage(UpdaterState { target, config });
+                if !linux_installer_args.is_empty() {
+                    config
+                        .linux
+                        .get_or_insert_with(Default::default)
+                        .installer_args
+                        .extend(linux_installer_args);
+                }
+                let updater_dir = app
+                    .path()
+                    .app_data_dir()
+                    .ok()
+                    .map(|dir| dir.join("updater"));
+                let install_id = updater_dir
+                    .as_ref()
+                    .and_then(|dir| read_or_create_install_id(&dir.join("install-id")));
+                app.manage(UpdaterState {
+                    target,
+                    channel,
+                    install_id,
+                    health_check_window,
+                    state_dir: updater_dir.clone(),
+                    linux_installer: OnceLock::new(),
+                    config,
+                });
+                app.manage(StagedUpdate::default());
+                app.manage(DownloadedUpdate::default());
+
+                if let Some(dir) = updater_dir {
+                    let version = app.package_info().version.to_string();
+                    let mut failed_update = None;
+                    // a corrupted rollback state must not prevent the app from starting
+                    match rollback::on_startup(&dir, &version).unwrap_or(Startup::Idle) {
+                        Startup::Idle => {}
+                        Startup::RolledBack(failed) => failed_update = Some(failed),
+                        Startup::AwaitingConfirmation(remaining) => {
+                            let app = app.clone();
+                            std::thread::spawn(move || {
+                                std::thread::sleep(remaining);
+                                if let Some(rollback) = app.try_state::<RollbackState>() {
+                                    let _ = rollback.expire();
+                                }
+                            });
+                        }
+                    }
+                    app.manage(RollbackState::new(dir, version, failed_update));
+                }
+
+                if let Some(schedule) = schedule {
+                    let state_dir = app.state::<UpdaterState>().state_dir.clone();
+                    scheduler::spawn(app.clone(), schedule, state_dir);
+                }
+
                 Ok(())
             })
             .invoke_handler(tauri::generate_handler![
@@ -186,7 +486,25 @@ impl Builder {
                 commands::download,
                 commands::install,
                 commands::download_and_install,
+                commands::download_and_install_on_exit,
+                commands::cancel_install_on_exit,
+                commands::install_downloaded_update,
+                commands::new_download_handle,
+                commands::pause_download,
+                commands::resume_download,
+                commands::cancel_download,
+                commands::confirm_healthy,
+                commands::failed_update,
             ])
+            .on_event(|app, event| {
+                if let RunEvent::Exit = event {
+                    if let Some(staged) = app.try_state::<StagedUpdate>() {
+                        if let Err(e) = staged.install() {
+                            log::error!("failed to install the update staged for exit: {e}");
+                        }
+                    }
+                }
+            })
             .build()
     }
 }
@@ -0,0 +1,271 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Keeps the previously installed package around after an update so it can be restored
+//! if the new version does not confirm it is healthy in time.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+const PENDING_UPDATE_FILE: &str = "pending-update.json";
+const BACKUP_DIR: &str = "previous";
+
+/// Where the rollback state is stored and how long the new version has to confirm it is healthy.
+#[derive(Debug, Clone)]
+pub(crate) struct HealthCheck {
+    pub(crate) dir: PathBuf,
+    pub(crate) window: Duration,
+}
+
+/// An update that was rolled back because the new version did not call `confirm_healthy` in time.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedUpdate {
+    /// The version that was rolled back.
+    pub version: String,
+    /// The version that was restored.
+    pub previous_version: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PendingUpdate {
+    previous_version: String,
+    version: String,
+    /// The installed package, replaced by the backup on rollback.
+    artifact: PathBuf,
+    backup: PathBuf,
+    window_secs: u64,
+    /// Seconds since the UNIX epoch of the first launch of the new version.
+    first_launch: Option<u64>,
+    rolled_back: bool,
+}
+
+impl PendingUpdate {
+    fn read(dir: &Path) -> Option<Self> {
+        std::fs::read(dir.join(PENDING_UPDATE_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+    }
+
+    fn write(&self, dir: &Path) -> Result<()> {
+        std::fs::write(dir.join(PENDING_UPDATE_FILE), serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    fn remove(self, dir: &Path) {
+        let _ = std::fs::remove_file(&self.backup);
+        let _ = std::fs::remove_file(dir.join(PENDING_UPDATE_FILE));
+    }
+
+    fn restore(&mut self, dir: &Path) -> Result<()> {
+        move_file(&self.backup, &self.artifact)?;
+        self.rolled_back = true;
+        self.write(dir)
+    }
+}
+
+/// What the plugin must do after [`on_startup`].
+pub(crate) enum Startup {
+    /// Nothing to do.
+    Idle,
+    /// The new version is running and must confirm it is healthy before the deadline.
+    AwaitingConfirmation(Duration),
+    /// The previous version was restored.
+    RolledBack(FailedUpdate),
+}
+
+/// Tracks the rollback state of the running app.
+pub(crate) struct RollbackState {
+    pub(crate) dir: PathBuf,
+    pub(crate) version: String,
+    pub(crate) failed_update: Option<FailedUpdate>,
+    lock: Mutex<()>,
+}
+
+impl RollbackState {
+    pub(crate) fn new(dir: PathBuf, version: String, failed_update: Option<FailedUpdate>) -> Self {
+        Self {
+            dir,
+            version,
+            failed_update,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Marks the running version as healthy, discarding the backup of the previous version.
+    pub(crate) fn confirm_healthy(&self) {
+        let _lock = self.lock.lock().unwrap();
+        if let Some(pending) = PendingUpdate::read(&self.dir) {
+            if !pending.rolled_back && pending.version == self.version {
+                pending.remove(&self.dir);
+            }
+        }
+    }
+
+    /// Restores the previous version if the running one was not confirmed healthy.
+    pub(crate) fn expire(&self) -> Result<()> {
+        let _lock = self.lock.lock().unwrap();
+        match PendingUpdate::read(&self.dir) {
+            Some(mut pending) if !pending.rolled_back && pending.version == self.version => {
+                pending.restore(&self.dir)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Moves the backup of the previous package out of the temporary install directory
+/// and records the update as pending confirmation.
+pub(crate) fn keep_backup(
+    health_check: &HealthCheck,
+    backup: &Path,
+    artifact: &Path,
+    previous_version: &str,
+    version: &str,
+) -> Result<()> {
+    let backup_dir = health_check.dir.join(BACKUP_DIR);
+    std::fs::create_dir_all(&backup_dir)?;
+
+    let kept_backup = backup_dir.join(artifact.file_name().unwrap_or(backup.as_os_str()));
+    move_file(backup, &kept_backup)?;
+
+    PendingUpdate {
+        previous_version: previous_version.into(),
+        version: version.into(),
+        artifact: artifact.into(),
+        backup: kept_backup,
+        window_secs: health_check.window.as_secs(),
+        first_launch: None,
+        rolled_back: false,
+    }
+    .write(&health_check.dir)
+}
+
+/// Evaluates the pending update, if any, when the app starts.
+pub(crate) fn on_startup(dir: &Path, current_version: &str) -> Result<Startup> {
+    let Some(mut pending) = PendingUpdate::read(dir) else {
+        return Ok(Startup::Idle);
+    };
+
+    if pending.rolled_back {
+        let failed_update = FailedUpdate {
+            version: pending.version.clone(),
+            previous_version: pending.previous_version.clone(),
+        };
+        let restored = current_version == pending.previous_version;
+        pending.remove(dir);
+        return Ok(if restored {
+            Startup::RolledBack(failed_update)
+        } else {
+            Startup::Idle
+        });
+    }
+
+    // the update was not installed or a different version was installed since
+    if current_version != pending.version {
+        pending.remove(dir);
+        return Ok(Startup::Idle);
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let first_launch = match pending.first_launch {
+        Some(first_launch) => first_launch,
+        None => {
+            pending.first_launch.replace(now);
+            pending.write(dir)?;
+            now
+        }
+    };
+
+    let deadline = first_launch + pending.window_secs;
+    if now >= deadline {
+        pending.restore(dir)?;
+        Ok(Startup::Idle)
+    } else {
+        Ok(Startup::AwaitingConfirmation(Duration::from_secs(
+            deadline - now,
+        )))
+    }
+}
+
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    // renaming fails across mount points, copy the file instead
+    if std::fs::rename(from, to).is_err() {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn install(dir: &Path, window: Duration) -> PathBuf {
+        let artifact = dir.join("app.AppImage");
+        let backup = dir.join("backup.AppImage");
+        std::fs::write(&backup, "1.0.0").unwrap();
+        std::fs::write(&artifact, "1.1.0").unwrap();
+
+        let health_check = HealthCheck {
+            dir: dir.join("updater"),
+            window,
+        };
+        keep_backup(&health_check, &backup, &artifact, "1.0.0", "1.1.0").unwrap();
+        artifact
+    }
+
+    #[test]
+    fn it_waits_for_confirmation() {
+        let dir = tempfile::tempdir().unwrap();
+        let artifact = install(dir.path(), Duration::from_secs(60));
+        let state_dir = dir.path().join("updater");
+
+        assert!(matches!(
+            on_startup(&state_dir, "1.1.0").unwrap(),
+            Startup::AwaitingConfirmation(_)
+        ));
+
+        RollbackState::new(state_dir.clone(), "1.1.0".into(), None).confirm_healthy();
+        assert!(matches!(
+            on_startup(&state_dir, "1.1.0").unwrap(),
+            Startup::Idle
+        ));
+        assert_eq!(std::fs::read_to_string(artifact).unwrap(), "1.1.0");
+        assert!(!state_dir.join(PENDING_UPDATE_FILE).exists());
+    }
+
+    #[test]
+    fn it_rolls_back_unconfirmed_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let artifact = install(dir.path(), Duration::ZERO);
+        let state_dir = dir.path().join("updater");
+
+        assert!(matches!(
+            on_startup(&state_dir, "1.1.0").unwrap(),
+            Startup::Idle
+        ));
+        assert_eq!(std::fs::read_to_string(&artifact).unwrap(), "1.0.0");
+
+        match on_startup(&state_dir, "1.0.0").unwrap() {
+            Startup::RolledBack(failed) => {
+                assert_eq!(failed.version, "1.1.0");
+                assert_eq!(failed.previous_version, "1.0.0");
+            }
+            _ => panic!("expected the update to be rolled back"),
+        }
+        assert!(!state_dir.join(PENDING_UPDATE_FILE).exists());
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Periodically checks for updates in the background, see [`Builder::check_interval`](crate::Builder::check_interval).
+
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::{DownloadedUpdate, Result, StagedUpdate, Update, UpdaterExt};
+
+const LAST_CHECK_FILE: &str = "last-check";
+
+/// Emitted when a background check finds an update.
+pub const UPDATE_AVAILABLE_EVENT: &str = "updater://update-available";
+/// Emitted when a background download of an update is finished.
+pub const UPDATE_DOWNLOADED_EVENT: &str = "updater://update-downloaded";
+
+/// What the background update checks do when an update is available.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UpdatePolicy {
+    /// Only emits the `updater://update-available` event.
+    #[default]
+    Notify,
+    /// Also downloads the update with [`Update::download_to_file`] in the background
+    /// and emits the `updater://update-downloaded` event once it is verified,
+    /// install it with [`UpdaterExt::install_downloaded_update`].
+    Download,
+    /// Also downloads the update in the background and installs it when the app exits,
+    /// see [`UpdaterExt::install_on_exit`].
+    InstallOnQuit,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Schedule {
+    pub(crate) interval: Duration,
+    pub(crate) jitter: Duration,
+    pub(crate) policy: UpdatePolicy,
+}
+
+/// Payload of the [`UPDATE_AVAILABLE_EVENT`] and [`UPDATE_DOWNLOADED_EVENT`] events.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateEvent {
+    current_version: String,
+    version: String,
+    date: Option<String>,
+    body: Option<String>,
+}
+
+impl From<&Update> for UpdateEvent {
+    fn from(update: &Update) -> Self {
+        Self {
+            current_version: update.current_version.clone(),
+            version: update.version.clone(),
+            date: update.date.map(|d| d.to_string()),
+            body: update.body.clone(),
+        }
+    }
+}
+
+/// Spawns the background checks on the async runtime.
+pub(crate) fn spawn<R: Runtime>(app: AppHandle<R>, schedule: Schedule, state_dir: Option<PathBuf>) {
+    let last_check_path = state_dir.map(|dir| dir.join(LAST_CHECK_FILE));
+    let last_check = last_check_path.as_deref().and_then(read_last_check);
+    let delays = check_delays(last_check, schedule.interval, unix_now());
+    tauri::async_runtime::spawn(async move {
+        // the version already announced, and downloaded or staged depending on the policy
+        let mut handled_version = None;
+        for delay in delays {
+            tokio::time::sleep(delay + random_jitter(schedule.jitter)).await;
+
+            if let Some(path) = &last_check_path {
+                if let Err(e) = write_last_check(path, unix_now()) {
+                    log::warn!("failed to persist the last background update check: {e}");
+                }
+            }
+            // a failed check is not retried before the next interval
+            if let Err(e) = check(&app, schedule.policy, &mut handled_version).await {
+                log::warn!("background update check failed: {e}");
+            }
+        }
+    });
+}
+
+async fn check<R: Runtime>(
+    app: &AppHandle<R>,
+    policy: UpdatePolicy,
+    handled_version: &mut Option<String>,
+) -> Result<()> {
+    let Some(update) = app.updater()?.check().await? else {
+        return Ok(());
+    };
+
+    let staged_version = app
+        .try_state::<StagedUpdate>()
+        .and_then(|staged| staged.version());
+    if handled_version.as_ref() == Some(&update.version)
+        || staged_version.as_ref() == Some(&update.version)
+    {
+        return Ok(());
+    }
+
+    let _ = app.emit(UPDATE_AVAILABLE_EVENT, UpdateEvent::from(&update));
+
+    if policy != UpdatePolicy::Notify {
+        let path = update.download_to_file(|_, _| {}, || {}).await?;
+        let event = UpdateEvent::from(&update);
+        handled_version.replace(update.version.clone());
+
+        if policy == UpdatePolicy::InstallOnQuit {
+            app.install_on_exit(update, path);
+        } else if let Some(downloaded) = app.try_state::<DownloadedUpdate>() {
+            downloaded.0.lock().unwrap().replace((update, path));
+        }
+        // only emitted once the update can be installed
+        let _ = app.emit(UPDATE_DOWNLOADED_EVENT, event);
+        return Ok(());
+    }
+
+    handled_version.replace(update.version);
+    Ok(())
+}
+
+/// The delays before each background check.
+///
+/// Only the first one depends on the check persisted by a previous run, the next checks always wait
+/// a whole `interval` so they do not run in a loop when the state cannot be persisted or the clock goes back.
+fn check_delays(
+    last_check: Option<u64>,
+    interval: Duration,
+    now: u64,
+) -> impl Iterator<Item = Duration> {
+    std::iter::once(next_check_delay(last_check, interval, now)).chain(std::iter::repeat(interval))
+}
+
+/// How long to wait before the next check so restarts do not check more often than `interval`.
+fn next_check_delay(last_check: Option<u64>, interval: Duration, now: u64) -> Duration {
+    match last_check {
+        // a last check in the future means the clock went back, check right away
+        Some(last_check) if last_check <= now => {
+            interval.saturating_sub(Duration::from_secs(now - last_check))
+        }
+        _ => Duration::ZERO,
+    }
+}
+
+fn random_jitter(jitter: Duration) -> Duration {
+    let millis = jitter.as_millis() as u64;
+    if millis == 0 {
+        Duration::ZERO
+    } else {
+        Duration::from_millis(crate::random_u64() % millis)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn read_last_check(path: &Path) -> Option<u64> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn write_last_check(path: &Path, timestamp: u64) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, timestamp.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_computes_next_check_delay() {
+        let interval = Duration::from_secs(3600);
+
+        assert_eq!(next_check_delay(None, interval, 10_000), Duration::ZERO);
+        assert_eq!(
+            next_check_delay(Some(10_000 - 600), interval, 10_000),
+            Duration::from_secs(3000)
+        );
+        assert_eq!(
+            next_check_delay(Some(10_000 - 7200), interval, 10_000),
+            Duration::ZERO
+        );
+        assert_eq!(
+            next_check_delay(Some(20_000), interval, 10_000),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn it_waits_an_interval_between_checks_without_state() {
+        let interval = Duration::from_secs(3600);
+
+        assert_eq!(
+            check_delays(None, interval, 10_000)
+                .take(3)
+                .collect::<Vec<_>>(),
+            vec![Duration::ZERO, interval, interval]
+        );
+        assert_eq!(
+            check_delays(Some(10_000 - 600), interval, 10_000)
+                .take(2)
+                .collect::<Vec<_>>(),
+            vec![Duration::from_secs(3000), interval]
+        );
+    }
+
+    #[test]
+    fn it_persists_last_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("updater").join(LAST_CHECK_FILE);
+
+        assert_eq!(read_last_check(&path), None);
+        write_last_check(&path, 1234).unwrap();
+        assert_eq!(read_last_check(&path), Some(1234));
+    }
+}
@@ -5,7 +5,8 @@
 use std::{
     collections::HashMap,
     ffi::{OsStr, OsString},
-    io::Cursor,
+    fs::{File, OpenOptions},
+    io::{Cursor, Read, Seek, Write},
     path::{Path, PathBuf},
     str::FromStr,
     sync::Arc,
@@ -15,10 +16,10 @@ use std::{
 use base64::Engine;
 use futures_util::StreamExt;
 use http::HeaderName;
-use minisign_verify::{PublicKey, Signature};
+use minisign_verify::{PublicKey, Signature, StreamVerifier};
 use percent_encoding::{AsciiSet, CONTROLS};
 use reqwest::{
-    header::{HeaderMap, HeaderValue},
+    header::{HeaderMap, HeaderValue, CONTENT_RANGE, ETAG, IF_RANGE, RANGE},
     ClientBuilder, StatusCode,
 };
 use semver::Version;
@@ -28,18 +29,89 @@ use time::OffsetDateTime;
 use url::Url;
 
 use crate::{
+    config::TrustedKey,
     error::{Error, Result},
-    Config,
+    rollback::HealthCheck,
+    Config, DownloadHandle,
 };
 
 const UPDATER_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
 
+/// File in the updater state directory listing the public keys announced by [`KeyRotation`]s.
+const ROTATED_KEYS_FILE: &str = "trusted-keys.json";
+
+/// The channel used when none is set with [`UpdaterBuilder::channel`].
+pub const DEFAULT_CHANNEL: &str = "stable";
+
+/// How many times [`Update::download_to_file`] resumes an interrupted download before giving up.
+const MAX_DOWNLOAD_ATTEMPTS: usize = 3;
+
+/// How many bytes of the updater package are read to detect its format.
+const PACKAGE_HEAD_LEN: u64 = 512;
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ReleaseManifestPlatform {
     /// Download URL for the platform
     pub url: Url,
     /// Signature for the platform
     pub signature: String,
+    /// Binary patches producing this platform's package from a previously installed version,
+    /// keyed by that version.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub patches: HashMap<String, ReleaseManifestPatch>,
+}
+
+/// A binary patch that reconstructs the updater package from the currently installed one.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ReleaseManifestPatch {
+    /// Download URL for the patch
+    pub url: Url,
+    /// Signature of the package reconstructed by applying the patch
+    pub signature: String,
+    /// Format of the patch
+    #[serde(default)]
+    pub format: PatchFormat,
+}
+
+/// Announces a new public key, signed by a currently trusted key, so future releases can be signed with it.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct KeyRotation {
+    /// The new public key
+    pub pubkey: String,
+    /// Signature of the `pubkey` value made with a currently trusted key,
+    /// for instance with `tauri signer sign` on a file containing the exact `pubkey` value
+    pub signature: String,
+}
+
+/// Binary diff formats supported for [`ReleaseManifestPatch`].
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum PatchFormat {
+    /// A zstd frame compressed using the installed package as a dictionary,
+    /// as generated by `zstd --patch-from <installed package> <new package>`.
+    #[default]
+    Zstd,
+}
+
+#[cfg(feature = "delta")]
+impl PatchFormat {
+    /// Applies the `patch` to the `base` package and writes the reconstructed package to `writer`.
+    fn apply(&self, base: &[u8], patch: &[u8], writer: &mut impl Write) -> Result<()> {
+        match self {
+            Self::Zstd => {
+                let mut decoder = zstd::stream::read::Decoder::with_dictionary(patch, base)?;
+                // `--patch-from` uses a window as large as the installed package
+                decoder.window_log_max(if cfg!(target_pointer_width = "64") {
+                    31
+                } else {
+                    30
+                })?;
+                std::io::copy(&mut decoder, writer)?;
+                Ok(())
+            }
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -55,6 +127,7 @@ pub enum RemoteReleaseInner {
 ///
 /// This type can have one of two shapes: Server Format (Dynamic Format) and Static Format.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct RemoteRelease {
     /// Version to install.
     pub version: Version,
@@ -62,6 +135,14 @@ pub struct RemoteRelease {
     pub notes: Option<String>,
     /// Release date.
     pub pub_date: Option<OffsetDateTime>,
+    /// Release channel, releases without a channel are available on every channel.
+    pub channel: Option<String>,
+    /// Percentage (from 0 to 100) of installs the release is rolled out to.
+    pub rollout: Option<f64>,
+    /// New public key announced by the release.
+    pub key_rotation: Option<KeyRotation>,
+    /// Oldest version that can update to this release, older versions must update to an intermediate release first.
+    pub min_supported_version: Option<Version>,
     /// Release data.
     pub data: RemoteReleaseInner,
 }
@@ -90,10 +171,127 @@ impl RemoteRelease {
                 }),
         }
     }
+
+    /// Whether `current_version` can update to this release directly.
+    pub fn supports(&self, current_version: &Version) -> bool {
+        self.min_supported_version
+            .as_ref()
+            .map_or(true, |min| current_version >= min)
+    }
+
+    /// Whether the release is available on the given channel.
+    pub fn is_on_channel(&self, channel: &str) -> bool {
+        self.channel.as_deref().map_or(true, |c| c == channel)
+    }
+
+    /// Whether the staged rollout of the release includes the given install.
+    ///
+    /// Installs are deterministically assigned a bucket from their id and the release version,
+    /// so raising the rollout percentage never excludes an install that was previously included.
+    /// Without an install id, only fully rolled out releases are available.
+    pub fn is_rolled_out_to(&self, install_id: Option<&str>) -> bool {
+        match (self.rollout, install_id) {
+            (None, _) => true,
+            (Some(rollout), _) if rollout >= 100.0 => true,
+            (Some(rollout), Some(install_id)) => {
+                (rollout_bucket(install_id, &self.version) as f64) < rollout * 100.0
+            }
+            (Some(_), None) => false,
+        }
+    }
+
+    /// The release's patch for the given target that applies on top of `current_version`, if any.
+    pub fn patch(&self, target: &str, current_version: &Version) -> Option<&ReleaseManifestPatch> {
+        let platform = match self.data {
+            RemoteReleaseInner::Dynamic(ref platform) => platform,
+            RemoteReleaseInner::Static { ref platforms } => platforms.get(target)?,
+        };
+        platform
+            .patches
+            .iter()
+            .find(|(version, _)| {
+                Version::from_str(version.trim_start_matches('v'))
+                    .map_or(false, |version| &version == current_version)
+            })
+            .map(|(_, patch)| patch)
+    }
+}
+
+/// The notes of a release between the current version and the announced update.
+#[derive(Debug, Clone)]
+pub struct ReleaseNotes {
+    /// Version of the release.
+    pub version: String,
+    /// Release date.
+    pub date: Option<OffsetDateTime>,
+    /// Release notes.
+    pub notes: Option<String>,
+}
+
+/// Parses an update server response, either a single release
+/// or a static manifest listing several releases under `releases`.
+fn parse_releases(value: serde_json::Value) -> Result<Vec<RemoteRelease>> {
+    #[derive(Deserialize)]
+    struct ReleaseManifest {
+        releases: Vec<RemoteRelease>,
+    }
+
+    if value.get("releases").is_some() {
+        Ok(serde_json::from_value::<ReleaseManifest>(value)?.releases)
+    } else {
+        Ok(vec![serde_json::from_value(value)?])
+    }
 }
 
 pub type OnBeforeExit = Arc<dyn Fn() + Send + Sync + 'static>;
 
+/// How the application was installed on Linux, which determines how updates are installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinuxInstaller {
+    /// The AppImage is replaced in place.
+    AppImage,
+    /// The `.deb` package is installed with `dpkg -i`.
+    Deb,
+    /// The `.rpm` package is installed with `rpm -U`.
+    Rpm,
+}
+
+impl LinuxInstaller {
+    /// Detects how the given executable was installed.
+    ///
+    /// Falls back to [`LinuxInstaller::AppImage`] when no package manager owns the executable.
+    pub fn detect(executable_path: &Path) -> Self {
+        let owned_by = |program: &str, arg: &str| {
+            std::process::Command::new(program)
+                .arg(arg)
+                .arg(executable_path)
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .status()
+                .map_or(false, |status| status.success())
+        };
+
+        if std::env::var_os("APPIMAGE").is_some() {
+            Self::AppImage
+        } else if owned_by("dpkg", "-S") {
+            Self::Deb
+        } else if owned_by("rpm", "-qf") {
+            Self::Rpm
+        } else {
+            Self::AppImage
+        }
+    }
+
+    /// The suffix added to the updater target to find the matching package in the update manifest.
+    fn target_suffix(&self) -> Option<&'static str> {
+        match self {
+            Self::AppImage => None,
+            Self::Deb => Some("deb"),
+            Self::Rpm => Some("rpm"),
+        }
+    }
+}
+
 pub struct UpdaterBuilder {
     app_name: String,
     current_version: Version,
@@ -108,6 +306,13 @@ pub struct UpdaterBuilder {
     installer_args: Vec<OsString>,
     current_exe_args: Vec<OsString>,
     on_before_exit: Option<OnBeforeExit>,
+    download_dir: Option<PathBuf>,
+    channel: Option<String>,
+    install_id: Option<String>,
+    health_check_window: Option<Duration>,
+    state_dir: Option<PathBuf>,
+    linux_installer: Option<LinuxInstaller>,
+    linux_installer_args: Vec<OsString>,
 }
 
 impl UpdaterBuilder {
@@ -132,6 +337,17 @@ impl UpdaterBuilder {
             timeout: None,
             proxy: None,
             on_before_exit: None,
+            download_dir: None,
+            channel: None,
+            install_id: None,
+            health_check_window: None,
+            state_dir: None,
+            linux_installer: None,
+            linux_installer_args: config
+                .linux
+                .as_ref()
+                .map(|l| l.installer_args.clone())
+                .unwrap_or_default(),
         }
     }
 
@@ -148,6 +364,23 @@ impl UpdaterBuilder {
         self
     }
 
+    /// Sets the release channel, defaults to [`DEFAULT_CHANNEL`].
+    ///
+    /// The channel replaces the `{{channel}}` variable in the endpoints
+    /// and releases announcing a different channel are ignored.
+    pub fn channel(mut self, channel: impl Into<String>) -> Self {
+        self.channel.replace(channel.into());
+        self
+    }
+
+    /// Sets the identifier of this install, used to evaluate staged rollouts.
+    ///
+    /// [`crate::UpdaterExt::updater_builder`] sets it to an id persisted in the app data directory.
+    pub fn install_id(mut self, install_id: impl Into<String>) -> Self {
+        self.install_id.replace(install_id.into());
+        self
+    }
+
     pub fn endpoints(mut self, endpoints: Vec<Url>) -> Result<Self> {
         crate::config::validate_endpoints(
             &endpoints,
@@ -193,6 +426,12 @@ impl UpdaterBuilder {
         self
     }
 
+    /// Adds a public key trusted to sign updates in addition to [`Config::pubkey`].
+    pub fn trusted_key(mut self, key: TrustedKey) -> Self {
+        self.config.pubkeys.push(key);
+        self
+    }
+
     pub fn installer_arg<S>(mut self, arg: S) -> Self
     where
         S: Into<OsString>,
@@ -221,7 +460,51 @@ impl UpdaterBuilder {
         self
     }
 
-    pub fn build(self) -> Result<Updater> {
+    /// Sets the directory where [`Update::download_to_file`] stores the updater package.
+    ///
+    /// Defaults to the system temporary directory.
+    pub fn download_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.download_dir.replace(dir.as_ref().into());
+        self
+    }
+
+    /// Sets how the application was installed on Linux instead of detecting it with [`LinuxInstaller::detect`].
+    ///
+    /// Unless [`UpdaterBuilder::target`] is set, `deb` and `rpm` installs look for
+    /// the `<target>-deb` and `<target>-rpm` platforms in the update manifest, for instance `linux-x86_64-deb`.
+    pub fn linux_installer(mut self, installer: LinuxInstaller) -> Self {
+        self.linux_installer.replace(installer);
+        self
+    }
+
+    /// Adds arguments given to `dpkg` or `rpm` when installing a `deb` or `rpm` update.
+    pub fn linux_installer_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<OsString>,
+    {
+        self.linux_installer_args
+            .extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Keeps the previously installed package after an update and requires the new version
+    /// to call [`crate::UpdaterExt::confirm_healthy`] within `window` after its first launch,
+    /// otherwise the previous package is restored.
+    ///
+    /// Only supported for AppImage installs on Linux.
+    pub fn health_check_window(mut self, window: Duration) -> Self {
+        self.health_check_window.replace(window);
+        self
+    }
+
+    pub fn build(mut self) -> Result<Updater> {
+        if let Some(dir) = &self.state_dir {
+            self.config
+                .pubkeys
+                .extend(read_rotated_keys(dir).into_iter().map(TrustedKey::new));
+        }
+
         let endpoints = self
             .endpoints
             .unwrap_or_else(|| self.config.endpoints.clone());
@@ -230,16 +513,27 @@ impl UpdaterBuilder {
             return Err(Error::EmptyEndpoints);
         };
 
+        let executable_path = self.executable_path.clone().unwrap_or(current_exe()?);
+
+        let linux_installer = if cfg!(target_os = "linux") {
+            self.linux_installer
+                .unwrap_or_else(|| LinuxInstaller::detect(&executable_path))
+        } else {
+            LinuxInstaller::AppImage
+        };
+
         let arch = get_updater_arch().ok_or(Error::UnsupportedArch)?;
         let (target, json_target) = if let Some(target) = self.target {
             (target.clone(), target)
         } else {
             let target = get_updater_target().ok_or(Error::UnsupportedOs)?;
-            (target.to_string(), format!("{target}-{arch}"))
+            let json_target = match linux_installer.target_suffix() {
+                Some(suffix) => format!("{target}-{arch}-{suffix}"),
+                None => format!("{target}-{arch}"),
+            };
+            (target.to_string(), json_target)
         };
 
-        let executable_path = self.executable_path.clone().unwrap_or(current_exe()?);
-
         // Get the extract_path from the provided executable_path
         let extract_path = if cfg!(target_os = "linux") {
             executable_path
@@ -263,11 +557,27 @@ impl UpdaterBuilder {
             headers: self.headers,
             extract_path,
             on_before_exit: self.on_before_exit,
+            download_dir: self.download_dir,
+            channel: self.channel.unwrap_or_else(|| DEFAULT_CHANNEL.to_string()),
+            install_id: self.install_id,
+            health_check: self
+                .health_check_window
+                .zip(self.state_dir.clone())
+                .map(|(window, dir)| HealthCheck { dir, window }),
+            state_dir: self.state_dir,
+            linux_installer,
+            linux_installer_args: self.linux_installer_args,
         })
     }
 }
 
 impl UpdaterBuilder {
+    /// Sets the directory where the updater persists its state, like rotated keys and rollback data.
+    pub(crate) fn state_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.state_dir.replace(dir.as_ref().into());
+        self
+    }
+
     pub(crate) fn current_exe_args<I, S>(mut self, args: I) -> Self
     where
         I: IntoIterator<Item = S>,
@@ -295,6 +605,13 @@ pub struct Updater {
     headers: HeaderMap,
     extract_path: PathBuf,
     on_before_exit: Option<OnBeforeExit>,
+    download_dir: Option<PathBuf>,
+    channel: String,
+    install_id: Option<String>,
+    health_check: Option<HealthCheck>,
+    state_dir: Option<PathBuf>,
+    linux_installer: LinuxInstaller,
+    linux_installer_args: Vec<OsString>,
     #[allow(unused)]
     installer_args: Vec<OsString>,
     #[allow(unused)]
@@ -318,10 +635,10 @@ impl Updater {
             }
         }
 
-        let mut remote_release: Option<RemoteRelease> = None;
+        let mut remote_releases: Option<Vec<RemoteRelease>> = None;
         let mut last_error: Option<Error> = None;
         for url in &self.endpoints {
-            // replace {{current_version}}, {{target}} and {{arch}} in the provided URL
+            // replace {{current_version}}, {{target}}, {{arch}} and {{channel}} in the provided URL
             // this is useful if we need to query example
             // https://releases.myapp.com/update/{{target}}/{{arch}}/{{current_version}}
             // will be translated into ->
@@ -340,10 +657,12 @@ impl Updater {
                 .replace("%7B%7Bcurrent_version%7D%7D", &encoded_version)
                 .replace("%7B%7Btarget%7D%7D", &self.target)
                 .replace("%7B%7Barch%7D%7D", self.arch)
+                .replace("%7B%7Bchannel%7D%7D", &self.channel)
                 // but not query parameters
                 .replace("{{current_version}}", &encoded_version)
                 .replace("{{target}}", &self.target)
                 .replace("{{arch}}", self.arch)
+                .replace("{{channel}}", &self.channel)
                 .parse()?;
 
             let mut request = ClientBuilder::new().user_agent(UPDATER_USER_AGENT);
@@ -368,12 +687,10 @@ impl Updater {
                         return Ok(None);
                     };
 
-                    match serde_json::from_value::<RemoteRelease>(res.json().await?)
-                        .map_err(Into::into)
-                    {
-                        Ok(release) => {
+                    match parse_releases(res.json().await?) {
+                        Ok(releases) => {
                             last_error = None;
-                            remote_release = Some(release);
+                            remote_releases = Some(releases);
                             // we found a relase, break the loop
                             break;
                         }
@@ -390,16 +707,17 @@ impl Updater {
         }
 
         // Extracted remote metadata
-        let release = remote_release.ok_or(Error::ReleaseNotFound)?;
+        let releases = remote_releases.ok_or(Error::ReleaseNotFound)?;
 
-        let should_update = match self.version_comparator.as_ref() {
-            Some(comparator) => comparator(self.current_version.clone(), release.clone()),
-            None => release.version > self.current_version,
-        };
+        let update = if let Some((release, changelog)) = self.select_release(&releases) {
+            let mut config = self.config.clone();
+            let rotated_key = match &release.key_rotation {
+                Some(rotation) => accept_key_rotation(&mut config, rotation),
+                None => None,
+            };
 
-        let update = if should_update {
             Some(Update {
-                config: self.config.clone(),
+                config,
                 on_before_exit: self.on_before_exit.clone(),
                 app_name: self.app_name.clone(),
                 current_version: self.current_version.to_string(),
@@ -409,12 +727,24 @@ impl Updater {
                 date: release.pub_date,
                 download_url: release.download_url(&self.json_target)?.to_owned(),
                 body: release.notes.clone(),
+                changelog,
                 signature: release.signature(&self.json_target)?.to_owned(),
+                patch: release
+                    .patch(&self.json_target, &self.current_version)
+                    .cloned(),
                 timeout: self.timeout,
                 proxy: self.proxy.clone(),
                 headers: self.headers.clone(),
                 installer_args: self.installer_args.clone(),
                 current_exe_args: self.current_exe_args.clone(),
+                download_dir: self.download_dir.clone(),
+                download_handle: None,
+                installing_on_exit: false,
+                health_check: self.health_check.clone(),
+                rotated_key,
+                state_dir: self.state_dir.clone(),
+                linux_installer: self.linux_installer,
+                linux_installer_args: self.linux_installer_args.clone(),
             })
         } else {
             None
@@ -422,6 +752,98 @@ impl Updater {
 
         Ok(update)
     }
+
+    /// Picks the newest release this install can update to,
+    /// along with the notes of every release since the current version, newest first.
+    fn select_release<'a>(
+        &self,
+        releases: &'a [RemoteRelease],
+    ) -> Option<(&'a RemoteRelease, Vec<ReleaseNotes>)> {
+        let release = releases
+            .iter()
+            .filter(|release| {
+                release.is_on_channel(&self.channel)
+                    && release.is_rolled_out_to(self.install_id.as_deref())
+                    && release.supports(&self.current_version)
+                    && match self.version_comparator.as_ref() {
+                        Some(comparator) => {
+                            comparator(self.current_version.clone(), (*release).clone())
+                        }
+                        None => release.version > self.current_version,
+                    }
+            })
+            .max_by(|a, b| a.version.cmp(&b.version))?;
+
+        let mut changelog = releases
+            .iter()
+            .filter(|r| {
+                r.is_on_channel(&self.channel)
+                    && r.version > self.current_version
+                    && r.version <= release.version
+            })
+            .collect::<Vec<_>>();
+        changelog.sort_by(|a, b| b.version.cmp(&a.version));
+        changelog.dedup_by(|a, b| a.version == b.version);
+        let mut changelog = changelog
+            .into_iter()
+            .map(|r| ReleaseNotes {
+                version: r.version.to_string(),
+                date: r.pub_date,
+                notes: r.notes.clone(),
+            })
+            .collect::<Vec<_>>();
+        // a custom comparator may allow downgrades, which are not covered by the range above
+        if changelog.is_empty() {
+            changelog.push(ReleaseNotes {
+                version: release.version.to_string(),
+                date: release.pub_date,
+                notes: release.notes.clone(),
+            });
+        }
+
+        Some((release, changelog))
+    }
+}
+
+/// Trusts the announced key for this update if the announcement is signed by a currently trusted key.
+///
+/// Returns the key if it was not trusted yet, it is only persisted once the update is installed.
+/// An announcement that cannot be verified is ignored, the update is then verified with the current keys.
+fn accept_key_rotation(config: &mut Config, rotation: &KeyRotation) -> Option<String> {
+    if config
+        .trusted_keys()
+        .any(|key| key.pubkey == rotation.pubkey)
+    {
+        return None;
+    }
+
+    if let Err(e) = verify_signature(rotation.pubkey.as_bytes(), &rotation.signature, config) {
+        log::warn!("ignoring updater key rotation: {e}");
+        return None;
+    }
+
+    config
+        .pubkeys
+        .push(TrustedKey::new(rotation.pubkey.clone()));
+
+    Some(rotation.pubkey.clone())
+}
+
+fn read_rotated_keys(dir: &Path) -> Vec<String> {
+    std::fs::read(dir.join(ROTATED_KEYS_FILE))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn persist_rotated_key(dir: &Path, pubkey: &str) -> Result<()> {
+    let mut keys = read_rotated_keys(dir);
+    if !keys.iter().any(|key| key == pubkey) {
+        keys.push(pubkey.to_string());
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join(ROTATED_KEYS_FILE), serde_json::to_vec(&keys)?)?;
+    }
+    Ok(())
 }
 
 #[derive(Clone)]
@@ -431,6 +853,8 @@ pub struct Update {
     on_before_exit: Option<OnBeforeExit>,
     /// Update description
     pub body: Option<String>,
+    /// Notes of every release between the current version and the update, newest first
+    pub changelog: Vec<ReleaseNotes>,
     /// Version used to check for update
     pub current_version: String,
     /// Version announced
@@ -443,12 +867,18 @@ pub struct Update {
     pub download_url: Url,
     /// Signature announced
     pub signature: String,
+    /// Binary patch announced for the current version
+    pub patch: Option<ReleaseManifestPatch>,
     /// Request timeout
     pub timeout: Option<Duration>,
     /// Request proxy
     pub proxy: Option<Url>,
     /// Request headers
     pub headers: HeaderMap,
+    /// Handle to pause, resume or cancel the download
+    pub download_handle: Option<DownloadHandle>,
+    /// Whether the update is installed while the app exits, see [`crate::UpdaterExt::install_on_exit`]
+    pub(crate) installing_on_exit: bool,
     /// Extract path
     #[allow(unused)]
     extract_path: PathBuf,
@@ -459,6 +889,20 @@ pub struct Update {
     installer_args: Vec<OsString>,
     #[allow(unused)]
     current_exe_args: Vec<OsString>,
+    /// Directory used by [`Update::download_to_file`]
+    download_dir: Option<PathBuf>,
+    /// Rollback configuration, when enabled the previous package is kept after installing
+    #[allow(unused)]
+    health_check: Option<HealthCheck>,
+    /// Key announced by the release, trusted from now on once the update is installed
+    rotated_key: Option<String>,
+    /// Directory where the updater persists its state
+    state_dir: Option<PathBuf>,
+    /// How the application was installed on Linux
+    #[allow(unused)]
+    linux_installer: LinuxInstaller,
+    #[allow(unused)]
+    linux_installer_args: Vec<OsString>,
 }
 
 impl Resource for Update {}
@@ -466,12 +910,114 @@ impl Resource for Update {}
 impl Update {
     /// Downloads the updater package, verifies it then return it as bytes.
     ///
+    /// If the update announces a [`ReleaseManifestPatch`] for the current version and the `delta` feature is enabled,
+    /// the patch is downloaded and applied to the installed package instead,
+    /// falling back to the full package if that fails.
+    /// The progress then starts over: `on_chunk` is called with a zero-length chunk before the full package is downloaded.
+    ///
     /// Use [`Update::install`] to install it
     pub async fn download<C: FnMut(usize, Option<u64>), D: FnOnce()>(
         &self,
         mut on_chunk: C,
         on_download_finish: D,
     ) -> Result<Vec<u8>> {
+        #[cfg(feature = "delta")]
+        {
+            let mut bytes = Vec::new();
+            match self
+                .download_and_apply_patch(&mut on_chunk, &mut bytes)
+                .await
+            {
+                Ok(patch) => match verify_signature(&bytes, &patch.signature, &self.config) {
+                    Ok(_) => {
+                        on_download_finish();
+                        return Ok(bytes);
+                    }
+                    Err(e) => fall_back_from_patch(e, &mut on_chunk)?,
+                },
+                Err(e) => fall_back_from_patch(e, &mut on_chunk)?,
+            }
+        }
+
+        let buffer = self.fetch(&self.download_url, &mut on_chunk).await?;
+        on_download_finish();
+
+        verify_signature(&buffer, &self.signature, &self.config)?;
+
+        Ok(buffer)
+    }
+
+    /// Downloads the announced patch and applies it to the installed package,
+    /// writing the reconstructed package to `writer`.
+    ///
+    /// Returns the patch, whose signature must be verified against the reconstructed package.
+    #[cfg(feature = "delta")]
+    async fn download_and_apply_patch<C: FnMut(usize, Option<u64>)>(
+        &self,
+        on_chunk: &mut C,
+        writer: &mut impl Write,
+    ) -> Result<&ReleaseManifestPatch> {
+        let patch = self.applicable_patch()?;
+        let patch_bytes = self.fetch(&patch.url, on_chunk).await?;
+        // the installed package is the dictionary of the patch, which zstd needs in memory
+        let base = std::fs::read(&self.extract_path)?;
+        patch.format.apply(&base, &patch_bytes, writer)?;
+
+        Ok(patch)
+    }
+
+    #[cfg(feature = "delta")]
+    fn applicable_patch(&self) -> Result<&ReleaseManifestPatch> {
+        let patch = self.patch.as_ref().ok_or(Error::PatchNotFound)?;
+        // only single file packages, like the AppImage, can be patched
+        if !self.extract_path.is_file() {
+            return Err(Error::PatchNotFound);
+        }
+        Ok(patch)
+    }
+
+    /// Applies the announced patch like [`Update::download_and_apply_patch`],
+    /// moving the reconstructed package to `path` once its signature is verified.
+    #[cfg(feature = "delta")]
+    async fn download_and_apply_patch_to_file<C: FnMut(usize, Option<u64>)>(
+        &self,
+        on_chunk: &mut C,
+        path: &Path,
+    ) -> Result<()> {
+        self.applicable_patch()?;
+
+        // keep any partial download of the full package until the patch succeeds
+        let patched = path.with_extension("patched");
+        let mut file = File::create(&patched)?;
+        let result = match self.download_and_apply_patch(on_chunk, &mut file).await {
+            Ok(patch) => file
+                .sync_all()
+                .map_err(Into::into)
+                .and_then(|_| verify_file_signature(&patched, &patch.signature, &self.config)),
+            Err(e) => Err(e),
+        };
+        drop(file);
+
+        match result {
+            Ok(()) => {
+                std::fs::rename(&patched, path)?;
+                let _ = std::fs::remove_file(PartialDownload::path(path));
+                Ok(())
+            }
+            Err(e) => {
+                let _ = std::fs::remove_file(&patched);
+                Err(e)
+            }
+        }
+    }
+
+    async fn fetch<C: FnMut(usize, Option<u64>)>(
+        &self,
+        url: &Url,
+        on_chunk: &mut C,
+    ) -> Result<Vec<u8>> {
+        self.checkpoint().await?;
+
         // set our headers
         let mut headers = self.headers.clone();
         headers.insert(
@@ -489,7 +1035,7 @@ impl Update {
         }
         let response = request
             .build()?
-            .get(self.download_url.clone())
+            .get(url.clone())
             .headers(headers)
             .send()
             .await?;
@@ -512,47 +1058,345 @@ impl Update {
         let mut stream = response.bytes_stream();
         while let Some(chunk) = stream.next().await {
             let chunk = chunk?;
-            on_chunk(chunk.len(), content_length);
+            // zero-length chunks are reserved to start the progress over
+            if !chunk.is_empty() {
+                on_chunk(chunk.len(), content_length);
+            }
             buffer.extend(chunk);
+            self.checkpoint().await?;
+        }
+
+        Ok(buffer)
+    }
+
+    /// Waits while the download is paused and fails once it is cancelled.
+    async fn checkpoint(&self) -> Result<()> {
+        match &self.download_handle {
+            Some(handle) => handle.checkpoint().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Installs the updater package downloaded by [`Update::download`]
+    pub fn install(&self, bytes: impl AsRef<[u8]>) -> Result<()> {
+        let package = Package::Bytes(bytes.as_ref());
+        self.install_inner(package)?;
+        self.finish_install(package);
+        Ok(())
+    }
+
+    /// Downloads and installs the updater package
+    pub async fn download_and_install<C: FnMut(usize, Option<u64>), D: FnOnce()>(
+        &self,
+        on_chunk: C,
+        on_download_finish: D,
+    ) -> Result<()> {
+        let bytes = self.download(on_chunk, on_download_finish).await?;
+        self.install(bytes)
+    }
+
+    /// The path where [`Update::download_to_file`] stores the updater package.
+    pub fn download_path(&self) -> PathBuf {
+        self.download_dir
+            .clone()
+            .unwrap_or_else(std::env::temp_dir)
+            .join(format!(
+                "{}-{}-{}.download",
+                self.app_name, self.version, self.target
+            ))
+    }
+
+    /// Downloads the updater package to [`Update::download_path`], verifies it then returns its path.
+    ///
+    /// Unlike [`Update::download`], the package is streamed to disk instead of being buffered in memory,
+    /// and an interrupted download is resumed using HTTP `Range` requests, either right away
+    /// or on the next call for the same update.
+    /// Patches are applied and reported like with [`Update::download`].
+    /// When a previous download is resumed, the bytes it already received are reported as the first chunk.
+    ///
+    /// Use [`Update::install_from_file`] to install it
+    pub async fn download_to_file<C: FnMut(usize, Option<u64>), D: FnOnce()>(
+        &self,
+        mut on_chunk: C,
+        on_download_finish: D,
+    ) -> Result<PathBuf> {
+        let path = self.download_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        #[cfg(feature = "delta")]
+        match self
+            .download_and_apply_patch_to_file(&mut on_chunk, &path)
+            .await
+        {
+            Ok(()) => {
+                on_download_finish();
+                return Ok(path);
+            }
+            Err(e) => fall_back_from_patch(e, &mut on_chunk)?,
+        }
+
+        let signature = decode_signature(&self.signature)?;
+        let key = SigningKey::find(&self.config, &self.signature)?;
+        let mut progress = DownloadProgress::new(&key.public_key, &signature)?;
+
+        let mut request = ClientBuilder::new().user_agent(UPDATER_USER_AGENT);
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        if let Some(ref proxy) = self.proxy {
+            let proxy = reqwest::Proxy::all(proxy.as_str())?;
+            request = request.proxy(proxy);
+        }
+        let client = request.build()?;
+
+        let mut attempt = 1;
+        loop {
+            match self
+                .download_to_file_attempt(&client, &path, &mut progress, &mut on_chunk)
+                .await
+            {
+                Ok(()) => break,
+                // network errors are the only ones worth resuming from
+                Err(Error::Reqwest(_)) if attempt < MAX_DOWNLOAD_ATTEMPTS => attempt += 1,
+                Err(e) => return Err(e),
+            }
         }
         on_download_finish();
 
-        verify_signature(&buffer, &self.signature, &self.config.pubkey)?;
+        let verified = match progress.verifier.as_mut() {
+            Some(verifier) => verifier.finalize().map_err(|e| key.error(e)),
+            None => verify_legacy_file_signature(&path, &self.signature, &self.config),
+        };
 
-        Ok(buffer)
+        let _ = std::fs::remove_file(PartialDownload::path(&path));
+        if let Err(e) = verified {
+            let _ = std::fs::remove_file(&path);
+            return Err(e);
+        }
+
+        Ok(path)
     }
 
-    /// Installs the updater package downloaded by [`Update::download`]
-    pub fn install(&self, bytes: impl AsRef<[u8]>) -> Result<()> {
-        self.install_inner(bytes.as_ref())
+    async fn download_to_file_attempt<C: FnMut(usize, Option<u64>)>(
+        &self,
+        client: &reqwest::Client,
+        path: &Path,
+        progress: &mut DownloadProgress<'_>,
+        on_chunk: &mut C,
+    ) -> Result<()> {
+        // a paused download that lost its connection only reconnects once resumed
+        self.checkpoint().await?;
+
+        let state_path = PartialDownload::path(path);
+        let state = PartialDownload::read(&state_path)
+            .filter(|s| s.url.as_ref() == Some(&self.download_url));
+
+        // we can only resume if we are able to tell the remote package did not change
+        let existing_len = match (&state, path.metadata()) {
+            (Some(state), Ok(metadata))
+                if state.etag.is_some() || state.content_length.is_some() =>
+            {
+                metadata.len()
+            }
+            _ => 0,
+        };
+
+        let mut headers = self.headers.clone();
+        headers.insert(
+            "Accept",
+            HeaderValue::from_str("application/octet-stream").unwrap(),
+        );
+        if existing_len > 0 {
+            headers.insert(
+                RANGE,
+                HeaderValue::from_str(&format!("bytes={existing_len}-"))?,
+            );
+            if let Some(etag) = state.as_ref().and_then(|s| s.etag.as_deref()) {
+                headers.insert(IF_RANGE, HeaderValue::from_str(etag)?);
+            }
+        }
+
+        let response = client
+            .get(self.download_url.clone())
+            .headers(headers)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(ToString::to_string);
+
+        // the previous attempt already downloaded the whole package
+        if status == StatusCode::RANGE_NOT_SATISFIABLE
+            && state.as_ref().and_then(|s| s.content_length) == Some(existing_len)
+        {
+            progress.catch_up(path, existing_len)?;
+            progress.report(existing_len, Some(existing_len), on_chunk);
+            return Ok(());
+        }
+
+        if !status.is_success() {
+            return Err(Error::Network(format!(
+                "Download request failed with status: {status}"
+            )));
+        }
+
+        let (offset, content_length) = if status == StatusCode::PARTIAL_CONTENT {
+            let state = state.unwrap_or_default();
+            let range = response
+                .headers()
+                .get(CONTENT_RANGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_content_range);
+            match range {
+                Some((start, total))
+                    if start == existing_len
+                        && state.content_length.map_or(true, |len| Some(len) == total)
+                        && (etag.is_none() || etag == state.etag) =>
+                {
+                    (start, total)
+                }
+                _ => {
+                    // the partial file cannot be trusted anymore, start from scratch next time
+                    let _ = std::fs::remove_file(&state_path);
+                    let _ = std::fs::remove_file(path);
+                    return Err(Error::InvalidContentRange);
+                }
+            }
+        } else {
+            let content_length: Option<u64> = response
+                .headers()
+                .get("Content-Length")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok());
+            (0, content_length)
+        };
+
+        let mut file = if offset == 0 {
+            let state = PartialDownload {
+                url: Some(self.download_url.clone()),
+                etag,
+                content_length,
+            };
+            std::fs::write(&state_path, serde_json::to_vec(&state)?)?;
+            File::create(path)?
+        } else {
+            OpenOptions::new().append(true).open(path)?
+        };
+
+        progress.catch_up(path, offset)?;
+        progress.report(offset, content_length, on_chunk);
+
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            file.write_all(&chunk)?;
+            progress.update(&chunk);
+            progress.report(progress.downloaded, content_length, on_chunk);
+            self.checkpoint().await?;
+        }
+        file.sync_all()?;
+
+        if content_length.map_or(false, |len| len != progress.downloaded) {
+            return Err(Error::Network(
+                "Download finished before the whole package was received".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Installs the updater package downloaded by [`Update::download_to_file`]
+    ///
+    /// The package is read from the file as it is installed, and removed once it is installed.
+    pub fn install_from_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let package = Package::File(path.as_ref());
+        self.install_inner(package)?;
+        self.finish_install(package);
+        Ok(())
+    }
+
+    /// Removes the downloaded package and persists the key announced by the release.
+    fn finish_install(&self, package: Package<'_>) {
+        package.remove();
+        if let (Some(pubkey), Some(dir)) = (&self.rotated_key, &self.state_dir) {
+            if let Err(e) = persist_rotated_key(dir, pubkey) {
+                log::warn!("failed to persist the rotated updater key: {e}");
+            }
+        }
+    }
+
+    /// Downloads the updater package to disk with [`Update::download_to_file`] and installs it
+    pub async fn download_to_file_and_install<C: FnMut(usize, Option<u64>), D: FnOnce()>(
+        &self,
+        on_chunk: C,
+        on_download_finish: D,
+    ) -> Result<()> {
+        let path = self.download_to_file(on_chunk, on_download_finish).await?;
+        self.install_from_file(path)
+    }
+
+    #[cfg(mobile)]
+    fn install_inner(&self, _package: Package<'_>) -> Result<()> {
+        Ok(())
+    }
+}
+
+trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// The updater package to install, downloaded either in memory or to a file.
+#[derive(Clone, Copy)]
+enum Package<'a> {
+    Bytes(&'a [u8]),
+    File(&'a Path),
+}
+
+#[allow(unused)]
+impl Package<'_> {
+    /// The first bytes of the package, enough to detect its format.
+    fn head(&self) -> Result<Vec<u8>> {
+        let mut head = Vec::new();
+        self.reader()?
+            .take(PACKAGE_HEAD_LEN)
+            .read_to_end(&mut head)?;
+        Ok(head)
     }
 
-    /// Downloads and installs the updater package
-    pub async fn download_and_install<C: FnMut(usize, Option<u64>), D: FnOnce()>(
-        &self,
-        on_chunk: C,
-        on_download_finish: D,
-    ) -> Result<()> {
-        let bytes = self.download(on_chunk, on_download_finish).await?;
-        self.install(bytes)
+    fn reader(&self) -> Result<Box<dyn ReadSeek + '_>> {
+        match self {
+            Self::Bytes(bytes) => Ok(Box::new(Cursor::new(*bytes))),
+            Self::File(path) => Ok(Box::new(File::open(path)?)),
+        }
     }
 
-    #[cfg(mobile)]
-    fn install_inner(&self, _bytes: &[u8]) -> Result<()> {
+    fn copy_to(&self, writer: &mut impl Write) -> Result<()> {
+        std::io::copy(&mut self.reader()?, writer)?;
         Ok(())
     }
+
+    /// Removes the downloaded file once the package is installed.
+    fn remove(&self) {
+        if let Self::File(path) = self {
+            let _ = std::fs::remove_file(path);
+        }
+    }
 }
 
 #[cfg(windows)]
 enum WindowsUpdaterType {
     Nsis {
         path: PathBuf,
-        #[allow(unused)]
         temp: Option<tempfile::TempPath>,
     },
     Msi {
         path: PathBuf,
-        #[allow(unused)]
         temp: Option<tempfile::TempPath>,
     },
 }
@@ -569,6 +1413,15 @@ impl WindowsUpdaterType {
             temp,
         }
     }
+
+    /// Keeps the temporary copy of the installer, which is still running once the app exited.
+    fn keep(self) -> Result<()> {
+        let (Self::Nsis { temp, .. } | Self::Msi { temp, .. }) = self;
+        if let Some(temp) = temp {
+            temp.keep().map_err(std::io::Error::from)?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(windows)]
@@ -592,14 +1445,14 @@ impl Update {
     /// ├── [AppName]_[version]_x64-setup.exe.zip          # ZIP generated by tauri-bundler
     /// │   └──[AppName]_[version]_x64-setup.exe           # NSIS installer
     /// └── ...
-    fn install_inner(&self, bytes: &[u8]) -> Result<()> {
+    fn install_inner(&self, package: Package<'_>) -> Result<()> {
         use std::iter::once;
         use windows_sys::{
             w,
             Win32::UI::{Shell::ShellExecuteW, WindowsAndMessaging::SW_SHOW},
         };
 
-        let updater_type = self.extract(bytes)?;
+        let updater_type = self.extract(package)?;
 
         let install_mode = self.config.install_mode();
         let current_args = &self.current_exe_args()[1..];
@@ -623,12 +1476,16 @@ impl Update {
                     .join(" ");
                 msi_args = OsString::from(format!("LAUNCHAPPARGS=\"{escaped_args}\""));
 
+                // the user quit the app, do not launch it again
+                let auto_launch =
+                    (!self.installing_on_exit).then_some(OsStr::new("AUTOLAUNCHAPP=True"));
+
                 [OsStr::new("/i"), path.as_os_str()]
                     .into_iter()
                     .chain(install_mode.msiexec_args().iter().map(OsStr::new))
                     .chain(once(OsStr::new("/promptrestart")))
                     .chain(self.installer_args())
-                    .chain(once(OsStr::new("AUTOLAUNCHAPP=True")))
+                    .chain(auto_launch)
                     .chain(once(msi_args.as_os_str()))
                     .collect()
             }
@@ -650,7 +1507,7 @@ impl Update {
         let parameters = installer_args.join(OsStr::new(" "));
         let parameters = encode_wide(parameters);
 
-        unsafe {
+        let result = unsafe {
             ShellExecuteW(
                 std::ptr::null_mut(),
                 w!("open"),
@@ -660,8 +1517,20 @@ impl Update {
                 SW_SHOW,
             )
         };
-
-        std::process::exit(0);
+        // values up to 32 are error codes
+        if result as isize <= 32 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        // when installing on exit, the app keeps running while the installer starts
+        updater_type.keep()?;
+
+        // the app is already exiting, let it finish its own shutdown
+        if !self.installing_on_exit {
+            // the installer has its own copy of the package
+            self.finish_install(package);
+            std::process::exit(0);
+        }
+        Ok(())
     }
 
     fn installer_args(&self) -> Vec<&OsStr> {
@@ -678,13 +1547,15 @@ impl Update {
             .collect::<Vec<_>>()
     }
 
-    fn extract(&self, bytes: &[u8]) -> Result<WindowsUpdaterType> {
+    fn extract(&self, package: Package<'_>) -> Result<WindowsUpdaterType> {
+        let head = package.head()?;
+
         #[cfg(feature = "zip")]
-        if infer::archive::is_zip(bytes) {
-            return self.extract_zip(bytes);
+        if infer::archive::is_zip(&head) {
+            return self.extract_zip(package);
         }
 
-        self.extract_exe(bytes)
+        self.extract_exe(package, &head)
     }
 
     fn make_temp_dir(&self) -> Result<PathBuf> {
@@ -695,11 +1566,10 @@ impl Update {
     }
 
     #[cfg(feature = "zip")]
-    fn extract_zip(&self, bytes: &[u8]) -> Result<WindowsUpdaterType> {
+    fn extract_zip(&self, package: Package<'_>) -> Result<WindowsUpdaterType> {
         let temp_dir = self.make_temp_dir()?;
 
-        let archive = Cursor::new(bytes);
-        let mut extractor = zip::ZipArchive::new(archive)?;
+        let mut extractor = zip::ZipArchive::new(package.reader()?)?;
         extractor.extract(&temp_dir)?;
 
         let paths = std::fs::read_dir(&temp_dir)?;
@@ -716,12 +1586,12 @@ impl Update {
         Err(crate::Error::BinaryNotFoundInArchive)
     }
 
-    fn extract_exe(&self, bytes: &[u8]) -> Result<WindowsUpdaterType> {
-        if infer::app::is_exe(bytes) {
-            let (path, temp) = self.write_to_temp(bytes, ".exe")?;
+    fn extract_exe(&self, package: Package<'_>, head: &[u8]) -> Result<WindowsUpdaterType> {
+        if infer::app::is_exe(head) {
+            let (path, temp) = self.write_to_temp(package, ".exe")?;
             Ok(WindowsUpdaterType::nsis(path, temp))
-        } else if infer::archive::is_msi(bytes) {
-            let (path, temp) = self.write_to_temp(bytes, ".msi")?;
+        } else if infer::archive::is_msi(head) {
+            let (path, temp) = self.write_to_temp(package, ".msi")?;
             Ok(WindowsUpdaterType::msi(path, temp))
         } else {
             Err(crate::Error::InvalidUpdaterFormat)
@@ -730,25 +1600,23 @@ impl Update {
 
     fn write_to_temp(
         &self,
-        bytes: &[u8],
+        package: Package<'_>,
         ext: &str,
     ) -> Result<(PathBuf, Option<tempfile::TempPath>)> {
-        use std::io::Write;
-
         let temp_dir = self.make_temp_dir()?;
         let mut temp_file = tempfile::Builder::new()
             .prefix(&format!("{}-{}-installer", self.app_name, self.version))
             .suffix(ext)
             .rand_bytes(0)
             .tempfile_in(temp_dir)?;
-        temp_file.write_all(bytes)?;
+        package.copy_to(&mut temp_file)?;
 
         let temp = temp_file.into_temp_path();
         Ok((temp.to_path_buf(), Some(temp)))
     }
 }
 
-/// Linux (AppImage)
+/// Linux (AppImage, deb and rpm)
 #[cfg(any(
     target_os = "linux",
     target_os = "dragonfly",
@@ -757,6 +1625,67 @@ impl Update {
     target_os = "openbsd"
 ))]
 impl Update {
+    fn install_inner(&self, package: Package<'_>) -> Result<()> {
+        match self.linux_installer {
+            LinuxInstaller::AppImage => self.install_appimage(package),
+            LinuxInstaller::Deb => {
+                if !infer::archive::is_deb(&package.head()?) {
+                    return Err(Error::InvalidUpdaterFormat);
+                }
+                self.install_package(package, ".deb", "dpkg", "-i")
+            }
+            LinuxInstaller::Rpm => {
+                if !infer::archive::is_rpm(&package.head()?) {
+                    return Err(Error::InvalidUpdaterFormat);
+                }
+                self.install_package(package, ".rpm", "rpm", "-U")
+            }
+        }
+    }
+
+    /// Installs a `deb` or `rpm` package with the given package manager,
+    /// elevating privileges with `pkexec` unless the app runs as root.
+    fn install_package(
+        &self,
+        package: Package<'_>,
+        ext: &str,
+        program: &str,
+        arg: &str,
+    ) -> Result<()> {
+        use std::os::unix::fs::MetadataExt;
+
+        // the package manager needs the right extension
+        let mut file = tempfile::Builder::new()
+            .prefix(&format!("{}-{}-", self.app_name, self.version))
+            .suffix(ext)
+            .tempfile()?;
+        package.copy_to(&mut file)?;
+        file.flush()?;
+
+        let is_root = std::fs::metadata("/proc/self").map_or(false, |m| m.uid() == 0);
+        let mut command = if is_root {
+            std::process::Command::new(program)
+        } else {
+            let mut command = std::process::Command::new("pkexec");
+            command.arg(program);
+            command
+        };
+
+        let output = command
+            .arg(arg)
+            .args(&self.linux_installer_args)
+            .arg(file.path())
+            .output()?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(Error::PackageInstallFailed(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ))
+        }
+    }
+
     /// ### Expected structure:
     /// ├── [AppName]_[version]_amd64.AppImage.tar.gz    # GZ generated by tauri-bundler
     /// │   └──[AppName]_[version]_amd64.AppImage        # Application AppImage
@@ -765,7 +1694,7 @@ impl Update {
     /// We should have an AppImage already installed to be able to copy and install
     /// the extract_path is the current AppImage path
     /// tmp_dir is where our new AppImage is found
-    fn install_inner(&self, bytes: &[u8]) -> Result<()> {
+    fn install_appimage(&self, package: Package<'_>) -> Result<()> {
         use std::os::unix::fs::{MetadataExt, PermissionsExt};
         let extract_path_metadata = self.extract_path.metadata()?;
 
@@ -795,11 +1724,10 @@ impl Update {
                     std::fs::rename(&self.extract_path, tmp_app_image)?;
 
                     #[cfg(feature = "zip")]
-                    if infer::archive::is_gz(bytes) {
+                    if infer::archive::is_gz(&package.head()?) {
                         // extract the buffer to the tmp_dir
                         // we extract our signed archive into our final directory without any temp file
-                        let archive = Cursor::new(bytes);
-                        let decoder = flate2::read::GzDecoder::new(archive);
+                        let decoder = flate2::read::GzDecoder::new(package.reader()?);
                         let mut archive = tar::Archive::new(decoder);
                         for mut entry in archive.entries()?.flatten() {
                             if let Ok(path) = entry.path() {
@@ -809,6 +1737,7 @@ impl Update {
                                         std::fs::rename(tmp_app_image, &self.extract_path)?;
                                         return Err(err.into());
                                     }
+                                    self.keep_backup(tmp_app_image);
                                     // early finish we have everything we need here
                                     return Ok(());
                                 }
@@ -819,15 +1748,22 @@ impl Update {
                         return Err(Error::BinaryNotFoundInArchive);
                     }
 
-                    return match std::fs::write(&self.extract_path, bytes)
-                        .and_then(|_| std::fs::set_permissions(&self.extract_path, permissions))
-                    {
+                    return match File::create(&self.extract_path)
+                        .map_err(Into::into)
+                        .and_then(|mut file| package.copy_to(&mut file))
+                        .and_then(|_| {
+                            std::fs::set_permissions(&self.extract_path, permissions)
+                                .map_err(Into::into)
+                        }) {
                         Err(err) => {
                             // if something went wrong during the extraction, we should restore previous app
                             std::fs::rename(tmp_app_image, &self.extract_path)?;
-                            Err(err.into())
+                            Err(err)
+                        }
+                        Ok(_) => {
+                            self.keep_backup(tmp_app_image);
+                            Ok(())
                         }
-                        Ok(_) => Ok(()),
                     };
                 }
             }
@@ -835,6 +1771,20 @@ impl Update {
 
         Err(Error::TempDirNotOnSameMountPoint)
     }
+
+    /// Keeps the backup of the previous AppImage so it can be restored if the new version is not healthy.
+    fn keep_backup(&self, backup: &Path) {
+        if let Some(health_check) = &self.health_check {
+            // the update is already installed at this point, failing here only disables the rollback
+            let _ = crate::rollback::keep_backup(
+                health_check,
+                backup,
+                &self.extract_path,
+                &self.current_version,
+                &self.version,
+            );
+        }
+    }
 }
 
 /// MacOS
@@ -846,10 +1796,10 @@ impl Update {
     /// │      └── Contents                          # Application contents...
     /// │          └── ...
     /// └── ...
-    fn install_inner(&self, bytes: &[u8]) -> Result<()> {
+    fn install_inner(&self, package: Package<'_>) -> Result<()> {
         use flate2::read::GzDecoder;
 
-        let cursor = Cursor::new(bytes);
+        let reader = package.reader()?;
         let mut extracted_files: Vec<PathBuf> = Vec::new();
 
         // the first file in the tar.gz will always be
@@ -861,7 +1811,7 @@ impl Update {
         // create backup of our current app
         std::fs::rename(&self.extract_path, tmp_dir.path())?;
 
-        let decoder = GzDecoder::new(cursor);
+        let decoder = GzDecoder::new(reader);
         let mut archive = tar::Archive::new(decoder);
 
         std::fs::create_dir(&self.extract_path)?;
@@ -975,10 +1925,17 @@ impl<'de> Deserialize<'de> for RemoteRelease {
             version: Version,
             notes: Option<String>,
             pub_date: Option<String>,
+            channel: Option<String>,
+            rollout: Option<f64>,
+            key_rotation: Option<KeyRotation>,
+            #[serde(default, deserialize_with = "parse_optional_version")]
+            min_supported_version: Option<Version>,
             platforms: Option<HashMap<String, ReleaseManifestPlatform>>,
             // dynamic platform response
             url: Option<Url>,
             signature: Option<String>,
+            #[serde(default)]
+            patches: HashMap<String, ReleaseManifestPatch>,
         }
 
         let release = InnerRemoteRelease::deserialize(deserializer)?;
@@ -992,10 +1949,22 @@ impl<'de> Deserialize<'de> for RemoteRelease {
             None
         };
 
+        if let Some(rollout) = release.rollout {
+            if !(0.0..=100.0).contains(&rollout) {
+                return Err(DeError::custom(format!(
+                    "invalid value for `rollout`: {rollout}, expected a percentage between 0 and 100"
+                )));
+            }
+        }
+
         Ok(RemoteRelease {
             version: release.version,
             notes: release.notes,
             pub_date,
+            channel: release.channel,
+            rollout: release.rollout,
+            key_rotation: release.key_rotation,
+            min_supported_version: release.min_supported_version,
             data: if let Some(platforms) = release.platforms {
                 RemoteReleaseInner::Static { platforms }
             } else {
@@ -1006,12 +1975,29 @@ impl<'de> Deserialize<'de> for RemoteRelease {
                     signature: release.signature.ok_or_else(|| {
                         DeError::custom("the `signature` field was not set on the updater response")
                     })?,
+                    patches: release.patches,
                 })
             },
         })
     }
 }
 
+/// Deterministically maps an install and a release to a bucket between 0 and 9999 using FNV-1a,
+/// which unlike the std hasher is stable across Rust versions and platforms.
+fn rollout_bucket(install_id: &str, version: &Version) -> u64 {
+    let version = version.to_string();
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in install_id
+        .bytes()
+        .chain(std::iter::once(b':'))
+        .chain(version.bytes())
+    {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash % 10_000
+}
+
 fn parse_version<'de, D>(deserializer: D) -> std::result::Result<Version, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -1021,17 +2007,259 @@ where
     Version::from_str(str.trim_start_matches('v')).map_err(serde::de::Error::custom)
 }
 
+fn parse_optional_version<'de, D>(deserializer: D) -> std::result::Result<Option<Version>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|str| Version::from_str(str.trim_start_matches('v')).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// Gives up on the patch after `error`, unless the download was cancelled.
+#[cfg(feature = "delta")]
+fn fall_back_from_patch<C: FnMut(usize, Option<u64>)>(
+    error: Error,
+    on_chunk: &mut C,
+) -> Result<()> {
+    match error {
+        Error::DownloadCancelled => Err(error),
+        Error::PatchNotFound => Ok(()),
+        error => {
+            log::warn!(
+                "failed to apply the update patch, downloading the full package instead: {error}"
+            );
+            // the progress of the patch does not count towards the full package
+            on_chunk(0, None);
+            Ok(())
+        }
+    }
+}
+
+/// Verifies the signature of the file at `path` without loading it in memory, unless the signature is legacy.
+#[cfg(feature = "delta")]
+fn verify_file_signature(path: &Path, release_signature: &str, config: &Config) -> Result<()> {
+    let signature = decode_signature(release_signature)?;
+    let key = SigningKey::find(config, release_signature)?;
+    let mut progress = DownloadProgress::new(&key.public_key, &signature)?;
+    progress.catch_up(path, path.metadata()?.len())?;
+    match progress.verifier.as_mut() {
+        Some(verifier) => verifier.finalize().map_err(|e| key.error(e)),
+        None => verify_legacy_file_signature(path, release_signature, config),
+    }
+}
+
+/// Legacy signatures cover a hash of the whole file, which can only be verified once it is in memory.
+fn verify_legacy_file_signature(
+    path: &Path,
+    release_signature: &str,
+    config: &Config,
+) -> Result<()> {
+    let bytes = std::fs::read(path)?;
+    verify_signature(&bytes, release_signature, config)?;
+    Ok(())
+}
+
 // Validate signature
-fn verify_signature(data: &[u8], release_signature: &str, pub_key: &str) -> Result<bool> {
+fn verify_signature(data: &[u8], release_signature: &str, config: &Config) -> Result<bool> {
+    let signature = decode_signature(release_signature)?;
+    let key = SigningKey::find(config, release_signature)?;
+
+    // Validate signature or bail out
+    key.public_key
+        .verify(data, &signature, true)
+        .map_err(|e| key.error(e))?;
+    Ok(true)
+}
+
+/// The trusted public key a signature was made with.
+struct SigningKey {
+    id: String,
+    public_key: PublicKey,
+}
+
+impl SigningKey {
+    /// Finds the trusted key matching the key id of the signature that is valid right now.
+    fn find(config: &Config, release_signature: &str) -> Result<Self> {
+        let id = signature_key_id(release_signature)?;
+        let now = OffsetDateTime::now_utc();
+
+        let mut outside_validity_window = false;
+        for key in config.trusted_keys() {
+            match public_key_id(&key.pubkey) {
+                Ok(key_id) if key_id == id => {}
+                Ok(_) => continue,
+                // a malformed key must not prevent the other keys from being used
+                Err(e) => {
+                    log::warn!("ignoring malformed trusted updater key: {e}");
+                    continue;
+                }
+            }
+            if !key.is_valid_at(now) {
+                outside_validity_window = true;
+                continue;
+            }
+            return Ok(Self {
+                id,
+                public_key: decode_public_key(&key.pubkey)?,
+            });
+        }
+
+        Err(if outside_validity_window {
+            Error::SigningKeyNotValid(id)
+        } else {
+            Error::UntrustedSigningKey(id)
+        })
+    }
+
+    fn error(&self, error: minisign_verify::Error) -> Error {
+        Error::InvalidSignature {
+            key_id: self.id.clone(),
+            source: error,
+        }
+    }
+}
+
+/// The id of the key a signature was made with, formatted like minisign does.
+fn signature_key_id(release_signature: &str) -> Result<String> {
+    // the second line holds the signature algorithm, key id and signature
+    let decoded = base64_to_string(release_signature)?;
+    key_id(decoded.lines().nth(1))
+}
+
+/// The id of a public key, formatted like minisign does.
+fn public_key_id(pub_key: &str) -> Result<String> {
+    // the last line holds the signature algorithm, key id and key
+    let decoded = base64_to_string(pub_key)?;
+    key_id(decoded.lines().rev().find(|line| !line.trim().is_empty()))
+}
+
+fn key_id(line: Option<&str>) -> Result<String> {
+    let bytes =
+        base64::engine::general_purpose::STANDARD.decode(line.unwrap_or_default().trim())?;
+    let id: [u8; 8] = bytes
+        .get(2..10)
+        .and_then(|id| id.try_into().ok())
+        .ok_or(minisign_verify::Error::InvalidEncoding)?;
+    Ok(format!("{:016X}", u64::from_le_bytes(id)))
+}
+
+fn decode_public_key(pub_key: &str) -> Result<PublicKey> {
     // we need to convert the pub key
     let pub_key_decoded = base64_to_string(pub_key)?;
-    let public_key = PublicKey::decode(&pub_key_decoded)?;
+    PublicKey::decode(&pub_key_decoded).map_err(Into::into)
+}
+
+fn decode_signature(release_signature: &str) -> Result<Signature> {
     let signature_base64_decoded = base64_to_string(release_signature)?;
-    let signature = Signature::decode(&signature_base64_decoded)?;
+    Signature::decode(&signature_base64_decoded).map_err(Into::into)
+}
 
-    // Validate signature or bail out
-    public_key.verify(data, &signature, true)?;
-    Ok(true)
+/// State of a partially downloaded updater package, persisted next to it so the download can be resumed.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PartialDownload {
+    url: Option<Url>,
+    etag: Option<String>,
+    content_length: Option<u64>,
+}
+
+impl PartialDownload {
+    fn path(download_path: &Path) -> PathBuf {
+        let mut path = download_path.as_os_str().to_os_string();
+        path.push(".json");
+        PathBuf::from(path)
+    }
+
+    fn read(path: &Path) -> Option<Self> {
+        std::fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+    }
+}
+
+/// Tracks how much of the updater package was downloaded and verified so far.
+struct DownloadProgress<'a> {
+    public_key: &'a PublicKey,
+    signature: &'a Signature,
+    // `None` for legacy signatures which can only be verified once the whole package is downloaded
+    verifier: Option<StreamVerifier<'a>>,
+    // bytes written to disk and fed to the verifier
+    downloaded: u64,
+    // bytes reported to the `on_chunk` callback
+    reported: u64,
+}
+
+impl<'a> DownloadProgress<'a> {
+    fn new(public_key: &'a PublicKey, signature: &'a Signature) -> Result<Self> {
+        let verifier = match public_key.verify_stream(signature) {
+            Ok(verifier) => Some(verifier),
+            Err(minisign_verify::Error::UnsupportedLegacyMode) => None,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            public_key,
+            signature,
+            verifier,
+            downloaded: 0,
+            reported: 0,
+        })
+    }
+
+    /// Makes sure the first `offset` bytes of the file at `path` were fed to the verifier.
+    fn catch_up(&mut self, path: &Path, offset: u64) -> Result<()> {
+        if offset != 0 && offset == self.downloaded {
+            return Ok(());
+        }
+
+        if let Some(verifier) = self.verifier.as_mut() {
+            *verifier = self.public_key.verify_stream(self.signature)?;
+
+            let mut file = File::open(path)?.take(offset);
+            let mut buf = vec![0; 64 * 1024];
+            loop {
+                let read = file.read(&mut buf)?;
+                if read == 0 {
+                    break;
+                }
+                verifier.update(&buf[..read]);
+            }
+        }
+        self.downloaded = offset;
+
+        Ok(())
+    }
+
+    fn update(&mut self, chunk: &[u8]) {
+        if let Some(verifier) = self.verifier.as_mut() {
+            verifier.update(chunk);
+        }
+        self.downloaded += chunk.len() as u64;
+    }
+
+    fn report<C: FnMut(usize, Option<u64>)>(
+        &mut self,
+        downloaded: u64,
+        content_length: Option<u64>,
+        on_chunk: &mut C,
+    ) {
+        if downloaded > self.reported {
+            on_chunk((downloaded - self.reported) as usize, content_length);
+            self.reported = downloaded;
+        }
+    }
+}
+
+/// Parses a `Content-Range: bytes <start>-<end>/<total>` header value into its start and total length.
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _end) = range.split_once('-')?;
+    let total = if total == "*" {
+        None
+    } else {
+        Some(total.parse().ok()?)
+    };
+    Some((start.parse().ok()?, total))
 }
 
 fn base64_to_string(base64_string: &str) -> Result<String> {
@@ -1096,6 +2324,566 @@ fn escape_msi_property_arg(arg: impl AsRef<OsStr>) -> String {
 
 #[cfg(test)]
 mod tests {
+    // minisign key and signature of `package()` made with it, used by the download tests
+    const SIGNING_PUBKEY: &str = "dW50cnVzdGVkIGNvbW1lbnQ6IG1pbmlzaWduIHB1YmxpYyBrZXk6IDExMTExMTExMTExMTExMTEKUldRUkVSRVJFUkVSRVFPaEI3L3p6aEMrSFhEZEdPZEx3SmxuNU5Zd202VU5YeDNjaG1RU1ZURzQK";
+    const PACKAGE_SIGNATURE: &str = "dW50cnVzdGVkIGNvbW1lbnQ6IHNpZ25hdHVyZSBmcm9tIG1pbmlzaWduIHNlY3JldCBrZXkKUlVRUkVSRVJFUkVSRVFjUy94NWZTbzFXOFdQb2VZZTV2V1ZacGVWcmZVdW4zWGV2SnZ0RkUvcHlXMmlWZEhqSUIxdXhzQWFRRUc1TktXYitERW53TDBaVkVTVWhCSW9Pb0FNPQp0cnVzdGVkIGNvbW1lbnQ6IHRpbWVzdGFtcDowCmx4Nmd6a2RhYUtWNmRYcmtYQWpPbkk4RW0xR1VZNld6dkZSSG81K0xGSFJsQ3ZyZGRsckNMNnMzSTJHK3psdVVQTEIzaG9XUHFkT2Z0MXJ2UXBJVkFnPT0K";
+
+    // a key announced by a release, with its signature made with `SIGNING_PUBKEY`
+    const ROTATED_PUBKEY: &str = "dW50cnVzdGVkIGNvbW1lbnQ6IG1pbmlzaWduIHB1YmxpYyBrZXk6IDIyMjIyMjIyMjIyMjIyMjIKUldRaUlpSWlJaUlpSWltc3V1RkJ2TXJ3c2k0YWxOTk5DOGMySGxKdEMvNFN5SmVVdkpNaWxtM1gK";
+    const ROTATION_SIGNATURE: &str = "dW50cnVzdGVkIGNvbW1lbnQ6IHNpZ25hdHVyZSBmcm9tIG1pbmlzaWduIHNlY3JldCBrZXkKUlVRUkVSRVJFUkVSRWVndzNlYlVNdkY3QTVRMXZIM0t5dkRiT1Bzbm0vSS8vem5oczg4Y0dsQUFPV2VsTThWZ1c3NjZhRkpIZXNDM0pQQUVteHZadk1ySVYvd1ZuSC9aTHdBPQp0cnVzdGVkIGNvbW1lbnQ6IHRpbWVzdGFtcDowCnhQR0Y0end2bG1RR3lwb1gwTjJ4eDFSd2RRV2ZCWEtwaGxNQlN0MG81UnlKTkNIaFZxM3lZejRTQm1lTTRtVXJRdUVRQml4Mjg3a1VKN0tZTWVTMUFBPT0K";
+
+    fn package() -> Vec<u8> {
+        (0..4096u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// Serves the given responses, one per connection, and returns the requests it received.
+    fn serve(responses: Vec<Vec<u8>>) -> (url::Url, std::thread::JoinHandle<Vec<String>>) {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/package", listener.local_addr().unwrap())
+            .parse()
+            .unwrap();
+        let server = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = String::new();
+                let mut reader = BufReader::new(&mut stream);
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    request.push_str(&line.to_lowercase());
+                }
+                requests.push(request);
+                stream.write_all(&response).unwrap();
+            }
+            requests
+        });
+        (url, server)
+    }
+
+    fn response(status: &str, headers: &[(&str, String)], body: &[u8]) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 {status}\r\nConnection: close\r\nContent-Length: {}\r\n",
+            body.len()
+        );
+        for (name, value) in headers {
+            response.push_str(&format!("{name}: {value}\r\n"));
+        }
+        response.push_str("\r\n");
+        let mut response = response.into_bytes();
+        response.extend(body);
+        response
+    }
+
+    fn update(download_url: url::Url, download_dir: &std::path::Path) -> super::Update {
+        super::Update {
+            config: crate::Config {
+                pubkey: SIGNING_PUBKEY.into(),
+                ..Default::default()
+            },
+            on_before_exit: None,
+            body: None,
+            changelog: Vec::new(),
+            current_version: "1.0.0".into(),
+            version: "2.0.0".into(),
+            date: None,
+            target: "test".into(),
+            download_url,
+            signature: PACKAGE_SIGNATURE.into(),
+            patch: None,
+            timeout: None,
+            proxy: None,
+            headers: Default::default(),
+            download_handle: None,
+            installing_on_exit: false,
+            extract_path: Default::default(),
+            app_name: "app".into(),
+            installer_args: Vec::new(),
+            current_exe_args: Vec::new(),
+            download_dir: Some(download_dir.to_path_buf()),
+            health_check: None,
+            rotated_key: None,
+            state_dir: None,
+            linux_installer: super::LinuxInstaller::AppImage,
+            linux_installer_args: Vec::new(),
+        }
+    }
+
+    /// Leaves the first `len` bytes of the package on disk as if a previous download was interrupted.
+    fn interrupt_download(update: &super::Update, len: usize, etag: Option<&str>) {
+        let path = update.download_path();
+        std::fs::write(&path, &package()[..len]).unwrap();
+        let state = super::PartialDownload {
+            url: Some(update.download_url.clone()),
+            etag: etag.map(Into::into),
+            content_length: Some(package().len() as u64),
+        };
+        std::fs::write(
+            super::PartialDownload::path(&path),
+            serde_json::to_vec(&state).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn it_selects_trusted_signing_key() {
+        use super::{public_key_id, SigningKey};
+        use crate::{config::TrustedKey, Config, Error};
+        use base64::Engine;
+        use time::OffsetDateTime;
+
+        const PUBKEY: &str = "dW50cnVzdGVkIGNvbW1lbnQ6IG1pbmlzaWduIHB1YmxpYyBrZXk6IEUwNDRGMjkwRjg2MDhCRDAKUldUUWkyRDRrUEpFNEQ4SmdwcU5PaXl6R2ZRUUNvUnhIaVkwVUltV0NMaEx6VTkrWVhpT0ZqeEEK";
+        const KEY_ID: u64 = 0xE044F290F8608BD0;
+
+        // only the key id of the signature matters to select the key
+        let signature = |key_id: u64| {
+            let engine = base64::engine::general_purpose::STANDARD;
+            let mut line = b"ED".to_vec();
+            line.extend(key_id.to_le_bytes());
+            line.extend([0; 64]);
+            engine.encode(format!(
+                "untrusted comment: signature\n{}\ntrusted comment: timestamp:0\n{}\n",
+                engine.encode(line),
+                engine.encode([0; 64])
+            ))
+        };
+
+        assert_eq!(public_key_id(PUBKEY).unwrap(), "E044F290F8608BD0");
+
+        let config = Config {
+            pubkey: PUBKEY.into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            SigningKey::find(&config, &signature(KEY_ID)).unwrap().id,
+            "E044F290F8608BD0"
+        );
+        assert!(matches!(
+            SigningKey::find(&config, &signature(1)),
+            Err(Error::UntrustedSigningKey(id)) if id == "0000000000000001"
+        ));
+
+        let config = Config {
+            pubkeys: vec![TrustedKey {
+                pubkey: PUBKEY.into(),
+                not_before: None,
+                not_after: Some(OffsetDateTime::UNIX_EPOCH),
+            }],
+            ..Default::default()
+        };
+        assert!(matches!(
+            SigningKey::find(&config, &signature(KEY_ID)),
+            Err(Error::SigningKeyNotValid(_))
+        ));
+
+        // a malformed key does not prevent the other keys from being used
+        let config = Config {
+            pubkeys: vec![TrustedKey::new("not a key"), TrustedKey::new(PUBKEY)],
+            ..Default::default()
+        };
+        assert_eq!(
+            SigningKey::find(&config, &signature(KEY_ID)).unwrap().id,
+            "E044F290F8608BD0"
+        );
+    }
+
+    #[test]
+    fn it_persists_rotated_keys_once() {
+        use super::{accept_key_rotation, persist_rotated_key, read_rotated_keys, KeyRotation};
+        use crate::Config;
+
+        let mut config = Config {
+            pubkey: "current".into(),
+            ..Default::default()
+        };
+        // an already trusted key is not persisted again
+        let rotation = KeyRotation {
+            pubkey: "current".into(),
+            signature: String::new(),
+        };
+        assert_eq!(accept_key_rotation(&mut config, &rotation), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        persist_rotated_key(dir.path(), "next").unwrap();
+        persist_rotated_key(dir.path(), "next").unwrap();
+        assert_eq!(read_rotated_keys(dir.path()), vec!["next".to_string()]);
+    }
+
+    #[test]
+    fn it_accepts_signed_key_rotation() {
+        use super::{accept_key_rotation, read_rotated_keys, KeyRotation, Package};
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut update = update("http://localhost".parse().unwrap(), dir.path());
+
+        // a rotation whose signature does not cover the announced key is ignored
+        let rotation = KeyRotation {
+            pubkey: ROTATED_PUBKEY.into(),
+            signature: PACKAGE_SIGNATURE.into(),
+        };
+        assert_eq!(accept_key_rotation(&mut update.config, &rotation), None);
+        assert!(update
+            .config
+            .trusted_keys()
+            .all(|key| key.pubkey != ROTATED_PUBKEY));
+
+        let rotation = KeyRotation {
+            pubkey: ROTATED_PUBKEY.into(),
+            signature: ROTATION_SIGNATURE.into(),
+        };
+        update.rotated_key = accept_key_rotation(&mut update.config, &rotation);
+        assert_eq!(update.rotated_key.as_deref(), Some(ROTATED_PUBKEY));
+        assert!(update
+            .config
+            .trusted_keys()
+            .any(|key| key.pubkey == ROTATED_PUBKEY));
+
+        // the key is only persisted once the update is installed
+        update.state_dir = Some(dir.path().to_path_buf());
+        assert!(read_rotated_keys(dir.path()).is_empty());
+        update.finish_install(Package::Bytes(&[]));
+        assert_eq!(
+            read_rotated_keys(dir.path()),
+            vec![ROTATED_PUBKEY.to_string()]
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn it_suffixes_target_with_linux_package_format() {
+        use super::{LinuxInstaller, UpdaterBuilder};
+
+        let builder = || {
+            UpdaterBuilder::new("app".into(), "1.0.0".parse().unwrap(), Default::default())
+                .endpoints(vec!["https://example.com/latest.json".parse().unwrap()])
+                .unwrap()
+        };
+        let build = |installer| {
+            builder()
+                .linux_installer(installer)
+                .build()
+                .unwrap()
+                .json_target
+        };
+
+        assert!(!build(LinuxInstaller::AppImage).ends_with("-deb"));
+        assert!(build(LinuxInstaller::Deb).ends_with("-deb"));
+        assert!(build(LinuxInstaller::Rpm).ends_with("-rpm"));
+
+        let updater = builder()
+            .target("custom")
+            .linux_installer(LinuxInstaller::Deb)
+            .build()
+            .unwrap();
+        assert_eq!(updater.json_target, "custom");
+    }
+
+    #[test]
+    fn it_selects_release_from_static_manifest() {
+        use super::{parse_releases, UpdaterBuilder};
+
+        let releases = parse_releases(serde_json::json!({
+            "releases": [
+                {
+                    "version": "1.1.0",
+                    "notes": "1.1.0 notes",
+                    "url": "https://example.com/1.1.0.AppImage",
+                    "signature": ""
+                },
+                {
+                    "version": "1.2.0",
+                    "notes": "1.2.0 notes",
+                    "min_supported_version": "1.1.0",
+                    "url": "https://example.com/1.2.0.AppImage",
+                    "signature": ""
+                },
+                {
+                    "version": "1.3.0-beta.1",
+                    "channel": "beta",
+                    "url": "https://example.com/1.3.0-beta.1.AppImage",
+                    "signature": ""
+                }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(releases.len(), 3);
+
+        let updater = |version: &str| {
+            UpdaterBuilder::new("app".into(), version.parse().unwrap(), Default::default())
+                .endpoints(vec!["https://example.com/latest.json".parse().unwrap()])
+                .unwrap()
+                .build()
+                .unwrap()
+        };
+
+        // 1.2.0 requires 1.1.0, so 1.0.0 must update to 1.1.0 first
+        let (release, changelog) = updater("1.0.0").select_release(&releases).unwrap();
+        assert_eq!(release.version.to_string(), "1.1.0");
+        assert_eq!(changelog.len(), 1);
+
+        let (release, changelog) = updater("1.1.0").select_release(&releases).unwrap();
+        assert_eq!(release.version.to_string(), "1.2.0");
+        assert_eq!(
+            changelog
+                .iter()
+                .map(|r| r.notes.as_deref().unwrap())
+                .collect::<Vec<_>>(),
+            ["1.2.0 notes"]
+        );
+
+        assert!(updater("1.2.0").select_release(&releases).is_none());
+
+        // without the requirement 1.0.0 updates to 1.2.0 directly and sees the notes of both releases
+        let mut releases = releases;
+        releases[1].min_supported_version = None;
+        let (release, changelog) = updater("1.0.0").select_release(&releases).unwrap();
+        assert_eq!(release.version.to_string(), "1.2.0");
+        assert_eq!(
+            changelog
+                .iter()
+                .map(|r| r.notes.as_deref().unwrap())
+                .collect::<Vec<_>>(),
+            ["1.2.0 notes", "1.1.0 notes"]
+        );
+
+        let single = parse_releases(serde_json::json!({
+            "version": "1.1.0",
+            "url": "https://example.com/1.1.0.AppImage",
+            "signature": ""
+        }))
+        .unwrap();
+        assert_eq!(single.len(), 1);
+    }
+
+    #[test]
+    fn it_evaluates_staged_rollouts() {
+        use super::RemoteRelease;
+
+        let release = |rollout: f64| -> RemoteRelease {
+            serde_json::from_value(serde_json::json!({
+                "version": "1.2.0",
+                "rollout": rollout,
+                "url": "https://example.com/app.AppImage",
+                "signature": ""
+            }))
+            .unwrap()
+        };
+
+        let ids = (0..1000)
+            .map(|i| format!("install-{i}"))
+            .collect::<Vec<_>>();
+        let rolled_out = |rollout: f64| {
+            let release = release(rollout);
+            ids.iter()
+                .filter(|id| release.is_rolled_out_to(Some(id)))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        assert!(rolled_out(0.0).is_empty());
+        assert_eq!(rolled_out(100.0).len(), ids.len());
+
+        let five = rolled_out(5.0);
+        let fifty = rolled_out(50.0);
+        assert!((20..=80).contains(&five.len()));
+        assert!((400..=600).contains(&fifty.len()));
+        // ramping up never excludes an install that already had the release
+        assert!(five.iter().all(|id| fifty.contains(id)));
+
+        assert!(!release(50.0).is_rolled_out_to(None));
+        assert!(release(100.0).is_rolled_out_to(None));
+        assert!(serde_json::from_value::<RemoteRelease>(serde_json::json!({
+            "version": "1.2.0",
+            "rollout": 150,
+            "url": "https://example.com/app.AppImage",
+            "signature": ""
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn it_filters_channels() {
+        use super::RemoteRelease;
+
+        let release: RemoteRelease = serde_json::from_value(serde_json::json!({
+            "version": "1.2.0-beta.1",
+            "channel": "beta",
+            "url": "https://example.com/app.AppImage",
+            "signature": ""
+        }))
+        .unwrap();
+        assert!(release.is_on_channel("beta"));
+        assert!(!release.is_on_channel("stable"));
+
+        let release: RemoteRelease = serde_json::from_value(serde_json::json!({
+            "version": "1.2.0",
+            "url": "https://example.com/app.AppImage",
+            "signature": ""
+        }))
+        .unwrap();
+        assert!(release.is_on_channel("beta"));
+        assert!(release.is_on_channel("stable"));
+    }
+
+    #[test]
+    fn it_finds_patch_for_current_version() {
+        use super::{PatchFormat, RemoteRelease};
+        use semver::Version;
+
+        let release: RemoteRelease = serde_json::from_value(serde_json::json!({
+            "version": "1.2.0",
+            "platforms": {
+                "linux-x86_64": {
+                    "url": "https://example.com/app_1.2.0_amd64.AppImage",
+                    "signature": "full",
+                    "patches": {
+                        "v1.1.0": {
+                            "url": "https://example.com/app_1.1.0_1.2.0_amd64.AppImage.zst",
+                            "signature": "patched"
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let patch = release
+            .patch("linux-x86_64", &Version::new(1, 1, 0))
+            .unwrap();
+        assert_eq!(patch.signature, "patched");
+        assert_eq!(patch.format, PatchFormat::Zstd);
+        assert!(release
+            .patch("linux-x86_64", &Version::new(1, 0, 0))
+            .is_none());
+        assert!(release
+            .patch("windows-x86_64", &Version::new(1, 1, 0))
+            .is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "delta")]
+    fn it_applies_zstd_patch() {
+        use super::PatchFormat;
+
+        let base = b"the quick brown fox jumps over the lazy dog".repeat(64);
+        let mut new = base.clone();
+        new.extend_from_slice(b"and runs away");
+
+        let patch = zstd::bulk::Compressor::with_dictionary(19, &base)
+            .unwrap()
+            .compress(&new)
+            .unwrap();
+
+        let mut patched = Vec::new();
+        PatchFormat::Zstd
+            .apply(&base, &patch, &mut patched)
+            .unwrap();
+        assert_eq!(patched, new);
+    }
+
+    #[test]
+    fn it_reads_package_from_file() {
+        use super::{Package, PACKAGE_HEAD_LEN};
+
+        let bytes = (0..2048u32).map(|i| i as u8).collect::<Vec<_>>();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), &bytes).unwrap();
+
+        for package in [Package::Bytes(&bytes), Package::File(file.path())] {
+            assert_eq!(package.head().unwrap(), &bytes[..PACKAGE_HEAD_LEN as usize]);
+            let mut copy = Vec::new();
+            package.copy_to(&mut copy).unwrap();
+            assert_eq!(copy, bytes);
+        }
+
+        Package::Bytes(&bytes).remove();
+        assert!(file.path().exists());
+        Package::File(file.path()).remove();
+        assert!(!file.path().exists());
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn it_keeps_installer_for_install_on_exit() {
+        use super::WindowsUpdaterType;
+
+        let temp = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+        let path = temp.to_path_buf();
+        WindowsUpdaterType::nsis(path.clone(), Some(temp))
+            .keep()
+            .unwrap();
+        assert!(path.exists());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn it_resumes_interrupted_download() {
+        let package = package();
+        let etag = "\"v1\"".to_string();
+        let (url, server) = serve(vec![response(
+            "206 Partial Content",
+            &[
+                (
+                    "Content-Range",
+                    format!("bytes 1000-4095/{}", package.len()),
+                ),
+                ("ETag", etag.clone()),
+            ],
+            &package[1000..],
+        )]);
+        let dir = tempfile::tempdir().unwrap();
+        let update = update(url, dir.path());
+        interrupt_download(&update, 1000, Some(&etag));
+
+        // the signature is only valid if the verifier was fed the partial file before the rest
+        let path =
+            tauri::async_runtime::block_on(update.download_to_file(|_, _| {}, || {})).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), package);
+        assert!(!super::PartialDownload::path(&path).exists());
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].contains("range: bytes=1000-\r\n"));
+        assert!(requests[0].contains("if-range: \"v1\"\r\n"));
+    }
+
+    #[test]
+    fn it_restarts_download_when_range_is_ignored() {
+        let package = package();
+        let (url, server) = serve(vec![response("200 OK", &[], &package)]);
+        let dir = tempfile::tempdir().unwrap();
+        let update = update(url, dir.path());
+        interrupt_download(&update, 1000, Some("\"v1\""));
+
+        let mut downloaded = 0;
+        let path = tauri::async_runtime::block_on(
+            update.download_to_file(|len, _| downloaded += len, || {}),
+        )
+        .unwrap();
+        // the whole package replaces the partial file instead of being appended to it
+        assert_eq!(std::fs::read(&path).unwrap(), package);
+        assert_eq!(downloaded, package.len());
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].contains("range: bytes=1000-\r\n"));
+    }
+
+    #[test]
+    fn it_parses_content_range() {
+        use super::parse_content_range;
+
+        assert_eq!(
+            parse_content_range("bytes 100-199/200"),
+            Some((100, Some(200)))
+        );
+        assert_eq!(parse_content_range("bytes 0-99/*"), Some((0, None)));
+        assert_eq!(parse_content_range("bytes */200"), None);
+        assert_eq!(parse_content_range("100-199/200"), None);
+    }
 
     #[test]
     #[cfg(windows)]